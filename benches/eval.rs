@@ -0,0 +1,54 @@
+//! Rows per second through each way of evaluating a table, over the same
+//! synthetic expressions as `truth bench`. `cargo bench`
+
+extern crate test;
+extern crate truth;
+
+use test::Bencher;
+
+use truth::{Assignment, Expr};
+
+// `(v0 & !v1) ^ (v2 & !v3) ^ ...` over `vars` variables, an even number
+fn synthetic(vars: uint) -> Expr {
+    let pairs: Vec<String> = range(0, vars / 2).map(|pair| format!("(v{} & !v{})", 2 * pair, 2 * pair + 1)).collect();
+    Expr::parse(pairs.connect(" ^ ").as_slice()).unwrap()
+}
+
+fn walk(b: &mut Bencher, vars: uint) {
+    let expr = synthetic(vars);
+    let names = expr.get_variables();
+    let rows = 1u << vars;
+    b.bytes = rows as u64;
+    b.iter(|| {
+        for row in range(0, rows) {
+            let values = range(0, vars).map(|bit| (row >> bit) & 1 == 1).collect();
+            test::black_box(expr.eval(&Assignment::new(names.clone(), values)));
+        }
+    });
+}
+
+fn table(b: &mut Bencher, vars: uint, threads: uint) {
+    let expr = synthetic(vars);
+    b.bytes = (1u << vars) as u64;
+    b.iter(|| test::black_box(expr.truth_table_threads(threads)));
+}
+
+// `bytes` counts rows, so the reported MB/s are millions of rows a second
+
+#[bench]
+fn walk_8(b: &mut Bencher) { walk(b, 8) }
+
+#[bench]
+fn walk_12(b: &mut Bencher) { walk(b, 12) }
+
+#[bench]
+fn table_8(b: &mut Bencher) { table(b, 8, 1) }
+
+#[bench]
+fn table_12(b: &mut Bencher) { table(b, 12, 1) }
+
+#[bench]
+fn table_16(b: &mut Bencher) { table(b, 16, 1) }
+
+#[bench]
+fn table_16_threaded(b: &mut Bencher) { table(b, 16, std::os::num_cpus()) }
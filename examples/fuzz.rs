@@ -0,0 +1,68 @@
+//! Feeds random input through the lexer and parser, printing any input
+//! which makes them panic rather than fail with an error.
+//!
+//! `cargo run --example fuzz [iterations]`
+//!
+//! Inputs are mostly characters the lexer knows, so that they get past the
+//! first token, with random bytes mixed in which needn't be UTF-8. The bytes
+//! are lexed from a buffer as they are, then parsed as a string with the
+//! invalid sequences replaced.
+
+extern crate truth;
+
+use std::io::MemReader;
+use std::os;
+use std::rand;
+use std::rand::Rng;
+use std::task;
+
+use truth::{Lexer, Parser, Style};
+
+const ALPHABET: &'static str = "()&*|+!~^?:,-><'01abcTF_ \t\r\n#/∧∨¬⊕→↔⊤⊥";
+
+// Most characters in an input
+const LENGTH: uint = 40;
+
+fn main() {
+    let args = os::args();
+    let iterations = args.as_slice().get(1).and_then(|arg| from_str::<uint>(arg.as_slice())).unwrap_or(100000);
+    let alphabet: Vec<char> = ALPHABET.chars().collect();
+    let mut rng = rand::task_rng();
+
+    for _ in range(0, iterations) {
+        let bytes = input(&mut rng, alphabet.as_slice());
+        let shared = bytes.clone();
+        if task::try(proc() run(shared)).is_err() {
+            println!("Panicked on {}: {}", bytes, String::from_utf8_lossy(bytes.as_slice()));
+            os::set_exit_status(1);
+            return
+        }
+    }
+    println!("No panics in {} inputs", iterations);
+}
+
+fn input<R: Rng>(rng: &mut R, alphabet: &[char]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for _ in range(0, rng.gen_range(0, LENGTH + 1)) {
+        if rng.gen_weighted_bool(8) {
+            bytes.push(rng.gen());
+        } else {
+            bytes.push_all(rng.choose(alphabet).unwrap().to_string().as_bytes());
+        }
+    }
+    bytes
+}
+
+// Everything the input goes through; errors are fine, panics aren't
+fn run(bytes: Vec<u8>) {
+    for _ in Lexer::from_buffer(MemReader::new(bytes.clone())) {}
+
+    let src = String::from_utf8_lossy(bytes.as_slice()).to_string();
+    for &implicit_and in [false, true].iter() {
+        let mut lexer = if implicit_and { Lexer::with_implicit_and(src.clone()) } else { Lexer::new(src.clone()) };
+        match Parser::parse_recovering(&mut lexer) {
+            Ok(expr) => { format!("{}", expr.display(Style::Native)); },
+            Err(errs) => for err in errs.iter() { err.render(src.as_slice(), "<fuzz>"); }
+        }
+    }
+}
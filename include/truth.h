@@ -0,0 +1,41 @@
+/* C interface to truth, a boolean expression parser and evaluator.
+ *
+ * Link against the truth library built by cargo (libtruth.so, .dylib or .a).
+ * Expressions from truth_parse are freed with truth_free and strings from
+ * truth_table_json with truth_free_string. Null is accepted everywhere and
+ * makes a function fail, as does a panic inside the library.
+ */
+
+#ifndef TRUTH_H
+#define TRUTH_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct truth_expr truth_expr;
+
+/* Parses the NUL terminated UTF-8 src, NULL when it doesn't parse. */
+truth_expr *truth_parse(const char *src);
+
+/* The value of expr with the count variables in names given the values in
+ * values, nonzero being true. 1 or 0, or -1 when a variable of the
+ * expression has no value or evaluating it fails. */
+int truth_eval(const truth_expr *expr, const char *const *names, const int *values, size_t count);
+
+/* The truth table of expr as JSON:
+ * {"variables": [...], "rows": [{"inputs": {...}, "output": true}, ...]}
+ * NULL when it has too many variables or building the table fails. */
+char *truth_table_json(const truth_expr *expr);
+
+void truth_free(truth_expr *expr);
+
+void truth_free_string(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
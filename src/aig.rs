@@ -0,0 +1,186 @@
+//! And-inverter graphs.
+//!
+//! Every function is built from two-input ANDs and negated edges. A literal
+//! is a node index shifted left by one with the low bit set when negated,
+//! so node 0 gives the constants: literal 0 is false and literal 1 is true.
+//! Structurally equal ANDs are hashed to the same node.
+
+use std::collections::HashMap;
+
+use dag::{Dag, Node};
+use error::TruthError;
+use expr::{Component, Expr, VarOrExpr};
+use lexer::Type;
+
+/// A possibly negated node of an `Aig`
+pub type AigLit = uint;
+
+pub const FALSE: AigLit = 0;
+pub const TRUE: AigLit = 1;
+
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+pub enum AigNode {
+    Const,
+    /// Index into `Aig::variables`
+    Input(uint),
+    And(AigLit, AigLit)
+}
+
+/// Nodes are stored after their fanins
+pub struct Aig {
+    nodes:  Vec<AigNode>,
+    vars:   Vec<String>,
+    inputs: Vec<AigLit>,
+    strash: HashMap<(AigLit, AigLit), AigLit>
+}
+
+impl Aig {
+    /// A graph with an input for each of `vars`
+    pub fn new(vars: Vec<String>) -> Aig {
+        let mut aig = Aig { nodes: vec!(AigNode::Const), vars: Vec::new(), inputs: Vec::new(), strash: HashMap::new() };
+        aig.add_inputs(vars.as_slice());
+        aig
+    }
+
+    pub fn nodes(&self) -> &[AigNode] {
+        self.nodes.as_slice()
+    }
+
+    pub fn variables(&self) -> &[String] {
+        self.vars.as_slice()
+    }
+
+    /// The number of AND nodes
+    pub fn ands(&self) -> uint {
+        self.nodes.iter().filter(|node| match **node { AigNode::And(..) => true, _ => false }).count()
+    }
+
+    /// The literal of the variable at `idx`
+    pub fn input(&self, idx: uint) -> AigLit {
+        self.inputs[idx]
+    }
+
+    /// Builds `expr`, adding any of its variables not yet in the graph
+    pub fn build(&mut self, expr: &Expr) -> Result<AigLit, TruthError> {
+        let dag = try!(Dag::from_expr(expr, self.vars.as_slice()));
+        let added = dag.variables().slice_from(self.vars.len()).to_vec();
+        self.add_inputs(added.as_slice());
+
+        let mut lits: Vec<AigLit> = Vec::with_capacity(dag.nodes().len());
+        for node in dag.nodes().iter() {
+            let lit = match *node {
+                Node::Var(idx) => self.inputs[idx],
+                Node::Const(val) => if val { TRUE } else { FALSE },
+                Node::Not(a) => self.not(lits[a]),
+                Node::And(a, b) => self.and(lits[a], lits[b]),
+                Node::Or(a, b) => self.or(lits[a], lits[b]),
+                Node::Xor(a, b) => self.xor(lits[a], lits[b]),
+                Node::Ite(c, t, e) => self.ite(lits[c], lits[t], lits[e])
+            };
+            lits.push(lit);
+        }
+        Ok(lits[dag.root()])
+    }
+
+    pub fn not(&self, a: AigLit) -> AigLit {
+        a ^ 1
+    }
+
+    pub fn and(&mut self, a: AigLit, b: AigLit) -> AigLit {
+        if a == FALSE || b == FALSE || a == b ^ 1 { return FALSE }
+        if a == TRUE || a == b { return b }
+        if b == TRUE { return a }
+
+        let key = if a < b { (a, b) } else { (b, a) };
+        match self.strash.get(&key) {
+            Some(&lit) => return lit,
+            None => {}
+        }
+        let lit = self.nodes.len() << 1;
+        self.nodes.push(AigNode::And(key.val0(), key.val1()));
+        self.strash.insert(key, lit);
+        lit
+    }
+
+    pub fn or(&mut self, a: AigLit, b: AigLit) -> AigLit {
+        let and = self.and(a ^ 1, b ^ 1);
+        and ^ 1
+    }
+
+    pub fn xor(&mut self, a: AigLit, b: AigLit) -> AigLit {
+        let left = self.and(a, b ^ 1);
+        let right = self.and(a ^ 1, b);
+        self.or(left, right)
+    }
+
+    pub fn ite(&mut self, c: AigLit, t: AigLit, e: AigLit) -> AigLit {
+        let then = self.and(c, t);
+        let other = self.and(c ^ 1, e);
+        self.or(then, other)
+    }
+
+    /// Evaluates 64 assignments at once, `lanes[i]` holding the values of variable `i`
+    pub fn eval_lanes(&self, lit: AigLit, lanes: &[u64]) -> u64 {
+        let mut vals: Vec<u64> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let val = match *node {
+                AigNode::Const => 0,
+                AigNode::Input(idx) => lanes[idx],
+                AigNode::And(a, b) => lit_value(vals.as_slice(), a) & lit_value(vals.as_slice(), b)
+            };
+            vals.push(val);
+        }
+        lit_value(vals.as_slice(), lit)
+    }
+
+    /// Evaluates a single assignment, `values[i]` being the value of variable `i`
+    pub fn eval(&self, lit: AigLit, values: &[bool]) -> bool {
+        let lanes: Vec<u64> = values.iter().map(|&val| if val { 1 } else { 0 }).collect();
+        self.eval_lanes(lit, lanes.as_slice()) & 1 == 1
+    }
+
+    /// Converts back to an expression of ANDs and negations, shared nodes
+    /// are written out at each use
+    pub fn to_expr(&self, lit: AigLit) -> Expr {
+        let mut components = Vec::new();
+        self.gather(lit, &mut components);
+        Expr::join(components, Type::And)
+    }
+
+    // Collects the conjuncts of `lit`, flattening nested ANDs
+    fn gather(&self, lit: AigLit, out: &mut Vec<Component>) {
+        let negated = lit & 1 == 1;
+        let value = match self.nodes[lit >> 1] {
+            AigNode::Const => VarOrExpr::Const(negated),
+            AigNode::Input(idx) => VarOrExpr::Var(self.vars[idx].clone()),
+            AigNode::And(a, b) => {
+                if !negated {
+                    self.gather(a, out);
+                    self.gather(b, out);
+                    return
+                }
+                let mut inner = Vec::new();
+                self.gather(a, &mut inner);
+                self.gather(b, &mut inner);
+                VarOrExpr::Expr(Expr::join(inner, Type::And))
+            }
+        };
+        let negated = match value { VarOrExpr::Const(_) => false, _ => negated };
+        out.push(Component { value: value, negated: negated });
+    }
+
+    fn add_inputs(&mut self, vars: &[String]) {
+        for var in vars.iter() {
+            self.inputs.push(self.nodes.len() << 1);
+            self.nodes.push(AigNode::Input(self.vars.len()));
+            self.vars.push(var.clone());
+        }
+    }
+}
+
+fn lit_value(vals: &[u64], lit: AigLit) -> u64 {
+    let val = vals[lit >> 1];
+    if lit & 1 == 1 { !val } else { val }
+}
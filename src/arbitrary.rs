@@ -0,0 +1,75 @@
+//! Random well-formed expressions, for property tests of the parser, the
+//! printers and the evaluators.
+//!
+//! `Expr` implements `Rand` over four variables; a `Generator` picks the
+//! variables and how deep the expressions go. Expressions have the shapes
+//! the parser builds: each nested expression uses a single operator,
+//! implications have two operands and a `?:` sits in a group of its own.
+//! So printing one in the native style and parsing it back gives the same
+//! tree, which prints the same.
+
+use std::rand::{Rand, Rng};
+
+use expr::{Component, Expr, VarOrExpr};
+use lexer::Type;
+
+// Operators for the nested expressions, all but implication associative
+const OPS: [Type, ..6] = [Type::And, Type::Or, Type::Xor, Type::Xnor, Type::Implies, Type::Iff];
+
+pub struct Generator {
+    vars:  Vec<String>,
+    depth: uint,
+    // Most operands of a nested expression
+    width: uint
+}
+
+impl Generator {
+    /// Expressions over `vars` nested at most `depth` deep
+    pub fn new(vars: Vec<String>, depth: uint) -> Generator {
+        assert!(!vars.is_empty(), "no variables to generate expressions over");
+        Generator { vars: vars, depth: depth, width: 3 }
+    }
+
+    /// At most `width` operands per nested expression, at least 1
+    pub fn width(mut self, width: uint) -> Generator {
+        self.width = if width == 0 { 1 } else { width };
+        self
+    }
+
+    pub fn generate<R: Rng>(&self, rng: &mut R) -> Expr {
+        self.expr(rng, self.depth)
+    }
+
+    fn expr<R: Rng>(&self, rng: &mut R, depth: uint) -> Expr {
+        let op = rng.choose(OPS.as_slice()).unwrap().clone();
+        let most = if op == Type::Implies { 2 } else { self.width };
+        let count = if depth == 0 { 1 } else { rng.gen_range(1, most + 1) };
+        let components = range(0, count).map(|_| self.component(rng, depth)).collect();
+        Expr::join(components, op)
+    }
+
+    fn component<R: Rng>(&self, rng: &mut R, depth: uint) -> Component {
+        // Nesting gets less likely further down and stops at the bottom
+        let nest = rng.gen_range(0, self.depth + 1) < depth;
+        let value = if nest && rng.gen_weighted_bool(4) {
+            let ite = VarOrExpr::Ite(box self.expr(rng, depth - 1), box self.expr(rng, depth - 1),
+                                     box self.expr(rng, depth - 1));
+            VarOrExpr::Expr(Expr { components: vec!(Component { value: ite, negated: false }), ops: vec!() })
+        } else if nest {
+            VarOrExpr::Expr(self.expr(rng, depth - 1))
+        } else if rng.gen_weighted_bool(8) {
+            VarOrExpr::Const(rng.gen())
+        } else {
+            VarOrExpr::Var(rng.choose(self.vars.as_slice()).unwrap().clone())
+        };
+        Component { value: value, negated: rng.gen() }
+    }
+}
+
+/// Expressions over `a` to `d` nested at most 3 deep
+impl Rand for Expr {
+    fn rand<R: Rng>(rng: &mut R) -> Expr {
+        let vars = vec!("a".to_string(), "b".to_string(), "c".to_string(), "d".to_string());
+        Generator::new(vars, 3).generate(rng)
+    }
+}
@@ -0,0 +1,475 @@
+//! Reduced ordered binary decision diagrams.
+//!
+//! A `Bdd` owns every node built in it, so functions are shared between the
+//! diagrams of different expressions and equal functions get the same
+//! `BddRef`. Variables are tested in the order of `Bdd::variables`.
+
+use std::collections::HashMap;
+use std::num::Int;
+use std::rand::Rng;
+use std::uint;
+
+use dag::{Dag, Node};
+use dot;
+use env::Assignment;
+use error::TruthError;
+use expr::Expr;
+
+/// A function in a `Bdd`, equal functions have equal refs
+pub type BddRef = uint;
+
+pub const FALSE: BddRef = 0;
+pub const TRUE: BddRef = 1;
+
+/// Binary operations for `Bdd::apply`
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+#[deriving(Eq)]
+#[deriving(Hash)]
+pub enum BddOp {
+    And,
+    Or,
+    Xor
+}
+
+impl BddOp {
+    fn eval(&self, a: bool, b: bool) -> bool {
+        match *self {
+            BddOp::And => a & b,
+            BddOp::Or => a | b,
+            BddOp::Xor => a ^ b
+        }
+    }
+}
+
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+#[deriving(Eq)]
+#[deriving(Hash)]
+struct BddNode {
+    // Terminals have `uint::MAX` so they sort below every variable
+    var:  uint,
+    low:  BddRef,
+    high: BddRef
+}
+
+pub struct Bdd {
+    nodes:  Vec<BddNode>,
+    vars:   Vec<String>,
+    unique: HashMap<BddNode, BddRef>,
+    cache:  HashMap<(BddOp, BddRef, BddRef), BddRef>
+}
+
+impl Bdd {
+    /// An empty diagram testing `vars` in the given order
+    pub fn new(vars: Vec<String>) -> Bdd {
+        let terminal = BddNode { var: uint::MAX, low: FALSE, high: FALSE };
+        Bdd {
+            nodes: vec!(terminal.clone(), terminal),
+            vars: vars,
+            unique: HashMap::new(),
+            cache: HashMap::new()
+        }
+    }
+
+    pub fn variables(&self) -> &[String] {
+        self.vars.as_slice()
+    }
+
+    /// The number of nodes, including both terminals
+    pub fn len(&self) -> uint {
+        self.nodes.len()
+    }
+
+    pub fn constant(&self, val: bool) -> BddRef {
+        if val { TRUE } else { FALSE }
+    }
+
+    pub fn is_const(&self, f: BddRef) -> bool {
+        f == FALSE || f == TRUE
+    }
+
+    /// The variable index tested by `f` and its false and true branches,
+    /// None for the terminals
+    pub fn node(&self, f: BddRef) -> Option<(uint, BddRef, BddRef)> {
+        if self.is_const(f) { return None }
+        let node = &self.nodes[f];
+        Some((node.var, node.low, node.high))
+    }
+
+    /// The nodes reachable from `f` as a Graphviz graph, see `dot::bdd_to_dot`
+    pub fn to_dot(&self, f: BddRef) -> String {
+        dot::bdd_to_dot(self, f)
+    }
+
+    /// The function which is just the variable at `idx`
+    pub fn var(&mut self, idx: uint) -> BddRef {
+        assert!(idx < self.vars.len(), "variable {} out of range", idx);
+        self.mk(idx, FALSE, TRUE)
+    }
+
+    /// Builds `expr`, adding any of its variables not yet in the diagram
+    /// after the existing ones
+    pub fn build(&mut self, expr: &Expr) -> Result<BddRef, TruthError> {
+        let dag = try!(Dag::from_expr(expr, self.vars.as_slice()));
+        self.vars = dag.variables().to_vec();
+
+        // Dag nodes come after their operands so they can be built in order
+        let mut refs: Vec<BddRef> = Vec::with_capacity(dag.nodes().len());
+        for node in dag.nodes().iter() {
+            let f = match *node {
+                Node::Var(idx) => self.var(idx),
+                Node::Const(val) => self.constant(val),
+                Node::Not(a) => self.not(refs[a]),
+                Node::And(a, b) => self.apply(BddOp::And, refs[a], refs[b]),
+                Node::Or(a, b) => self.apply(BddOp::Or, refs[a], refs[b]),
+                Node::Xor(a, b) => self.apply(BddOp::Xor, refs[a], refs[b]),
+                Node::Ite(c, t, e) => self.ite(refs[c], refs[t], refs[e])
+            };
+            refs.push(f);
+        }
+        Ok(refs[dag.root()])
+    }
+
+    pub fn not(&mut self, f: BddRef) -> BddRef {
+        self.apply(BddOp::Xor, f, TRUE)
+    }
+
+    pub fn and(&mut self, f: BddRef, g: BddRef) -> BddRef {
+        self.apply(BddOp::And, f, g)
+    }
+
+    pub fn or(&mut self, f: BddRef, g: BddRef) -> BddRef {
+        self.apply(BddOp::Or, f, g)
+    }
+
+    pub fn xor(&mut self, f: BddRef, g: BddRef) -> BddRef {
+        self.apply(BddOp::Xor, f, g)
+    }
+
+    pub fn ite(&mut self, c: BddRef, t: BddRef, e: BddRef) -> BddRef {
+        let then = self.and(c, t);
+        let not = self.not(c);
+        let other = self.and(not, e);
+        self.or(then, other)
+    }
+
+    pub fn apply(&mut self, op: BddOp, f: BddRef, g: BddRef) -> BddRef {
+        if self.is_const(f) && self.is_const(g) {
+            return self.constant(op.eval(f == TRUE, g == TRUE))
+        }
+        match op {
+            BddOp::And => {
+                if f == FALSE || g == FALSE { return FALSE }
+                if f == TRUE || f == g { return g }
+                if g == TRUE { return f }
+            },
+            BddOp::Or => {
+                if f == TRUE || g == TRUE { return TRUE }
+                if f == FALSE || f == g { return g }
+                if g == FALSE { return f }
+            },
+            BddOp::Xor => {
+                if f == g { return FALSE }
+                if f == FALSE { return g }
+                if g == FALSE { return f }
+            }
+        }
+
+        // Every operation is commutative
+        let key = if f < g { (op, f, g) } else { (op, g, f) };
+        match self.cache.get(&key) {
+            Some(&result) => return result,
+            None => {}
+        }
+
+        let var = if self.nodes[f].var < self.nodes[g].var { self.nodes[f].var } else { self.nodes[g].var };
+        let (f_low, f_high) = self.cofactors(f, var);
+        let (g_low, g_high) = self.cofactors(g, var);
+        let low = self.apply(op, f_low, g_low);
+        let high = self.apply(op, f_high, g_high);
+        let result = self.mk(var, low, high);
+
+        self.cache.insert(key, result);
+        result
+    }
+
+    /// `f` with the variable at `var` fixed to `val`
+    pub fn restrict(&mut self, f: BddRef, var: uint, val: bool) -> BddRef {
+        let mut done = HashMap::new();
+        self.restrict_rec(f, var, val, &mut done)
+    }
+
+    /// The value of `f` when variable `i` is `values[i]`
+    pub fn eval(&self, f: BddRef, values: &[bool]) -> bool {
+        let mut f = f;
+        while !self.is_const(f) {
+            let node = &self.nodes[f];
+            f = if values[node.var] { node.high } else { node.low };
+        }
+        f == TRUE
+    }
+
+    /// The paths from `f` to the true terminal, each a value for the variables
+    /// it tests and None for the rest
+    pub fn cubes(&self, f: BddRef) -> Cubes {
+        Cubes { bdd: self, stack: start_paths(self, f) }
+    }
+
+    /// `count` assignments for which `f` is true, each equally likely to be any
+    /// of them. Empty when `f` is never true
+    pub fn sample<R: Rng>(&self, f: BddRef, count: uint, rng: &mut R) -> Vec<Assignment> {
+        if f == FALSE { return Vec::new() }
+        let mut density = HashMap::new();
+        self.density(f, &mut density);
+
+        range(0, count).map(|_| {
+            // Variables the path skips over are free to take either value
+            let mut values: Vec<bool> = range(0, self.vars.len()).map(|_| rng.gen()).collect();
+            let mut node = f;
+            while !self.is_const(node) {
+                let (var, low, high) = self.node(node).unwrap();
+                // Each branch is taken in proportion to the models below it
+                let low_density = *density.get(&low).unwrap();
+                let high_density = *density.get(&high).unwrap();
+                let take_high = rng.gen::<f64>() * (low_density + high_density) < high_density;
+                values[var] = take_high;
+                node = if take_high { high } else { low };
+            }
+            Assignment::new(self.vars.clone(), values)
+        }).collect()
+    }
+
+    // The fraction of assignments for which `f` is true, which is the mean of
+    // its branches since skipped variables don't change it
+    fn density(&self, f: BddRef, done: &mut HashMap<BddRef, f64>) -> f64 {
+        let density = match self.node(f) {
+            Some((_, low, high)) => {
+                match done.get(&f) {
+                    Some(&density) => return density,
+                    None => {}
+                }
+                (self.density(low, done) + self.density(high, done)) / 2.0
+            },
+            None => if f == TRUE { 1.0 } else { 0.0 }
+        };
+        done.insert(f, density);
+        density
+    }
+
+    /// The probability that `f` is true when each variable `i` is
+    /// independently true with probability `probs[i]`
+    pub fn probability(&self, f: BddRef, probs: &[f64]) -> f64 {
+        let weights: Vec<(f64, f64)> = probs.iter().map(|&prob| (1.0 - prob, prob)).collect();
+        self.weighted_count(f, weights.as_slice())
+    }
+
+    /// The sum over the assignments of every variable for which `f` is true
+    /// of the product of the weights of their literals, `weights[i]` being
+    /// the weights of variable `i` false and true
+    pub fn weighted_count(&self, f: BddRef, weights: &[(f64, f64)]) -> f64 {
+        assert!(weights.len() == self.vars.len(), "{} weights for {} variables", weights.len(), self.vars.len());
+        let mut done = HashMap::new();
+        self.weighted_rec(f, weights, &mut done) * skipped(weights, 0, self.level(f))
+    }
+
+    // Weighted models of `f` over the variables from its level down
+    fn weighted_rec(&self, f: BddRef, weights: &[(f64, f64)], done: &mut HashMap<BddRef, f64>) -> f64 {
+        if f == FALSE { return 0.0 }
+        if f == TRUE { return 1.0 }
+        match done.get(&f) {
+            Some(&count) => return count,
+            None => {}
+        }
+
+        let node = self.nodes[f].clone();
+        // Variables skipped between a node and its child may take either value
+        let low = self.weighted_rec(node.low, weights, done) * skipped(weights, node.var + 1, self.level(node.low));
+        let high = self.weighted_rec(node.high, weights, done) * skipped(weights, node.var + 1, self.level(node.high));
+        let (low_weight, high_weight) = weights[node.var];
+        let count = low_weight * low + high_weight * high;
+        done.insert(f, count);
+        count
+    }
+
+    /// The number of assignments of every variable for which `f` is true,
+    /// None if it doesn't fit in a u64
+    pub fn count(&self, f: BddRef) -> Option<u64> {
+        let mut done = HashMap::new();
+        self.count_rec(f, &mut done).and_then(|count| scaled(count, self.level(f)))
+    }
+
+    // Variables tested at or below the level of `f`, terminals are below all of them
+    fn level(&self, f: BddRef) -> uint {
+        if self.is_const(f) { self.vars.len() } else { self.nodes[f].var }
+    }
+
+    // Models of `f` over the variables from its level down
+    fn count_rec(&self, f: BddRef, done: &mut HashMap<BddRef, u64>) -> Option<u64> {
+        if f == FALSE { return Some(0) }
+        if f == TRUE { return Some(1) }
+        match done.get(&f) {
+            Some(&count) => return Some(count),
+            None => {}
+        }
+
+        let node = self.nodes[f].clone();
+        // Variables skipped between a node and its child may take either value
+        let low = self.count_rec(node.low, done)
+            .and_then(|count| scaled(count, self.level(node.low) - node.var - 1));
+        let high = self.count_rec(node.high, done)
+            .and_then(|count| scaled(count, self.level(node.high) - node.var - 1));
+        let count = match (low, high) {
+            (Some(low), Some(high)) => low.checked_add(high),
+            _ => None
+        };
+
+        match count {
+            Some(count) => { done.insert(f, count); },
+            None => {}
+        }
+        count
+    }
+
+    fn restrict_rec(&mut self, f: BddRef, var: uint, val: bool,
+                    done: &mut HashMap<BddRef, BddRef>) -> BddRef {
+        let node = self.nodes[f].clone();
+        // Nodes below `var`, including terminals, don't depend on it
+        if node.var > var { return f }
+        if node.var == var { return if val { node.high } else { node.low } }
+        match done.get(&f) {
+            Some(&result) => return result,
+            None => {}
+        }
+
+        let low = self.restrict_rec(node.low, var, val, done);
+        let high = self.restrict_rec(node.high, var, val, done);
+        let result = self.mk(node.var, low, high);
+        done.insert(f, result);
+        result
+    }
+
+    // The branches of `f` for `var`, which is at or above the level of `f`
+    fn cofactors(&self, f: BddRef, var: uint) -> (BddRef, BddRef) {
+        let node = &self.nodes[f];
+        if node.var == var { (node.low, node.high) } else { (f, f) }
+    }
+
+    // The node testing `var`, reusing an equal one and skipping redundant tests
+    fn mk(&mut self, var: uint, low: BddRef, high: BddRef) -> BddRef {
+        if low == high { return low }
+        let node = BddNode { var: var, low: low, high: high };
+        match self.unique.get(&node) {
+            Some(&f) => return f,
+            None => {}
+        }
+        let f = self.nodes.len();
+        self.nodes.push(node.clone());
+        self.unique.insert(node, f);
+        f
+    }
+}
+
+// The weight of the variables from `from` up to `to` taking either value
+fn skipped(weights: &[(f64, f64)], from: uint, to: uint) -> f64 {
+    weights.slice(from, to).iter().fold(1.0, |product, &(low, high)| product * (low + high))
+}
+
+// `count * 2^exp`, None on overflow
+fn scaled(count: u64, exp: uint) -> Option<u64> {
+    if exp >= 64 { return if count == 0 { Some(0) } else { None } }
+    count.checked_mul(1u64 << exp)
+}
+
+/// Iterator over the cubes of a function, see `Bdd::cubes`
+pub struct Cubes<'a> {
+    bdd:   &'a Bdd,
+    stack: Vec<(BddRef, Vec<Option<bool>>)>
+}
+
+impl<'a> Iterator<Vec<Option<bool>>> for Cubes<'a> {
+    fn next(&mut self) -> Option<Vec<Option<bool>>> {
+        next_cube(self.bdd, &mut self.stack)
+    }
+}
+
+/// Iterator over every assignment for which a function is true, in the
+/// order of the rows of its truth table within each cube
+pub struct Models {
+    bdd:     Bdd,
+    stack:   Vec<(BddRef, Vec<Option<bool>>)>,
+    // The cube being expanded and the values of its free variables
+    current: Option<(Vec<Option<bool>>, Vec<bool>)>
+}
+
+impl Models {
+    pub fn new(bdd: Bdd, f: BddRef) -> Models {
+        let stack = start_paths(&bdd, f);
+        Models { bdd: bdd, stack: stack, current: None }
+    }
+}
+
+impl Iterator<Assignment> for Models {
+    fn next(&mut self) -> Option<Assignment> {
+        if self.current.is_none() {
+            match next_cube(&self.bdd, &mut self.stack) {
+                Some(cube) => {
+                    let free = cube.iter().filter(|val| val.is_none()).count();
+                    self.current = Some((cube, Vec::from_elem(free, false)));
+                },
+                None => return None
+            }
+        }
+
+        let (values, done) = match self.current {
+            Some((ref cube, ref mut free)) => {
+                let values: Vec<bool> = {
+                    let mut next_free = free.iter();
+                    cube.iter().map(|val| match *val {
+                        Some(val) => val,
+                        None => *next_free.next().unwrap()
+                    }).collect()
+                };
+
+                // Counts up in binary, the cube is done once every free value wraps to false
+                let mut done = true;
+                for val in free.iter_mut().rev() {
+                    *val = !*val;
+                    if *val { done = false; break }
+                }
+                (values, done)
+            },
+            None => unreachable!()
+        };
+        if done { self.current = None }
+
+        Some(Assignment::new(self.bdd.variables().to_vec(), values))
+    }
+}
+
+fn start_paths(bdd: &Bdd, f: BddRef) -> Vec<(BddRef, Vec<Option<bool>>)> {
+    if f == FALSE { Vec::new() } else { vec!((f, Vec::from_elem(bdd.variables().len(), None))) }
+}
+
+// Depth first search for the next path to the true terminal, taking the
+// false branch of each node first
+fn next_cube(bdd: &Bdd, stack: &mut Vec<(BddRef, Vec<Option<bool>>)>) -> Option<Vec<Option<bool>>> {
+    loop {
+        let (f, cube) = match stack.pop() {
+            Some(path) => path,
+            None => return None
+        };
+        match bdd.node(f) {
+            Some((var, low, high)) => {
+                let mut high_cube = cube.clone();
+                high_cube[var] = Some(true);
+                let mut low_cube = cube;
+                low_cube[var] = Some(false);
+                if high != FALSE { stack.push((high, high_cube)) }
+                if low != FALSE { stack.push((low, low_cube)) }
+            },
+            None => if f == TRUE { return Some(cube) }
+        }
+    }
+}
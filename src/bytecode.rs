@@ -0,0 +1,207 @@
+//! Flat stack machine code for an expression.
+//!
+//! A `Program` is evaluated by a single loop over its instructions, without
+//! recursion or variable lookups by name. Nodes shared in the `Dag` are
+//! computed once and kept in a slot for their later uses.
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+
+use dag::{Dag, Node, NodeId};
+use error::TruthError;
+use expr::Expr;
+
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+pub enum Instr {
+    /// Pushes the lanes of the variable at this index
+    Load(uint),
+    Const(bool),
+    Not,
+    And,
+    Or,
+    Xor,
+    /// Pops the else, then and condition values, in that order
+    Ite,
+    /// Copies the top of the stack into a slot
+    Store(uint),
+    /// Pushes the value of a slot
+    Fetch(uint)
+}
+
+pub struct Program {
+    code:  Vec<Instr>,
+    vars:  Vec<String>,
+    slots: uint,
+    depth: uint
+}
+
+impl Program {
+    /// Compiles `expr`, `vars` gives the index of each variable as in `Dag::from_expr`
+    pub fn compile(expr: &Expr, vars: &[String]) -> Result<Program, TruthError> {
+        Ok(Program::from_dag(&try!(Dag::from_expr(expr, vars))))
+    }
+
+    pub fn from_dag(dag: &Dag) -> Program {
+        // Nodes used more than once get a slot so they're only computed once
+        let mut uses = Vec::from_elem(dag.nodes().len(), 0u);
+        uses[dag.root()] += 1;
+        for node in dag.nodes().iter() {
+            match *node {
+                Node::Var(_) | Node::Const(_) => {},
+                Node::Not(a) => uses[a] += 1,
+                Node::And(a, b) | Node::Or(a, b) | Node::Xor(a, b) => {
+                    uses[a] += 1;
+                    uses[b] += 1;
+                },
+                Node::Ite(c, t, e) => {
+                    uses[c] += 1;
+                    uses[t] += 1;
+                    uses[e] += 1;
+                }
+            }
+        }
+
+        let mut compiler = Compiler {
+            dag: dag,
+            uses: uses,
+            slots: Vec::from_elem(dag.nodes().len(), None),
+            next_slot: 0,
+            code: Vec::new(),
+            height: 0,
+            depth: 0
+        };
+        compiler.emit(dag.root());
+
+        Program {
+            code: compiler.code,
+            vars: dag.variables().to_vec(),
+            slots: compiler.next_slot,
+            depth: compiler.depth
+        }
+    }
+
+    pub fn code(&self) -> &[Instr] {
+        self.code.as_slice()
+    }
+
+    pub fn variables(&self) -> &[String] {
+        self.vars.as_slice()
+    }
+
+    /// Evaluates 64 assignments at once, `lanes[i]` holding the values of variable `i`
+    pub fn eval_lanes(&self, lanes: &[u64]) -> u64 {
+        let mut stack = Vec::from_elem(self.depth, 0u64);
+        let mut slots = Vec::from_elem(self.slots, 0u64);
+        let mut sp = 0u;
+
+        for instr in self.code.iter() {
+            match *instr {
+                Instr::Load(idx) => { stack[sp] = lanes[idx]; sp += 1 },
+                Instr::Const(val) => { stack[sp] = if val { !0 } else { 0 }; sp += 1 },
+                Instr::Not => { let a = stack[sp - 1]; stack[sp - 1] = !a },
+                Instr::And => { sp -= 1; let b = stack[sp]; stack[sp - 1] &= b },
+                Instr::Or => { sp -= 1; let b = stack[sp]; stack[sp - 1] |= b },
+                Instr::Xor => { sp -= 1; let b = stack[sp]; stack[sp - 1] ^= b },
+                Instr::Ite => {
+                    sp -= 2;
+                    let (c, t, e) = (stack[sp - 1], stack[sp], stack[sp + 1]);
+                    stack[sp - 1] = (c & t) | (!c & e);
+                },
+                Instr::Store(slot) => slots[slot] = stack[sp - 1],
+                Instr::Fetch(slot) => { stack[sp] = slots[slot]; sp += 1 }
+            }
+        }
+        stack[0]
+    }
+
+    /// Evaluates a single assignment, `values[i]` being the value of variable `i`
+    pub fn eval(&self, values: &[bool]) -> bool {
+        let lanes: Vec<u64> = values.iter().map(|&val| if val { 1 } else { 0 }).collect();
+        self.eval_lanes(lanes.as_slice()) & 1 == 1
+    }
+}
+
+struct Compiler<'a> {
+    dag:       &'a Dag,
+    uses:      Vec<uint>,
+    slots:     Vec<Option<uint>>,
+    next_slot: uint,
+    code:      Vec<Instr>,
+    // Stack height after the code so far, and the most it has been
+    height:    uint,
+    depth:     uint
+}
+
+impl<'a> Compiler<'a> {
+    fn push(&mut self, instr: Instr, pops: uint, pushes: uint) {
+        self.height = self.height - pops + pushes;
+        if self.height > self.depth { self.depth = self.height }
+        self.code.push(instr);
+    }
+
+    // Emits the code leaving the value of `root` on top of the stack. The
+    // nodes are walked with a stack of their own rather than by recursion,
+    // as a long chain of operators makes a DAG as deep as it is long
+    fn emit(&mut self, root: NodeId) {
+        let mut work = vec!(Work::Emit(root));
+        loop {
+            match work.pop() {
+                Some(Work::Emit(id)) => {
+                    match self.slots[id] {
+                        Some(slot) => {
+                            self.push(Instr::Fetch(slot), 0, 1);
+                            continue
+                        },
+                        None => {}
+                    }
+                    // Operands are pushed last first, so they are emitted in order
+                    match self.dag.nodes()[id] {
+                        Node::Var(idx) => self.push(Instr::Load(idx), 0, 1),
+                        Node::Const(val) => self.push(Instr::Const(val), 0, 1),
+                        Node::Not(a) => work.push_all(&[Work::Finish(id), Work::Emit(a)]),
+                        Node::And(a, b) | Node::Or(a, b) | Node::Xor(a, b) => {
+                            work.push_all(&[Work::Finish(id), Work::Emit(b), Work::Emit(a)])
+                        },
+                        Node::Ite(c, t, e) => {
+                            work.push_all(&[Work::Finish(id), Work::Emit(e), Work::Emit(t), Work::Emit(c)])
+                        }
+                    }
+                },
+                Some(Work::Finish(id)) => self.finish(id),
+                None => return
+            }
+        }
+    }
+
+    // Emits the operation of `id` once its operands are on the stack, then
+    // keeps the value in a slot when it is used again
+    fn finish(&mut self, id: NodeId) {
+        match self.dag.nodes()[id] {
+            Node::Var(_) | Node::Const(_) => return,
+            Node::Not(_) => self.push(Instr::Not, 1, 1),
+            Node::And(..) => self.push(Instr::And, 2, 1),
+            Node::Or(..) => self.push(Instr::Or, 2, 1),
+            Node::Xor(..) => self.push(Instr::Xor, 2, 1),
+            Node::Ite(..) => self.push(Instr::Ite, 3, 1)
+        }
+
+        // Variables and constants never get here, they are as cheap to load
+        // again as a slot
+        if self.uses[id] > 1 {
+            let slot = self.next_slot;
+            self.next_slot += 1;
+            self.slots[id] = Some(slot);
+            self.push(Instr::Store(slot), 0, 0);
+        }
+    }
+}
+
+// A step of `Compiler::emit`: a node to emit, or one whose operands have
+// been emitted
+#[deriving(Clone)]
+enum Work {
+    Emit(NodeId),
+    Finish(NodeId)
+}
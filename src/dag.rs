@@ -0,0 +1,172 @@
+//! Hash-consed form of an expression.
+//!
+//! Structurally equal sub-expressions become a single node, so a sub-expression
+//! that appears several times is evaluated once per row.
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use std::collections::BTreeMap;
+
+use error::{Span, TruthError};
+use expr::{undefined_function, Component, Expr, VarOrExpr};
+use lexer::Type;
+use symbol::{Symbol, SymbolTable};
+
+/// Index of a node in its `Dag`
+pub type NodeId = uint;
+
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+#[deriving(Eq)]
+#[deriving(PartialOrd)]
+#[deriving(Ord)]
+#[deriving(Hash)]
+pub enum Node {
+    /// The variable's symbol, its index into `Dag::variables`
+    Var(Symbol),
+    Const(bool),
+    Not(NodeId),
+    And(NodeId, NodeId),
+    Or(NodeId, NodeId),
+    Xor(NodeId, NodeId),
+    Ite(NodeId, NodeId, NodeId)
+}
+
+/// Nodes are stored after all of their operands, so evaluating them in
+/// order computes every operand before it is needed
+pub struct Dag {
+    nodes:  Vec<Node>,
+    vars:   SymbolTable,
+    root:   NodeId,
+    lookup: BTreeMap<Node, NodeId>
+}
+
+impl Dag {
+    /// Builds the DAG of `expr`, `vars` gives the index of each variable.
+    /// Variables of `expr` missing from `vars` are added after them
+    pub fn from_expr(expr: &Expr, vars: &[String]) -> Result<Dag, TruthError> {
+        let mut dag = Dag { nodes: Vec::new(), vars: SymbolTable::from_names(vars), root: 0, lookup: BTreeMap::new() };
+        dag.root = try!(dag.add_expr(expr));
+        Ok(dag)
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        self.nodes.as_slice()
+    }
+
+    pub fn variables(&self) -> &[String] {
+        self.vars.names()
+    }
+
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.vars
+    }
+
+    pub fn root(&self) -> NodeId {
+        self.root
+    }
+
+    /// Evaluates 64 assignments at once, `lanes[i]` holding the values of
+    /// variable `i` as in `Expr::eval_lanes`
+    pub fn eval_lanes(&self, lanes: &[u64]) -> u64 {
+        let mut vals: Vec<u64> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let val = match *node {
+                Node::Var(idx) => lanes[idx],
+                Node::Const(val) => if val { !0 } else { 0 },
+                Node::Not(a) => !vals[a],
+                Node::And(a, b) => vals[a] & vals[b],
+                Node::Or(a, b) => vals[a] | vals[b],
+                Node::Xor(a, b) => vals[a] ^ vals[b],
+                Node::Ite(c, t, e) => (vals[c] & vals[t]) | (!vals[c] & vals[e])
+            };
+            vals.push(val);
+        }
+        vals[self.root]
+    }
+
+    /// Evaluates a single assignment, `values[i]` being the value of variable `i`
+    pub fn eval(&self, values: &[bool]) -> bool {
+        let lanes: Vec<u64> = values.iter().map(|&val| if val { 1 } else { 0 }).collect();
+        self.eval_lanes(lanes.as_slice()) & 1 == 1
+    }
+
+    // Returns the existing node when an equal one was already added
+    fn add(&mut self, node: Node) -> NodeId {
+        // Operands of commutative nodes are ordered so a & b and b & a are shared
+        let node = match node {
+            Node::And(a, b) if a > b => Node::And(b, a),
+            Node::Or(a, b) if a > b => Node::Or(b, a),
+            Node::Xor(a, b) if a > b => Node::Xor(b, a),
+            Node::Not(a) => match self.nodes[a] {
+                Node::Not(inner) => return inner,
+                _ => Node::Not(a)
+            },
+            other => other
+        };
+
+        match self.lookup.get(&node) {
+            Some(&id) => return id,
+            None => {}
+        }
+        let id = self.nodes.len();
+        self.nodes.push(node.clone());
+        self.lookup.insert(node, id);
+        id
+    }
+
+    fn add_expr(&mut self, expr: &Expr) -> Result<NodeId, TruthError> {
+        // An empty expression is false, as in `Expr::eval`
+        if expr.components.len() == 0 {
+            return Ok(self.add(Node::Const(false)))
+        }
+        let mut id = try!(self.add_component(&expr.components[0]));
+
+        for idx in range(1u, expr.components.len()) {
+            let rhs = try!(self.add_component(&expr.components[idx]));
+            let node = match expr.ops[idx - 1].token_type {
+                Type::And => Node::And(id, rhs),
+                Type::Or => Node::Or(id, rhs),
+                Type::Xor => Node::Xor(id, rhs),
+                Type::Xnor | Type::Iff => {
+                    let xor = self.add(Node::Xor(id, rhs));
+                    Node::Not(xor)
+                },
+                Type::Implies => {
+                    let not = self.add(Node::Not(id));
+                    Node::Or(not, rhs)
+                },
+                ref other => {
+                    return Err(TruthError::Eval(
+                        format!("Unexpected operation: {}", other),
+                        Some(Span::from_token(&expr.ops[idx - 1])),
+                    ))
+                }
+            };
+            id = self.add(node);
+        }
+
+        Ok(id)
+    }
+
+    fn add_component(&mut self, component: &Component) -> Result<NodeId, TruthError> {
+        let id = match component.value {
+            VarOrExpr::Var(ref name) => {
+                let symbol = self.vars.intern(name.as_slice());
+                self.add(Node::Var(symbol))
+            },
+            VarOrExpr::Const(val) => self.add(Node::Const(val)),
+            VarOrExpr::Expr(ref expr) => try!(self.add_expr(expr)),
+            VarOrExpr::Ite(ref cond, ref then, ref other) => {
+                let cond = try!(self.add_expr(&**cond));
+                let then = try!(self.add_expr(&**then));
+                let other = try!(self.add_expr(&**other));
+                self.add(Node::Ite(cond, then, other))
+            },
+            VarOrExpr::Call(ref name, _) => return Err(undefined_function(name.as_slice()))
+        };
+
+        Ok(if component.negated { self.add(Node::Not(id)) } else { id })
+    }
+}
@@ -0,0 +1,328 @@
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use std::fmt;
+
+use expr::{Component, Expr, VarOrExpr};
+use lexer::Type;
+
+/// Notation used when printing an expression
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+pub enum Style {
+    /// The notation read by the lexer: `& | ! ^ !^ -> <->`
+    Native,
+    /// `&& || !`, with `==` for equivalence
+    C,
+    /// `∧ ∨ ¬ ⊕ → ↔`
+    Math,
+    /// `and or not`, with `==` for equivalence
+    Python,
+    /// Bitwise operators on single bit signals: `& | ~ ^ ~^`
+    Verilog,
+    /// Math mode LaTeX: `\land \lor \neg \oplus \rightarrow \leftrightarrow`
+    Latex
+}
+
+impl Style {
+    pub fn from_name(name: &str) -> Option<Style> {
+        match name {
+            "native" => Some(Style::Native),
+            "c" => Some(Style::C),
+            "math" => Some(Style::Math),
+            "python" => Some(Style::Python),
+            "verilog" => Some(Style::Verilog),
+            "latex" => Some(Style::Latex),
+            _ => None
+        }
+    }
+
+    fn not(&self) -> &'static str {
+        match *self {
+            Style::Native => "!",
+            Style::C => "!",
+            Style::Math => "¬",
+            Style::Python => "not ",
+            Style::Verilog => "~",
+            Style::Latex => "\\neg "
+        }
+    }
+
+    fn constant(&self, val: bool) -> &'static str {
+        match (*self, val) {
+            (Style::Native, true) => "1",
+            (Style::Native, false) => "0",
+            (Style::C, true) => "true",
+            (Style::C, false) => "false",
+            (Style::Math, true) => "⊤",
+            (Style::Math, false) => "⊥",
+            (Style::Python, true) => "True",
+            (Style::Python, false) => "False",
+            (Style::Verilog, true) => "1'b1",
+            (Style::Verilog, false) => "1'b0",
+            (Style::Latex, true) => "\\top",
+            (Style::Latex, false) => "\\bot"
+        }
+    }
+
+    // None where the style has no operator and it's written out instead
+    fn binary(&self, op: &Type) -> Option<&'static str> {
+        match (*self, op) {
+            (Style::Native, &Type::And) => Some("&"),
+            (Style::Native, &Type::Or) => Some("|"),
+            (Style::Native, &Type::Xor) => Some("^"),
+            (Style::Native, &Type::Xnor) => Some("!^"),
+            (Style::Native, &Type::Implies) => Some("->"),
+            (Style::Native, &Type::Iff) => Some("<->"),
+
+            (Style::C, &Type::And) => Some("&&"),
+            (Style::C, &Type::Or) => Some("||"),
+            (Style::C, &Type::Xor) => Some("^"),
+            (Style::C, &Type::Xnor) | (Style::C, &Type::Iff) => Some("=="),
+
+            (Style::Math, &Type::And) => Some("∧"),
+            (Style::Math, &Type::Or) => Some("∨"),
+            (Style::Math, &Type::Xor) => Some("⊕"),
+            (Style::Math, &Type::Xnor) => Some("⊙"),
+            (Style::Math, &Type::Implies) => Some("→"),
+            (Style::Math, &Type::Iff) => Some("↔"),
+
+            (Style::Python, &Type::And) => Some("and"),
+            (Style::Python, &Type::Or) => Some("or"),
+            (Style::Python, &Type::Xor) => Some("^"),
+            (Style::Python, &Type::Xnor) | (Style::Python, &Type::Iff) => Some("=="),
+
+            (Style::Verilog, &Type::And) => Some("&"),
+            (Style::Verilog, &Type::Or) => Some("|"),
+            (Style::Verilog, &Type::Xor) => Some("^"),
+            (Style::Verilog, &Type::Xnor) | (Style::Verilog, &Type::Iff) => Some("~^"),
+
+            (Style::Latex, &Type::And) => Some("\\land"),
+            (Style::Latex, &Type::Or) => Some("\\lor"),
+            (Style::Latex, &Type::Xor) => Some("\\oplus"),
+            (Style::Latex, &Type::Xnor) => Some("\\odot"),
+            (Style::Latex, &Type::Implies) => Some("\\rightarrow"),
+            (Style::Latex, &Type::Iff) => Some("\\leftrightarrow"),
+
+            _ => None
+        }
+    }
+
+    // Python's `not` binds looser than `^` and `==`
+    fn wraps_negation(&self, op: &Type) -> bool {
+        match (*self, op) {
+            (Style::Python, &Type::And) | (Style::Python, &Type::Or) => false,
+            (Style::Python, _) => true,
+            _ => false
+        }
+    }
+
+    // Python reads `a == b == c` as a chained comparison, `a == b and b == c`,
+    // so every `==` after the first in a run closes a group, `(a == b) == c`
+    fn chains(&self, op: &Type) -> bool {
+        match (*self, op) {
+            (Style::Python, &Type::Xnor) | (Style::Python, &Type::Iff) => true,
+            _ => false
+        }
+    }
+}
+
+/// Formats an expression in a chosen `Style`, see `Expr::display`
+pub struct Display<'a> {
+    expr:   &'a Expr,
+    style:  Style
+}
+
+impl<'a> Display<'a> {
+    pub fn new(expr: &'a Expr, style: Style) -> Display<'a> {
+        Display { expr: expr, style: style }
+    }
+}
+
+impl<'a> fmt::Show for Display<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_expr(f, self.expr, self.style)
+    }
+}
+
+impl fmt::Show for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_expr(f, self, Style::Native)
+    }
+}
+
+fn write_expr(f: &mut fmt::Formatter, expr: &Expr, style: Style) -> fmt::Result {
+    if expr.components.len() == 1 {
+        return write_component(f, &expr.components[0], style, false)
+    }
+    if has_implication(expr, style) {
+        return write_implication(f, expr, style)
+    }
+
+    // Operators sharing a precedence here may not share one in the target
+    // notation, so a change of operator closes a parenthesised group
+    let closes = |idx: uint| {
+        let op = &expr.ops[idx].token_type;
+        *op != expr.ops[idx - 1].token_type || style.chains(op)
+    };
+    let mut opened = 0u;
+    for idx in range(1, expr.ops.len()) {
+        if closes(idx) { opened += 1 }
+    }
+    for _ in range(0, opened) { try!(write!(f, "(")) }
+
+    for (idx, component) in expr.components.iter().enumerate() {
+        if idx == 0 {
+            let wrap = style.wraps_negation(&expr.ops[0].token_type);
+            try!(write_component(f, component, style, wrap));
+            continue;
+        }
+
+        let op = &expr.ops[idx - 1].token_type;
+        if idx > 1 && closes(idx - 1) {
+            try!(write!(f, ")"));
+        }
+
+        try!(write!(f, " {} ", style.binary(op).unwrap()));
+        try!(write_component(f, component, style, style.wraps_negation(op)));
+    }
+    Ok(())
+}
+
+fn write_component(f: &mut fmt::Formatter, component: &Component, style: Style,
+                   wrap_negation: bool) -> fmt::Result {
+    if component.negated {
+        if wrap_negation { try!(write!(f, "(")) }
+        try!(write!(f, "{}", style.not()));
+    }
+
+    match component.value {
+        VarOrExpr::Var(ref name) => try!(write!(f, "{}", name)),
+        VarOrExpr::Const(val) => try!(write!(f, "{}", style.constant(val))),
+        // A parenthesised `?:` parses to a group holding just the `?:`, which
+        // brings its own parentheses
+        VarOrExpr::Expr(ref expr) if is_ite(expr) => try!(write_expr(f, expr, style)),
+        VarOrExpr::Expr(ref expr) => {
+            try!(write!(f, "("));
+            try!(write_expr(f, expr, style));
+            try!(write!(f, ")"));
+        },
+        VarOrExpr::Ite(ref cond, ref then, ref other) => {
+            try!(write!(f, "("));
+            try!(write_ite(f, &**cond, &**then, &**other, style));
+            try!(write!(f, ")"));
+        },
+        VarOrExpr::Call(ref name, ref args) => {
+            try!(write!(f, "{}(", name));
+            for (idx, arg) in args.iter().enumerate() {
+                if idx > 0 { try!(write!(f, ", ")) }
+                try!(write_expr(f, arg, style));
+            }
+            try!(write!(f, ")"));
+        }
+    }
+
+    if component.negated && wrap_negation { try!(write!(f, ")")) }
+    Ok(())
+}
+
+fn is_ite(expr: &Expr) -> bool {
+    match expr.components.as_slice() {
+        [Component { value: VarOrExpr::Ite(..), negated: false }] => true,
+        _ => false
+    }
+}
+
+fn has_implication(expr: &Expr, style: Style) -> bool {
+    expr.ops.len() == 1 && style.binary(&expr.ops[0].token_type).is_none()
+}
+
+// `a -> b` written out as `!(a) | b` for styles without an implication operator.
+// Implication is right associative so it never shares a list with other operators
+fn write_implication(f: &mut fmt::Formatter, expr: &Expr, style: Style) -> fmt::Result {
+    try!(write!(f, "{}(", style.not()));
+    try!(write_component(f, &expr.components[0], style, false));
+    try!(write!(f, ") {} ", style.binary(&Type::Or).unwrap()));
+    write_component(f, &expr.components[1], style, style.wraps_negation(&Type::Or))
+}
+
+fn write_ite(f: &mut fmt::Formatter, cond: &Expr, then: &Expr, other: &Expr,
+             style: Style) -> fmt::Result {
+    match style {
+        Style::Python => {
+            try!(write_expr(f, then, style));
+            try!(write!(f, " if "));
+            try!(write_expr(f, cond, style));
+            try!(write!(f, " else "));
+            write_expr(f, other, style)
+        },
+        Style::Math | Style::Latex => {
+            // ((c) ∧ (t)) ∨ (¬(c) ∧ (e))
+            let and = style.binary(&Type::And).unwrap();
+            try!(write!(f, "(("));
+            try!(write_expr(f, cond, style));
+            try!(write!(f, ") {} (", and));
+            try!(write_expr(f, then, style));
+            try!(write!(f, ")) {} ({}(", style.binary(&Type::Or).unwrap(), style.not()));
+            try!(write_expr(f, cond, style));
+            try!(write!(f, ") {} (", and));
+            try!(write_expr(f, other, style));
+            write!(f, "))")
+        },
+        _ => {
+            try!(write_expr(f, cond, style));
+            try!(write!(f, " ? "));
+            try!(write_expr(f, then, style));
+            try!(write!(f, " : "));
+            write_expr(f, other, style)
+        }
+    }
+}
+
+/// The tree as an S-expression such as `(or (and a b) (not c))`. A run of
+/// the same operator is one list, mixed operators of the same precedence
+/// nest to the left
+pub fn sexpr(expr: &Expr) -> String {
+    if expr.components.len() == 0 { return "false".to_string() }
+    let mut list = sexpr_component(&expr.components[0]);
+    if expr.ops.len() == 0 { return list }
+
+    let op = &expr.ops[0].token_type;
+    if expr.ops.iter().all(|tok| tok.token_type == *op) {
+        let operands: Vec<String> = expr.components.iter().map(|component| sexpr_component(component)).collect();
+        return format!("({} {})", sexpr_name(op), operands.connect(" "))
+    }
+    for idx in range(1, expr.components.len()) {
+        list = format!("({} {} {})", sexpr_name(&expr.ops[idx - 1].token_type), list,
+                       sexpr_component(&expr.components[idx]));
+    }
+    list
+}
+
+fn sexpr_component(component: &Component) -> String {
+    let value = match component.value {
+        VarOrExpr::Var(ref name) => name.clone(),
+        VarOrExpr::Const(val) => val.to_string(),
+        VarOrExpr::Expr(ref expr) => sexpr(expr),
+        VarOrExpr::Ite(ref cond, ref then, ref other) => {
+            format!("(ite {} {} {})", sexpr(&**cond), sexpr(&**then), sexpr(&**other))
+        },
+        VarOrExpr::Call(ref name, ref args) => {
+            let args: Vec<String> = args.iter().map(|arg| sexpr(arg)).collect();
+            format!("({} {})", name, args.connect(" "))
+        }
+    };
+    if component.negated { format!("(not {})", value) } else { value }
+}
+
+fn sexpr_name(op: &Type) -> &'static str {
+    match *op {
+        Type::And => "and",
+        Type::Or => "or",
+        Type::Xor => "xor",
+        Type::Xnor => "xnor",
+        Type::Implies => "implies",
+        Type::Iff => "iff",
+        _ => "?"
+    }
+}
@@ -0,0 +1,152 @@
+//! Graphviz drawings, in the DOT language read by `dot`.
+//!
+//! Expression trees are drawn with a box for each gate above its operands
+//! and an ellipse for each variable or constant. Decision diagrams are drawn
+//! with a circle for each test, a solid edge to its high child and a dashed
+//! edge to its low child, ending in the boxed terminals 0 and 1.
+
+use bdd;
+use bdd::{Bdd, BddRef};
+use expr::{Component, Expr, VarOrExpr};
+use lexer::Type;
+
+/// The expression tree as a DOT graph. Operators of the same precedence
+/// make a single gate when they are all the same, otherwise they nest to
+/// the left like they evaluate
+pub fn expr_to_dot(expr: &Expr) -> String {
+    let mut graph = Graph { dot: String::new(), nodes: 0 };
+    graph.dot.push_str("digraph expr {\n");
+    graph.expr(expr);
+    graph.dot.push_str("}\n");
+    graph.dot
+}
+
+/// The nodes of `bdd` reachable from `f` as a DOT graph, each variable
+/// drawn on its own rank
+pub fn bdd_to_dot(bdd: &Bdd, f: BddRef) -> String {
+    let mut dot = String::from_str("digraph bdd {\n");
+    let mut seen = vec!(f);
+    let mut idx = 0;
+    let mut ranks: Vec<Vec<BddRef>> = Vec::from_fn(bdd.variables().len(), |_| Vec::new());
+    let mut terminals = Vec::new();
+    while idx < seen.len() {
+        let node = seen[idx];
+        idx += 1;
+        match bdd.node(node) {
+            Some((var, low, high)) => {
+                ranks[var].push(node);
+                dot.push_str(format!("    n{} [label=\"{}\", shape=circle];\n",
+                                     node, escape(bdd.variables()[var].as_slice())).as_slice());
+                dot.push_str(format!("    n{} -> n{};\n", node, high).as_slice());
+                dot.push_str(format!("    n{} -> n{} [style=dashed];\n", node, low).as_slice());
+                for &child in [high, low].iter() {
+                    if !seen.contains(&child) { seen.push(child) }
+                }
+            },
+            None => terminals.push(node)
+        }
+    }
+
+    for rank in ranks.iter().filter(|rank| !rank.is_empty()) {
+        let names: Vec<String> = rank.iter().map(|node| format!("n{}", node)).collect();
+        dot.push_str(format!("    {{ rank=same; {}; }}\n", names.connect("; ")).as_slice());
+    }
+    for &node in terminals.iter() {
+        let label = if node == bdd::TRUE { "1" } else { "0" };
+        dot.push_str(format!("    n{} [label=\"{}\", shape=box];\n", node, label).as_slice());
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+struct Graph {
+    dot:   String,
+    nodes: uint
+}
+
+impl Graph {
+    // Adds a node and returns its name
+    fn node(&mut self, label: &str, shape: &str) -> String {
+        let name = format!("n{}", self.nodes);
+        self.nodes += 1;
+        self.dot.push_str(format!("    {} [label=\"{}\", shape={}];\n", name, escape(label), shape).as_slice());
+        name
+    }
+
+    fn edge(&mut self, from: &str, to: &str) {
+        self.dot.push_str(format!("    {} -> {};\n", from, to).as_slice());
+    }
+
+    fn expr(&mut self, expr: &Expr) -> String {
+        if expr.components.len() == 0 { return self.node("0", "ellipse") }
+        if expr.ops.len() == 0 { return self.component(&expr.components[0]) }
+
+        let op = expr.ops[0].token_type.clone();
+        if expr.ops.iter().all(|tok| tok.token_type == op) {
+            let gate = self.node(gate_label(&op), "box");
+            for component in expr.components.iter() {
+                let child = self.component(component);
+                self.edge(gate.as_slice(), child.as_slice());
+            }
+            return gate
+        }
+
+        let mut left = self.component(&expr.components[0]);
+        for idx in range(1, expr.components.len()) {
+            let gate = self.node(gate_label(&expr.ops[idx - 1].token_type), "box");
+            let right = self.component(&expr.components[idx]);
+            self.edge(gate.as_slice(), left.as_slice());
+            self.edge(gate.as_slice(), right.as_slice());
+            left = gate;
+        }
+        left
+    }
+
+    fn component(&mut self, component: &Component) -> String {
+        let not = if component.negated { Some(self.node("NOT", "box")) } else { None };
+        let value = match component.value {
+            VarOrExpr::Var(ref name) => self.node(name.as_slice(), "ellipse"),
+            VarOrExpr::Const(val) => self.node(if val { "1" } else { "0" }, "ellipse"),
+            VarOrExpr::Expr(ref expr) => self.expr(expr),
+            VarOrExpr::Ite(ref cond, ref then, ref other) => {
+                let gate = self.node("ITE", "box");
+                for branch in [&**cond, &**then, &**other].iter() {
+                    let child = self.expr(*branch);
+                    self.edge(gate.as_slice(), child.as_slice());
+                }
+                gate
+            },
+            VarOrExpr::Call(ref name, ref args) => {
+                let gate = self.node(name.as_slice(), "box");
+                for arg in args.iter() {
+                    let child = self.expr(arg);
+                    self.edge(gate.as_slice(), child.as_slice());
+                }
+                gate
+            }
+        };
+        match not {
+            Some(not) => {
+                self.edge(not.as_slice(), value.as_slice());
+                not
+            },
+            None => value
+        }
+    }
+}
+
+fn gate_label(op: &Type) -> &'static str {
+    match *op {
+        Type::And => "AND",
+        Type::Or => "OR",
+        Type::Xor => "XOR",
+        Type::Xnor => "XNOR",
+        Type::Implies => "IMPLIES",
+        Type::Iff => "IFF",
+        _ => "?"
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace("\\", "\\\\").replace("\"", "\\\"")
+}
@@ -0,0 +1,303 @@
+//! Line editing for the REPL at a terminal.
+//!
+//! The terminal is put into raw mode while a line is read, so keys arrive
+//! one at a time: the arrows, Home and End move along the line, Backspace
+//! and Delete remove characters, Up and Down recall earlier lines, and the
+//! usual Emacs control keys do the same. Where raw mode isn't available
+//! the line is read as the terminal gives it.
+
+use std::io::stdio;
+use std::str;
+
+use self::termios::Termios;
+
+// Characters given by control keys
+const CTRL_A: u8 = 1;
+const CTRL_B: u8 = 2;
+const CTRL_C: u8 = 3;
+const CTRL_D: u8 = 4;
+const CTRL_E: u8 = 5;
+const CTRL_F: u8 = 6;
+const CTRL_H: u8 = 8;
+const CTRL_K: u8 = 11;
+const CTRL_N: u8 = 14;
+const CTRL_P: u8 = 16;
+const CTRL_U: u8 = 21;
+const ESC: u8 = 27;
+const BACKSPACE: u8 = 127;
+
+// What a key does to the line being edited
+enum Key {
+    Insert(char),
+    Left,
+    Right,
+    Home,
+    End,
+    Backspace,
+    Delete,
+    KillToEnd,
+    KillToStart,
+    Up,
+    Down,
+    Enter,
+    Cancel,
+    // Ctrl-D, the end of input on an empty line
+    Eof,
+    // Stdin has closed
+    Closed,
+    Ignored
+}
+
+/// Reads lines from stdin with editing and recall of earlier lines
+pub struct Editor {
+    input:   stdio::StdReader,
+    history: Vec<String>
+}
+
+impl Editor {
+    pub fn new() -> Editor {
+        Editor { input: stdio::stdin_raw(), history: Vec::new() }
+    }
+
+    /// Prints `prompt` and reads a line, without its newline. None at the
+    /// end of input
+    pub fn read_line(&mut self, prompt: &str) -> Option<String> {
+        print!("{}", prompt);
+        stdio::flush();
+        let saved = match termios::raw() {
+            Some(saved) => saved,
+            None => return self.read_cooked()
+        };
+        let line = self.edit(prompt);
+        termios::restore(&saved);
+        line
+    }
+
+    /// Makes `line` the latest for Up to recall, unless it already is
+    pub fn add_history(&mut self, line: String) {
+        if self.history.last() != Some(&line) { self.history.push(line) }
+    }
+
+    // A line as the terminal gives it, when it can't be put in raw mode
+    fn read_cooked(&mut self) -> Option<String> {
+        let mut bytes = Vec::new();
+        loop {
+            match self.input.read_byte() {
+                Ok(b'\n') => break,
+                Ok(byte) => bytes.push(byte),
+                Err(_) if bytes.is_empty() => return None,
+                Err(_) => break
+            }
+        }
+        Some(String::from_utf8_lossy(bytes.as_slice()).into_string())
+    }
+
+    // Reads keys until Enter, redrawing the line after each
+    fn edit(&mut self, prompt: &str) -> Option<String> {
+        let mut line: Vec<char> = Vec::new();
+        let mut cursor = 0u;
+        // The line in `history` on show, `history.len()` being the new one,
+        // and the new line as it was before Up was first pressed
+        let mut recalled = self.history.len();
+        let mut draft: Vec<char> = Vec::new();
+
+        loop {
+            match self.key() {
+                Key::Insert(c) => {
+                    line.insert(cursor, c);
+                    cursor += 1;
+                },
+                Key::Left => if cursor > 0 { cursor -= 1 },
+                Key::Right => if cursor < line.len() { cursor += 1 },
+                Key::Home => cursor = 0,
+                Key::End => cursor = line.len(),
+                Key::Backspace => if cursor > 0 {
+                    cursor -= 1;
+                    line.remove(cursor);
+                },
+                Key::Delete => if cursor < line.len() { line.remove(cursor); },
+                Key::KillToEnd => line.truncate(cursor),
+                Key::KillToStart => {
+                    line = line.slice_from(cursor).to_vec();
+                    cursor = 0;
+                },
+                Key::Up => if recalled > 0 {
+                    if recalled == self.history.len() { draft = line.clone() }
+                    recalled -= 1;
+                    line = self.history[recalled].as_slice().chars().collect();
+                    cursor = line.len();
+                },
+                Key::Down => if recalled < self.history.len() {
+                    recalled += 1;
+                    line = if recalled == self.history.len() {
+                        draft.clone()
+                    } else {
+                        self.history[recalled].as_slice().chars().collect()
+                    };
+                    cursor = line.len();
+                },
+                Key::Enter => {
+                    print!("\r\n");
+                    return Some(String::from_chars(line.as_slice()))
+                },
+                // The line is dropped and a new one started
+                Key::Cancel => {
+                    print!("^C\r\n");
+                    return Some(String::new())
+                },
+                Key::Eof if line.is_empty() => {
+                    print!("\r\n");
+                    return None
+                },
+                Key::Eof => if cursor < line.len() { line.remove(cursor); },
+                Key::Closed if line.is_empty() => return None,
+                Key::Closed => return Some(String::from_chars(line.as_slice())),
+                Key::Ignored => {}
+            }
+            redraw(prompt, line.as_slice(), cursor);
+        }
+    }
+
+    // The next key pressed, reading the whole of an escape sequence or a
+    // UTF-8 character
+    fn key(&mut self) -> Key {
+        let byte = match self.input.read_byte() {
+            Ok(byte) => byte,
+            Err(_) => return Key::Closed
+        };
+        match byte {
+            b'\r' | b'\n' => Key::Enter,
+            CTRL_A => Key::Home,
+            CTRL_B => Key::Left,
+            CTRL_C => Key::Cancel,
+            CTRL_D => Key::Eof,
+            CTRL_E => Key::End,
+            CTRL_F => Key::Right,
+            CTRL_H | BACKSPACE => Key::Backspace,
+            CTRL_K => Key::KillToEnd,
+            CTRL_N => Key::Down,
+            CTRL_P => Key::Up,
+            CTRL_U => Key::KillToStart,
+            ESC => self.escape(),
+            byte if byte < 0x20 => Key::Ignored,
+            byte => self.character(byte)
+        }
+    }
+
+    // The key given by an escape sequence, after its ESC. The arrows, Home,
+    // End and Delete are `ESC [ x` or `ESC O x`, some with a number before a `~`
+    fn escape(&mut self) -> Key {
+        match self.input.read_byte() {
+            Ok(b'[') | Ok(b'O') => {},
+            _ => return Key::Ignored
+        }
+        let mut number = None;
+        loop {
+            match self.input.read_byte() {
+                Ok(digit) if digit >= b'0' && digit <= b'9' => {
+                    number = Some(number.unwrap_or(0u) * 10 + (digit - b'0') as uint)
+                },
+                Ok(b'A') => return Key::Up,
+                Ok(b'B') => return Key::Down,
+                Ok(b'C') => return Key::Right,
+                Ok(b'D') => return Key::Left,
+                Ok(b'H') => return Key::Home,
+                Ok(b'F') => return Key::End,
+                Ok(b'~') => return match number {
+                    Some(1) | Some(7) => Key::Home,
+                    Some(3) => Key::Delete,
+                    Some(4) | Some(8) => Key::End,
+                    _ => Key::Ignored
+                },
+                _ => return Key::Ignored
+            }
+        }
+    }
+
+    // The character starting with `first`, reading the rest of its bytes
+    fn character(&mut self, first: u8) -> Key {
+        let width = str::utf8_char_width(first);
+        let mut bytes = vec!(first);
+        while bytes.len() < width {
+            match self.input.read_byte() {
+                Ok(byte) => bytes.push(byte),
+                Err(_) => return Key::Ignored
+            }
+        }
+        match str::from_utf8(bytes.as_slice()) {
+            Some(s) => Key::Insert(s.char_at(0)),
+            None => Key::Ignored
+        }
+    }
+}
+
+// Prints the prompt and `line` over the last drawing of them, then moves
+// back to `cursor`. Each character is taken to be one column wide
+fn redraw(prompt: &str, line: &[char], cursor: uint) {
+    print!("\r{}{}\x1b[K", prompt, String::from_chars(line));
+    if cursor < line.len() { print!("\x1b[{}D", line.len() - cursor) }
+    stdio::flush();
+}
+
+// Raw mode through termios, where echoing, line buffering and the keys
+// which send signals are turned off
+#[cfg(target_os = "linux")]
+mod termios {
+    use libc::{c_int, c_uint};
+
+    const ISIG: c_uint = 0o1;
+    const ICANON: c_uint = 0o2;
+    const ECHO: c_uint = 0o10;
+    const IXON: c_uint = 0o2000;
+    const ICRNL: c_uint = 0o400;
+    const VTIME: uint = 5;
+    const VMIN: uint = 6;
+    const TCSANOW: c_int = 0;
+    const STDIN: c_int = 0;
+
+    #[repr(C)]
+    #[deriving(Clone)]
+    pub struct Termios {
+        c_iflag:  c_uint,
+        c_oflag:  c_uint,
+        c_cflag:  c_uint,
+        c_lflag:  c_uint,
+        c_line:   u8,
+        c_cc:     [u8, ..32],
+        c_ispeed: c_uint,
+        c_ospeed: c_uint
+    }
+
+    extern {
+        fn tcgetattr(fd: c_int, termios: *mut Termios) -> c_int;
+        fn tcsetattr(fd: c_int, actions: c_int, termios: *const Termios) -> c_int;
+    }
+
+    /// Puts stdin into raw mode, returning the settings to restore. None
+    /// when stdin isn't a terminal
+    pub fn raw() -> Option<Termios> {
+        let mut saved = Termios { c_iflag: 0, c_oflag: 0, c_cflag: 0, c_lflag: 0, c_line: 0, c_cc: [0, ..32],
+                                  c_ispeed: 0, c_ospeed: 0 };
+        if unsafe { tcgetattr(STDIN, &mut saved) } != 0 { return None }
+        let mut raw = saved.clone();
+        raw.c_iflag &= !(IXON | ICRNL);
+        raw.c_lflag &= !(ECHO | ICANON | ISIG);
+        raw.c_cc[VMIN] = 1;
+        raw.c_cc[VTIME] = 0;
+        if unsafe { tcsetattr(STDIN, TCSANOW, &raw) } != 0 { return None }
+        Some(saved)
+    }
+
+    pub fn restore(saved: &Termios) {
+        unsafe { tcsetattr(STDIN, TCSANOW, saved); }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod termios {
+    pub struct Termios;
+
+    pub fn raw() -> Option<Termios> { None }
+
+    pub fn restore(_: &Termios) {}
+}
@@ -0,0 +1,191 @@
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use error::TruthError;
+use expr::{undefined_function, Expr, VarOrExpr};
+use visit::Folder;
+
+pub trait Environment {
+    fn get_variable(&self, name: &str) -> bool;
+}
+
+pub struct EnvironmentImpl {
+    pub vars: BTreeMap<String, bool>
+}
+
+impl Environment for EnvironmentImpl {
+    fn get_variable(&self, name: &str) -> bool {
+        match self.vars.get(name) {
+            Some(var) => *var,
+            None => false
+        }
+    }
+}
+
+/// A value for each of a list of variables, such as a model of an expression
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+pub struct Assignment {
+    vars:   Vec<String>,
+    values: Vec<bool>
+}
+
+impl Assignment {
+    pub fn new(vars: Vec<String>, values: Vec<bool>) -> Assignment {
+        assert!(vars.len() == values.len(), "{} values for {} variables", values.len(), vars.len());
+        Assignment { vars: vars, values: values }
+    }
+
+    pub fn variables(&self) -> &[String] {
+        self.vars.as_slice()
+    }
+
+    /// Values in the order of `variables`
+    pub fn values(&self) -> &[bool] {
+        self.values.as_slice()
+    }
+
+    /// The value of the variable called `name`, if it is assigned
+    pub fn get(&self, name: &str) -> Option<bool> {
+        self.vars.iter().position(|var| var.as_slice() == name).map(|pos| self.values[pos])
+    }
+}
+
+impl Environment for Assignment {
+    fn get_variable(&self, name: &str) -> bool {
+        self.get(name).unwrap_or(false)
+    }
+}
+
+/// `a = 1, b = 0`
+impl fmt::Show for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (pos, var) in self.vars.iter().enumerate() {
+            if pos > 0 { try!(write!(f, ", ")) }
+            try!(write!(f, "{} = {}", var, self.values[pos] as u8));
+        }
+        Ok(())
+    }
+}
+
+/// Names given to expressions by `let`, each stored with the names bound
+/// before it already expanded
+#[deriving(Clone)]
+pub struct Bindings {
+    names: Vec<String>,
+    exprs: Vec<Expr>
+}
+
+impl Bindings {
+    pub fn new() -> Bindings {
+        Bindings { names: Vec::new(), exprs: Vec::new() }
+    }
+
+    pub fn names(&self) -> &[String] {
+        self.names.as_slice()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Expr> {
+        self.names.iter().position(|var| var.as_slice() == name).map(|pos| &self.exprs[pos])
+    }
+
+    /// Binds `name` to `expr`, which may use the names bound so far,
+    /// including `name` itself for its previous value
+    pub fn define(&mut self, name: String, expr: &Expr) {
+        let expr = self.expand(expr);
+        match self.names.iter().position(|var| *var == name) {
+            Some(pos) => self.exprs[pos] = expr,
+            None => {
+                self.names.push(name);
+                self.exprs.push(expr);
+            }
+        }
+    }
+
+    /// `expr` with each bound name replaced by its expression
+    pub fn expand(&self, expr: &Expr) -> Expr {
+        self.names.iter().zip(self.exprs.iter()).fold(expr.clone(), |expr, (name, bound)| {
+            expr.substitute(name.as_slice(), bound)
+        })
+    }
+}
+
+/// Functions defined with `def`, as in `def maj(a,b,c) = (a&b)|(b&c)|(a&c)`
+#[deriving(Clone)]
+pub struct Functions {
+    names:  Vec<String>,
+    params: Vec<Vec<String>>,
+    bodies: Vec<Expr>
+}
+
+impl Functions {
+    pub fn new() -> Functions {
+        Functions { names: Vec::new(), params: Vec::new(), bodies: Vec::new() }
+    }
+
+    pub fn names(&self) -> &[String] {
+        self.names.as_slice()
+    }
+
+    /// The parameters and body of the function called `name`
+    pub fn get(&self, name: &str) -> Option<(&[String], &Expr)> {
+        self.names.iter().position(|var| var.as_slice() == name).map(|pos| {
+            (self.params[pos].as_slice(), &self.bodies[pos])
+        })
+    }
+
+    /// Defines `name`, replacing any earlier definition. Calls in `body`
+    /// are expanded straight away, so they must be of functions already
+    /// defined
+    pub fn define(&mut self, name: String, params: Vec<String>, body: &Expr) -> Result<(), TruthError> {
+        let body = try!(self.expand(body));
+        match self.names.iter().position(|var| *var == name) {
+            Some(pos) => {
+                self.params[pos] = params;
+                self.bodies[pos] = body;
+            },
+            None => {
+                self.names.push(name);
+                self.params.push(params);
+                self.bodies.push(body);
+            }
+        }
+        Ok(())
+    }
+
+    /// `expr` with each call replaced by the body of its function, the
+    /// parameters replaced by the arguments
+    pub fn expand(&self, expr: &Expr) -> Result<Expr, TruthError> {
+        let mut folder = Expansion { functions: self, error: None };
+        let expr = folder.fold_expr(expr.clone());
+        match folder.error {
+            Some(err) => Err(err),
+            None => Ok(expr)
+        }
+    }
+}
+
+// Expands calls, keeping the first error
+struct Expansion<'a> {
+    functions: &'a Functions,
+    error:     Option<TruthError>
+}
+
+impl<'a> Folder for Expansion<'a> {
+    fn fold_call(&mut self, name: String, args: Vec<Expr>) -> VarOrExpr {
+        let args: Vec<Expr> = args.into_iter().map(|arg| self.fold_expr(arg)).collect();
+        let err = match self.functions.get(name.as_slice()) {
+            Some((params, body)) if params.len() == args.len() => {
+                return VarOrExpr::Expr(body.substitute_all(params, args.as_slice()))
+            },
+            Some((params, _)) => TruthError::Eval(
+                format!("{} takes {} arguments, found {}", name, params.len(), args.len()), None
+            ),
+            None => undefined_function(name.as_slice())
+        };
+        if self.error.is_none() { self.error = Some(err) }
+        VarOrExpr::Const(false)
+    }
+}
@@ -0,0 +1,194 @@
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use std::cmp;
+#[cfg(feature = "std")]
+use std::error::Error;
+use std::fmt;
+
+use lexer::{Token, TAB_WIDTH};
+
+/// Where in the source an error occurred, columns are inclusive
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+pub struct Span {
+    pub line:       uint,
+    pub col_range:  (uint, uint),
+    /// Byte offsets into the source of the start and the end, exclusive.
+    /// None for spans which weren't read from a source
+    pub byte_range: Option<(uint, uint)>
+}
+
+impl Span {
+    pub fn new(line: uint, start: uint, end: uint) -> Span {
+        Span { line: line, col_range: (start, end), byte_range: None }
+    }
+
+    /// The same span with the byte offsets `start..end`
+    pub fn with_bytes(self, start: uint, end: uint) -> Span {
+        Span { byte_range: Some((start, end)), ..self }
+    }
+
+    /// Tokens built outside the lexer, on line 0, have no byte offsets
+    pub fn from_token(tok: &Token) -> Span {
+        let span = Span::new(tok.line, tok.col, tok.col);
+        if tok.line == 0 { span } else { span.with_bytes(tok.bytes.val0(), tok.bytes.val1()) }
+    }
+}
+
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+pub enum TruthError {
+    /// Input that isn't a valid token
+    Lex(String, Span),
+    /// Tokens in an order that isn't a valid expression, or an invalid
+    /// table. None where no one place in the source is at fault, such as
+    /// a missing row
+    Parse(String, Option<Span>),
+    /// A parsed expression which can't be evaluated, with the operator at
+    /// fault if there is one
+    Eval(String, Option<Span>),
+    /// Too many variables to enumerate every row, holds the variable count
+    Overflow(uint)
+}
+
+impl TruthError {
+    pub fn span(&self) -> Option<&Span> {
+        match *self {
+            TruthError::Lex(_, ref span) => Some(span),
+            TruthError::Parse(_, ref span) |
+            TruthError::Eval(_, ref span) => span.as_ref(),
+            TruthError::Overflow(_) => None
+        }
+    }
+
+    /// The same error placed on `line`, for sources parsed a line at a time
+    pub fn on_line(self, line: uint) -> TruthError {
+        self.map_span(|span| Span { line: line, ..span })
+    }
+
+    /// The same error with its columns moved `cols` to the right and its
+    /// byte offsets `bytes` on, for sources parsed from partway along a line
+    pub fn shifted(self, cols: uint, bytes: uint) -> TruthError {
+        self.map_span(|span| {
+            let (start, end) = span.col_range;
+            Span {
+                line: span.line,
+                col_range: (start + cols, end + cols),
+                byte_range: span.byte_range.map(|(start, end)| (start + bytes, end + bytes))
+            }
+        })
+    }
+
+    fn map_span(self, f: |Span| -> Span) -> TruthError {
+        match self {
+            TruthError::Lex(msg, span) => TruthError::Lex(msg, f(span)),
+            TruthError::Parse(msg, span) => TruthError::Parse(msg, span.map(|span| f(span))),
+            TruthError::Eval(msg, span) => TruthError::Eval(msg, span.map(|span| f(span))),
+            other => other
+        }
+    }
+
+    /// What was expected where the error occurred, if there is anything to say
+    pub fn note(&self) -> Option<&'static str> {
+        match *self {
+            TruthError::Lex(..) => {
+                Some("expressions are made of names, the constants 0 and 1, parentheses and the operators ! & ^ | -> <-> ? :")
+            },
+            TruthError::Parse(..) => Some("every operator needs an operand on each side, and every `(` a matching `)`"),
+            TruthError::Eval(..) | TruthError::Overflow(_) => None
+        }
+    }
+
+    /// The error in the style of rustc: the message, where it occurred, the
+    /// line of `src` it is on with the span underlined, and the note.
+    /// `origin` names the source, such as a file
+    pub fn render(&self, src: &str, origin: &str) -> String {
+        self.render_tabs(src, origin, TAB_WIDTH)
+    }
+
+    /// `render` for columns counted with tab stops every `tab_width`
+    /// columns, as set with `Lexer::set_tab_width`. Tabs in the line are
+    /// printed as spaces so the underline stays under the span
+    pub fn render_tabs(&self, src: &str, origin: &str, tab_width: uint) -> String {
+        let mut out = format!("error: {}\n", self.message());
+        let span = match self.span() {
+            Some(span) => span,
+            None => return out
+        };
+        let start = cmp::max(span.col_range.val0(), 1);
+        let end = cmp::max(span.col_range.val1(), start);
+        out.push_str(format!(" --> {}:{}:{}\n", origin, span.line, start).as_slice());
+
+        // A source of one line is the line itself, whatever line it was on.
+        // Lines may end in `\r\n`
+        let lines: Vec<&str> = src.lines_any().collect();
+        let line = if lines.len() > 1 { lines.get(cmp::max(span.line, 1) - 1) } else { lines.get(0) };
+        let number = span.line.to_string();
+        let gutter = String::from_char(number.len(), ' ');
+        match line {
+            Some(line) => {
+                out.push_str(format!("{} |\n{} | {}\n{} | {}{}\n", gutter, number, expand_tabs(*line, tab_width), gutter,
+                                     String::from_char(start - 1, ' '), String::from_char(end - start + 1, '^')).as_slice());
+            },
+            None => {}
+        }
+        match self.note() {
+            Some(note) => out.push_str(format!("{} = note: {}\n", gutter, note).as_slice()),
+            None => {}
+        }
+        out
+    }
+
+    pub fn message(&self) -> String {
+        match *self {
+            TruthError::Lex(ref msg, _) |
+            TruthError::Parse(ref msg, _) |
+            TruthError::Eval(ref msg, _) => msg.clone(),
+            TruthError::Overflow(count) => format!("Too many variables: {}", count)
+        }
+    }
+}
+
+// `line` with each tab replaced by the spaces up to the next tab stop
+fn expand_tabs(line: &str, tab_width: uint) -> String {
+    let mut out = String::new();
+    let mut col = 0u;
+    for c in line.chars() {
+        if c == '\t' {
+            let stop = (col / tab_width + 1) * tab_width;
+            out.push_str(String::from_char(stop - col, ' ').as_slice());
+            col = stop;
+        } else {
+            out.push(c);
+            col += 1;
+        }
+    }
+    out
+}
+
+impl fmt::Show for TruthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.span() {
+            Some(span) => write!(f, "{} at column {}, line {}",
+                                 self.message(), span.col_range.val0(), span.line),
+            None => write!(f, "{}", self.message())
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for TruthError {
+    fn description(&self) -> &str {
+        match *self {
+            TruthError::Lex(..) => "invalid token",
+            TruthError::Parse(..) => "invalid expression",
+            TruthError::Eval(..) => "evaluation failed",
+            TruthError::Overflow(..) => "too many variables"
+        }
+    }
+
+    fn detail(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
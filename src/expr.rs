@@ -0,0 +1,694 @@
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use bytecode::Program;
+use display;
+use display::{Display, Style};
+use env::{Assignment, Environment};
+use error::{Span, TruthError};
+use lexer::{Lexer, Token, Type};
+use parser::Parser;
+use simplify;
+use simplify::Step;
+use symbol::SymbolTable;
+use visit;
+use visit::{Folder, Visitor};
+
+#[cfg(feature = "std")]
+use std::cmp;
+#[cfg(feature = "std")]
+use std::os;
+#[cfg(feature = "std")]
+use std::rand;
+#[cfg(feature = "std")]
+use std::sync::{Arc, Future};
+
+#[cfg(feature = "std")]
+use aig::{Aig, AigLit};
+#[cfg(feature = "std")]
+use bdd::{Bdd, BddRef, Models};
+#[cfg(feature = "std")]
+use dot;
+#[cfg(feature = "std")]
+use minimize;
+#[cfg(feature = "std")]
+use normal;
+#[cfg(feature = "std")]
+use normal::{Anf, Cnf, CnfMethod, Dnf, DnfMethod};
+#[cfg(feature = "std")]
+use sat::{Classification, Counterexample, Solver};
+#[cfg(feature = "std")]
+use svg;
+#[cfg(feature = "std")]
+use table::TruthTable;
+
+#[cfg_attr(feature = "std", deriving(Encodable))]
+#[cfg_attr(feature = "std", deriving(Decodable))]
+#[deriving(Clone)]
+pub struct Expr {
+    pub components: Vec<Component>,
+    pub ops: Vec<Token>
+}
+
+impl Expr {
+    /// Lexes and parses `src`, use `from_str` when the error isn't needed
+    pub fn parse(src: &str) -> Result<Expr, TruthError> {
+        let mut lexer = Lexer::new(src.to_string());
+        let mut parser = try!(Parser::new(&mut lexer));
+        parser.parse()
+    }
+
+    /// Like `parse`, but goes on past each error to report all of them
+    pub fn parse_all(src: &str) -> Result<Expr, Vec<TruthError>> {
+        Parser::parse_recovering(&mut Lexer::new(src.to_string()))
+    }
+
+    /// The components joined by `op`, for building expressions outside the parser.
+    /// The operator tokens have no source position
+    pub fn join(components: Vec<Component>, op: Type) -> Expr {
+        let ops = range(1, components.len()).map(|_| Token { token_type: op.clone(), col: 0, line: 0, bytes: (0, 0) }).collect();
+        Expr { components: components, ops: ops }
+    }
+
+    // Unwraps single component operations so nesting only appears where needed
+    pub fn into_component(mut self) -> Component {
+        if self.ops.len() == 0 && self.components.len() == 1 {
+            self.components.pop().unwrap()
+        } else {
+            Component { value: VarOrExpr::Expr(self), negated: false }
+        }
+    }
+
+    /// The inverse of `into_component`, unwrapping a nested expression
+    pub fn from_component(component: Component) -> Expr {
+        match component {
+            Component { value: VarOrExpr::Expr(expr), negated: false } => expr,
+            component => Expr { components: vec!(component), ops: vec!() }
+        }
+    }
+
+    /// Formats the expression in the given notation
+    pub fn display(&self, style: Style) -> Display {
+        Display::new(self, style)
+    }
+
+    pub fn eval(&self, env: &Environment) -> Result<bool, TruthError> {
+        let mut val = false;
+        if self.components.len() > 0 {
+            val = try!(self.components[0].eval(env));
+        }
+
+        for idx in range(1u, self.components.len()) {
+            let eval = try!(self.components[idx].eval(env));
+            //let token = self.ops[idx - 1];
+            match self.ops[idx - 1].token_type {
+                Type::And => val &= eval,
+                Type::Or => val |= eval,
+                Type::Xor => val ^= eval,
+                Type::Xnor => val = val == eval,
+                Type::Implies => val = !val || eval,
+                Type::Iff => val = val == eval,
+                ref other => {
+                    return Err(TruthError::Eval(
+                        format!("Unexpected operation: {}", other),
+                        Some(Span::from_token(&self.ops[idx - 1])),
+                    ))
+                }
+            }
+        }
+
+        Ok(val)
+    }
+
+    /// The value for `assignment`, which must give every variable a value
+    pub fn eval_with(&self, assignment: &Assignment) -> Result<bool, TruthError> {
+        match self.get_variables().into_iter().find(|var| assignment.get(var.as_slice()).is_none()) {
+            Some(var) => Err(TruthError::Eval(format!("No value for {}", var), None)),
+            None => self.eval(assignment)
+        }
+    }
+
+    /// Evaluates 64 assignments at once, bit `i` of each variable's lane
+    /// being its value in assignment `i`. Missing variables are false
+    pub fn eval_lanes(&self, lanes: &BTreeMap<String, u64>) -> Result<u64, TruthError> {
+        let mut val = 0;
+        if self.components.len() > 0 {
+            val = try!(self.components[0].eval_lanes(lanes));
+        }
+
+        for idx in range(1u, self.components.len()) {
+            let eval = try!(self.components[idx].eval_lanes(lanes));
+            match self.ops[idx - 1].token_type {
+                Type::And => val &= eval,
+                Type::Or => val |= eval,
+                Type::Xor => val ^= eval,
+                Type::Xnor | Type::Iff => val = !(val ^ eval),
+                Type::Implies => val = !val | eval,
+                ref other => {
+                    return Err(TruthError::Eval(
+                        format!("Unexpected operation: {}", other),
+                        Some(Span::from_token(&self.ops[idx - 1])),
+                    ))
+                }
+            }
+        }
+
+        Ok(val)
+    }
+
+    /// The tree as an S-expression, see `display::sexpr`
+    pub fn to_sexpr(&self) -> String {
+        display::sexpr(self)
+    }
+
+    /// The Shannon cofactor: each variable given a value by `assignment`
+    /// replaced by that constant, then simplified
+    pub fn restrict(&self, assignment: &Assignment) -> Expr {
+        let mut folder = Restriction { assignment: assignment };
+        simplify::simplify(&folder.fold_expr(self.clone()))
+    }
+
+    /// Replaces each occurrence of the variable `var` with `expr`
+    pub fn substitute(&self, var: &str, expr: &Expr) -> Expr {
+        self.substitute_all(&[var.to_string()], &[expr.clone()])
+    }
+
+    /// Replaces each of `vars` with the expression at the same position of
+    /// `exprs`, all at once, so `exprs` may use the names in `vars`
+    pub fn substitute_all(&self, vars: &[String], exprs: &[Expr]) -> Expr {
+        let mut folder = Substitution { vars: vars, exprs: exprs };
+        folder.fold_expr(self.clone())
+    }
+
+    /// Rewrites by the laws of boolean algebra until none apply
+    pub fn simplify(&self) -> Expr {
+        simplify::simplify(self)
+    }
+
+    /// Each rewrite made by `simplify` with the law it applied
+    pub fn simplify_steps(&self) -> Vec<Step> {
+        simplify::simplify_steps(self)
+    }
+
+    /// Compiles to bytecode with the variables in order of first appearance
+    pub fn compile(&self) -> Result<Program, TruthError> {
+        Program::compile(self, self.get_variables().as_slice())
+    }
+
+    /// The parenthesised groups and negations inside the expression, each
+    /// after those it contains, as for the columns of a table worked out
+    /// step by step. The whole expression is left out
+    pub fn subexpressions(&self) -> Vec<Expr> {
+        let mut collector = SubexprCollector { exprs: Vec::new() };
+        collector.visit_expr(self);
+        let whole = self.to_string();
+        collector.exprs.into_iter().filter(|expr| expr.to_string() != whole).collect()
+    }
+
+    /// Variables in order of first appearance
+    pub fn get_variables(&self) -> Vec<String> {
+        let mut collector = VariableCollector { vars: SymbolTable::new() };
+        collector.visit_expr(self);
+        collector.vars.into_names()
+    }
+}
+
+// Truth tables and everything built on them or on the solvers and decision
+// diagrams, which need std
+#[cfg(feature = "std")]
+impl Expr {
+    /// An equal sum of products with as few terms as possible, over the
+    /// variables of the truth table
+    pub fn minimize(&self) -> Result<Expr, TruthError> {
+        Ok(minimize::minimize(&try!(self.truth_table())))
+    }
+
+    /// A sum of products found with the Espresso heuristic, for tables too
+    /// large to minimize exactly
+    pub fn minimize_heuristic(&self) -> Result<Expr, TruthError> {
+        Ok(minimize::minimize_heuristic(&try!(self.truth_table())))
+    }
+
+    /// Every sum of products tied for the fewest terms and literals
+    pub fn minimal_forms(&self) -> Result<Vec<Expr>, TruthError> {
+        Ok(minimize::minimal_forms(&try!(self.truth_table())))
+    }
+
+    /// Clauses over the variables in alphabetical order, followed by any
+    /// variables the method adds
+    pub fn cnf(&self, method: CnfMethod) -> Result<Cnf, TruthError> {
+        let vars = self.sorted_variables();
+        match method {
+            CnfMethod::Distribute => normal::cnf_distribute(self, vars.as_slice()),
+            CnfMethod::Tseitin => normal::cnf_tseitin(self, vars.as_slice())
+        }
+    }
+
+    /// An equivalent AND of ORs of literals
+    pub fn to_cnf(&self) -> Result<Expr, TruthError> {
+        Ok(try!(self.cnf(CnfMethod::Distribute)).to_expr())
+    }
+
+    /// Terms over the variables in alphabetical order
+    pub fn dnf(&self, method: DnfMethod) -> Result<Dnf, TruthError> {
+        match method {
+            DnfMethod::Distribute => normal::dnf_distribute(self, self.sorted_variables().as_slice()),
+            DnfMethod::Canonical => Ok(Dnf::canonical(&try!(self.truth_table()))),
+            DnfMethod::Minimal => {
+                let table = try!(self.truth_table());
+                let primes = minimize::prime_implicants(&table);
+                let covers = minimize::minimal_covers(&table, primes.as_slice());
+                Ok(Dnf::from_implicants(table.variables().to_vec(), covers[0].as_slice()))
+            }
+        }
+    }
+
+    /// An equivalent OR of ANDs of literals
+    pub fn to_dnf(&self) -> Result<Expr, TruthError> {
+        Ok(try!(self.dnf(DnfMethod::Distribute)).to_expr())
+    }
+
+    /// An equivalent expression of ANDs and ORs with negations only on
+    /// variables, see `normal::nnf`
+    pub fn to_nnf(&self) -> Result<Expr, TruthError> {
+        normal::nnf(self)
+    }
+
+    /// The XOR of ANDs equal to the expression, over the variables of the truth table
+    pub fn anf(&self) -> Result<Anf, TruthError> {
+        Ok(Anf::from_table(&try!(self.truth_table())))
+    }
+
+    pub fn to_anf(&self) -> Result<Expr, TruthError> {
+        Ok(try!(self.anf()).to_expr())
+    }
+
+    /// The tree as a Graphviz graph, see `dot::expr_to_dot`
+    pub fn to_dot(&self) -> String {
+        dot::expr_to_dot(self)
+    }
+
+    /// A circuit diagram of the tree as an SVG image, see `svg::expr_to_svg`
+    pub fn to_svg(&self) -> String {
+        svg::expr_to_svg(self)
+    }
+
+    /// Builds a BDD testing the variables in alphabetical order
+    pub fn to_bdd(&self) -> Result<(Bdd, BddRef), TruthError> {
+        let mut bdd = Bdd::new(self.sorted_variables());
+        let root = try!(bdd.build(self));
+        Ok((bdd, root))
+    }
+
+    /// Builds an and-inverter graph with the variables in order of first appearance
+    pub fn to_aig(&self) -> Result<(Aig, AigLit), TruthError> {
+        let mut aig = Aig::new(self.get_variables());
+        let root = try!(aig.build(self));
+        Ok((aig, root))
+    }
+
+    /// Every assignment for which the expression is true, enumerated lazily
+    /// from its BDD with the variables in alphabetical order
+    pub fn models(&self) -> Result<Models, TruthError> {
+        let (bdd, root) = try!(self.to_bdd());
+        Ok(Models::new(bdd, root))
+    }
+
+    /// The number of true rows of the table, counted on the BDD without
+    /// enumerating them. None if it doesn't fit in a u64
+    pub fn model_count(&self) -> Result<Option<u64>, TruthError> {
+        let (bdd, root) = try!(self.to_bdd());
+        Ok(bdd.count(root))
+    }
+
+    /// The probability that the expression is true when each variable is
+    /// independently true with the probability `probs` gives it, or one
+    /// half when it isn't listed. Computed exactly on the BDD
+    pub fn probability(&self, probs: &[(String, f64)]) -> Result<f64, TruthError> {
+        let (bdd, root) = try!(self.to_bdd());
+        let values: Vec<f64> = bdd.variables().iter().map(|var| {
+            probs.iter().find(|&&(ref name, _)| name == var).map_or(0.5, |&(_, prob)| prob)
+        }).collect();
+        Ok(bdd.probability(root, values.as_slice()))
+    }
+
+    /// The sum over the models of the product of the weights of their
+    /// literals, `weights` giving a variable's weights when false and when
+    /// true. Both are 1 for a variable which isn't listed, so with no
+    /// weights this is the model count
+    pub fn weighted_count(&self, weights: &[(String, f64, f64)]) -> Result<f64, TruthError> {
+        let (bdd, root) = try!(self.to_bdd());
+        let values: Vec<(f64, f64)> = bdd.variables().iter().map(|var| {
+            weights.iter().find(|&&(ref name, _, _)| name == var).map_or((1.0, 1.0), |&(_, low, high)| (low, high))
+        }).collect();
+        Ok(bdd.weighted_count(root, values.as_slice()))
+    }
+
+    /// `count` uniformly random models, weighted by the model counts of the
+    /// BDD branches. Empty when the expression is never true
+    pub fn sample(&self, count: uint) -> Result<Vec<Assignment>, TruthError> {
+        let (bdd, root) = try!(self.to_bdd());
+        Ok(bdd.sample(root, count, &mut rand::task_rng()))
+    }
+
+    /// A model found by the SAT solver, None when the expression is never true.
+    /// The assignment lists the variables in alphabetical order
+    pub fn satisfiable(&self) -> Result<Option<Assignment>, TruthError> {
+        let (aig, root) = try!(self.sorted_aig());
+        Ok(Solver::model(&aig, root))
+    }
+
+    /// Classifies by solving for a model of the expression and of its negation
+    pub fn classify(&self) -> Result<Classification, TruthError> {
+        let (aig, root) = try!(self.sorted_aig());
+        Ok(match (Solver::model(&aig, root), Solver::model(&aig, aig.not(root))) {
+            (Some(model), Some(counter)) => Classification::Contingent(model, counter),
+            (Some(_), None) => Classification::Tautology,
+            (None, _) => Classification::Contradiction
+        })
+    }
+
+    /// None when both expressions have the same value for every assignment of
+    /// their variables, found by solving for a model of their XOR
+    pub fn equivalent(&self, other: &Expr) -> Result<Option<Counterexample>, TruthError> {
+        let mut vars = self.get_variables();
+        for var in other.get_variables().into_iter() {
+            if !vars.contains(&var) { vars.push(var) }
+        }
+        vars.sort_by(|a, b| a.cmp(b));
+
+        let mut aig = Aig::new(vars);
+        let left = try!(aig.build(self));
+        let right = try!(aig.build(other));
+        let miter = aig.xor(left, right);
+        Ok(match Solver::model(&aig, miter) {
+            Some(model) => {
+                let values: Vec<u64> = model.values().iter().map(|&val| if val { 1 } else { 0 }).collect();
+                let left = aig.eval_lanes(left, values.as_slice()) & 1 == 1;
+                Some(Counterexample { assignment: model, left: left })
+            },
+            None => None
+        })
+    }
+
+    // An and-inverter graph with the variables in alphabetical order
+    fn sorted_aig(&self) -> Result<(Aig, AigLit), TruthError> {
+        let mut aig = Aig::new(self.sorted_variables());
+        let root = try!(aig.build(self));
+        Ok((aig, root))
+    }
+
+    fn sorted_variables(&self) -> Vec<String> {
+        let mut vars = self.get_variables();
+        vars.sort_by(|a, b| a.cmp(b));
+        vars
+    }
+
+    /// Evaluates every assignment, with the variables in alphabetical order.
+    /// Tables of `PARALLEL_ROWS` rows or more are split across a thread per CPU
+    pub fn truth_table(&self) -> Result<TruthTable, TruthError> {
+        self.truth_table_threads(os::num_cpus())
+    }
+
+    /// `truth_table` using at most `threads` threads
+    pub fn truth_table_threads(&self, threads: uint) -> Result<TruthTable, TruthError> {
+        self.truth_table_over(self.sorted_variables(), threads)
+    }
+
+    /// `truth_table` with the columns in the order of `vars`, which must
+    /// include every variable of the expression once. The first is the most
+    /// significant bit of the row index
+    pub fn truth_table_ordered(&self, vars: Vec<String>) -> Result<TruthTable, TruthError> {
+        try!(self.check_order(vars.as_slice()));
+        self.truth_table_over(vars, os::num_cpus())
+    }
+
+    /// Rows `start..end` of the table over `vars`, in the order of
+    /// `truth_table_ordered`, evaluated 64 at a time as they are needed
+    /// rather than held in memory. `end` is capped at the number of rows
+    pub fn stream(&self, vars: Vec<String>, start: uint, end: uint) -> Result<RowStream, TruthError> {
+        try!(self.check_order(vars.as_slice()));
+        let rows = try!(TruthTable::row_count(vars.len()));
+        let program = try!(Program::compile(self, vars.as_slice()));
+        Ok(RowStream {
+            program: program,
+            vars: vars.len(),
+            next: start,
+            end: cmp::min(end, rows),
+            batch: None
+        })
+    }
+
+    // Every variable of the expression appears in `vars` once
+    fn check_order(&self, vars: &[String]) -> Result<(), TruthError> {
+        match self.get_variables().into_iter().find(|var| !vars.contains(var)) {
+            Some(var) => return Err(TruthError::Eval(
+                format!("Variable missing from the order: {}", var), None
+            )),
+            None => {}
+        }
+        for idx in range(1, vars.len()) {
+            if vars.slice_to(idx).contains(&vars[idx]) {
+                return Err(TruthError::Eval(format!("Repeated variable in the order: {}", vars[idx]), None))
+            }
+        }
+        Ok(())
+    }
+
+    fn truth_table_over(&self, vars: Vec<String>, threads: uint) -> Result<TruthTable, TruthError> {
+        let tests = try!(TruthTable::row_count(vars.len()));
+
+        if threads <= 1 || tests < PARALLEL_ROWS {
+            let result = try!(self.eval_rows(vars.as_slice(), 0, tests));
+            return Ok(TruthTable::new(vars, result))
+        }
+
+        // Chunks are whole batches of 64 rows so no batch is split between threads
+        let batches = (tests + 63) / 64;
+        let chunk = (batches + threads - 1) / threads * 64;
+
+        let shared = Arc::new((self.clone(), vars.clone()));
+        let mut futures = Vec::new();
+        let mut start = 0u;
+        while start < tests {
+            let end = cmp::min(start + chunk, tests);
+            let shared = shared.clone();
+            futures.push(Future::spawn(proc() {
+                let (ref expr, ref vars) = *shared;
+                expr.eval_rows(vars.as_slice(), start, end)
+            }));
+            start = end;
+        }
+
+        let mut result = Vec::with_capacity(tests);
+        for future in futures.iter_mut() {
+            result.push_all(try!(future.get()).as_slice());
+        }
+        Ok(TruthTable::new(vars, result))
+    }
+
+    // Results for rows `start..end` of the table over `vars`, `start` is a multiple of 64
+    fn eval_rows(&self, vars: &[String], start: uint, end: uint) -> Result<Vec<bool>, TruthError> {
+        let mut result = Vec::with_capacity(end - start);
+        // Repeated sub-expressions are only evaluated once per batch
+        let program = try!(Program::compile(self, vars));
+
+        // 64 rows per evaluation, the last batch may be partly unused
+        let mut base = start;
+        while base < end {
+            let lanes: Vec<u64> = range(0, vars.len()).map(|pos| {
+                input_lanes(base, vars.len() - 1 - pos)
+            }).collect();
+            let eval = program.eval_lanes(lanes.as_slice());
+            for bit in range(0, cmp::min(64, end - base)) {
+                result.push((eval >> bit) & 1 == 1);
+            }
+            base += 64;
+        }
+
+        Ok(result)
+    }
+}
+
+/// The row numbers and results of part of a table, from `Expr::stream`
+#[cfg(feature = "std")]
+pub struct RowStream {
+    program: Program,
+    vars:    uint,
+    next:    uint,
+    end:     uint,
+    // The first row of the batch of 64 last evaluated and its results
+    batch:   Option<(uint, u64)>
+}
+
+#[cfg(feature = "std")]
+impl Iterator<(uint, bool)> for RowStream {
+    fn next(&mut self) -> Option<(uint, bool)> {
+        if self.next >= self.end { return None }
+        let base = self.next & !63;
+        let results = match self.batch {
+            Some((start, results)) if start == base => results,
+            _ => {
+                let lanes: Vec<u64> = range(0, self.vars).map(|pos| input_lanes(base, self.vars - 1 - pos)).collect();
+                let results = self.program.eval_lanes(lanes.as_slice());
+                self.batch = Some((base, results));
+                results
+            }
+        };
+        self.next += 1;
+        Some((self.next - 1, (results >> (self.next - 1 - base)) & 1 == 1))
+    }
+}
+
+/// Tables with at least this many rows are generated on several threads
+#[cfg(feature = "std")]
+pub const PARALLEL_ROWS: uint = 1 << 16;
+
+// Lane for the variable at row index bit `shift`, bit `i` of the lane is
+// its value in row `base + i`. `base` is a multiple of 64
+#[cfg(feature = "std")]
+fn input_lanes(base: uint, shift: uint) -> u64 {
+    static PATTERNS: [u64, ..6] = [
+        0xAAAAAAAAAAAAAAAA, 0xCCCCCCCCCCCCCCCC, 0xF0F0F0F0F0F0F0F0,
+        0xFF00FF00FF00FF00, 0xFFFF0000FFFF0000, 0xFFFFFFFF00000000
+    ];
+
+    if shift < 6 {
+        PATTERNS[shift]
+    } else if (base >> shift) & 1 == 1 {
+        !0
+    } else {
+        0
+    }
+}
+
+struct VariableCollector {
+    vars: SymbolTable
+}
+
+impl Visitor for VariableCollector {
+    fn visit_var(&mut self, name: &str) {
+        self.vars.intern(name);
+    }
+}
+
+// Replaces assigned variables with constants
+struct Restriction<'a> {
+    assignment: &'a Assignment
+}
+
+impl<'a> Folder for Restriction<'a> {
+    fn fold_var(&mut self, name: String) -> VarOrExpr {
+        match self.assignment.get(name.as_slice()) {
+            Some(val) => VarOrExpr::Const(val),
+            None => VarOrExpr::Var(name)
+        }
+    }
+}
+
+// Replaces variables with expressions
+struct Substitution<'a> {
+    vars:  &'a [String],
+    exprs: &'a [Expr]
+}
+
+impl<'a> Folder for Substitution<'a> {
+    fn fold_var(&mut self, name: String) -> VarOrExpr {
+        match self.vars.iter().position(|var| *var == name) {
+            Some(pos) => VarOrExpr::Expr(self.exprs[pos].clone()),
+            None => VarOrExpr::Var(name)
+        }
+    }
+}
+
+// Parenthesised groups and negations, innermost first
+struct SubexprCollector {
+    exprs: Vec<Expr>
+}
+
+impl SubexprCollector {
+    fn push(&mut self, expr: Expr) {
+        let label = expr.to_string();
+        if !self.exprs.iter().any(|other| other.to_string() == label) { self.exprs.push(expr) }
+    }
+}
+
+impl Visitor for SubexprCollector {
+    fn visit_component(&mut self, component: &Component) {
+        visit::walk_component(self, component);
+        match component.value {
+            VarOrExpr::Var(_) | VarOrExpr::Const(_) if !component.negated => return,
+            // `!(a & b)` gives a column for `a & b` before its negation
+            VarOrExpr::Expr(ref expr) if component.negated => self.push(expr.clone()),
+            _ => {}
+        }
+        self.push(Expr::from_component(component.clone()));
+    }
+}
+
+impl FromStr for Expr {
+    fn from_str(src: &str) -> Option<Expr> {
+        Expr::parse(src).ok()
+    }
+}
+
+#[deriving(Show)]
+#[cfg_attr(feature = "std", deriving(Encodable))]
+#[cfg_attr(feature = "std", deriving(Decodable))]
+#[deriving(Clone)]
+pub enum VarOrExpr {
+    Var(String),
+    Const(bool),
+    Expr(Expr),
+    // if-then-else, from `cond ? then : else`
+    Ite(Box<Expr>, Box<Expr>, Box<Expr>),
+    // A call of a function defined with `def`, which is expanded before
+    // the expression is evaluated
+    Call(String, Vec<Expr>)
+}
+
+/// The error for evaluating a call which was never expanded
+pub fn undefined_function(name: &str) -> TruthError {
+    TruthError::Eval(format!("Undefined function: {}", name), None)
+}
+
+#[deriving(Show)]
+#[cfg_attr(feature = "std", deriving(Encodable))]
+#[cfg_attr(feature = "std", deriving(Decodable))]
+#[deriving(Clone)]
+pub struct Component {
+    pub value: VarOrExpr,
+    pub negated: bool
+}
+
+impl Component {
+    pub fn eval(&self, env: &Environment) -> Result<bool, TruthError> {
+        let mut val = match self.value {
+            VarOrExpr::Var(ref name) => env.get_variable(name.as_slice()),
+            VarOrExpr::Const(val) => val,
+            VarOrExpr::Expr(ref op) => try!(op.eval(env)),
+            VarOrExpr::Ite(ref cond, ref then, ref other) => {
+                if try!(cond.eval(env)) { try!(then.eval(env)) } else { try!(other.eval(env)) }
+            },
+            VarOrExpr::Call(ref name, _) => return Err(undefined_function(name.as_slice()))
+        };
+        if self.negated { val = !val };
+        Ok(val)
+    }
+
+    pub fn eval_lanes(&self, lanes: &BTreeMap<String, u64>) -> Result<u64, TruthError> {
+        let val = match self.value {
+            VarOrExpr::Var(ref name) => *lanes.get(name).unwrap_or(&0),
+            VarOrExpr::Const(val) => if val { !0 } else { 0 },
+            VarOrExpr::Expr(ref op) => try!(op.eval_lanes(lanes)),
+            VarOrExpr::Ite(ref cond, ref then, ref other) => {
+                let cond = try!(cond.eval_lanes(lanes));
+                (cond & try!(then.eval_lanes(lanes))) | (!cond & try!(other.eval_lanes(lanes)))
+            },
+            VarOrExpr::Call(ref name, _) => return Err(undefined_function(name.as_slice()))
+        };
+        Ok(if self.negated { !val } else { val })
+    }
+}
@@ -0,0 +1,92 @@
+//! A C interface for embedding the engine in C and C++ programs, declared
+//! in `include/truth.h`.
+//!
+//! Expressions are handed out as opaque pointers which the caller frees with
+//! `truth_free`, and strings as C strings freed with `truth_free_string`.
+//! Every function accepts null and fails on it rather than crashing, and
+//! a panic inside the library is caught and returned as a failure, since
+//! unwinding into C is undefined behaviour.
+
+use std::c_str::{CString, ToCStr};
+use std::mem;
+use std::ptr;
+use std::task;
+
+use libc;
+use libc::{c_char, c_int, c_void, size_t};
+
+use env::Assignment;
+use expr::Expr;
+
+/// Parses the NUL terminated `src`. Null when it isn't UTF-8 or doesn't parse
+#[no_mangle]
+pub extern "C" fn truth_parse(src: *const c_char) -> *mut Expr {
+    if src.is_null() { return ptr::null_mut() }
+    let src = match unsafe { CString::new(src, false) }.as_str() {
+        Some(src) => src.to_string(),
+        None => return ptr::null_mut()
+    };
+    match catch(proc() Expr::parse(src.as_slice()).ok()) {
+        Some(Some(expr)) => unsafe { mem::transmute(box expr) },
+        _ => ptr::null_mut()
+    }
+}
+
+/// The value of `expr` with the `count` variables in `names` given the
+/// values in `values`, nonzero being true. 1 or 0, or -1 when a variable of
+/// the expression has no value or evaluating it panics
+#[no_mangle]
+pub extern "C" fn truth_eval(expr: *const Expr, names: *const *const c_char, values: *const c_int,
+                             count: size_t) -> c_int {
+    if expr.is_null() || (count > 0 && (names.is_null() || values.is_null())) { return -1 }
+    let expr = unsafe { &*expr }.clone();
+
+    let mut vars = Vec::new();
+    let mut vals = Vec::new();
+    for idx in range(0, count as uint) {
+        let (name, value) = unsafe { (*names.offset(idx as int), *values.offset(idx as int)) };
+        if name.is_null() { return -1 }
+        match unsafe { CString::new(name, false) }.as_str() {
+            Some(name) => vars.push(name.to_string()),
+            None => return -1
+        }
+        vals.push(value != 0);
+    }
+    match catch(proc() expr.eval_with(&Assignment::new(vars, vals))) {
+        Some(Ok(val)) => val as c_int,
+        _ => -1
+    }
+}
+
+/// The truth table of `expr` as JSON, in the form of `TruthTable::to_json`.
+/// Null when it has too many variables or building the table panics
+#[no_mangle]
+pub extern "C" fn truth_table_json(expr: *const Expr) -> *mut c_char {
+    if expr.is_null() { return ptr::null_mut() }
+    let expr = unsafe { &*expr }.clone();
+    match catch(proc() expr.truth_table().map(|table| table.to_json())) {
+        // The buffer is allocated with malloc, for `truth_free_string`
+        Some(Ok(json)) => json.to_c_str().unwrap() as *mut c_char,
+        _ => ptr::null_mut()
+    }
+}
+
+/// Frees an expression from `truth_parse`
+#[no_mangle]
+pub extern "C" fn truth_free(expr: *mut Expr) {
+    if expr.is_null() { return }
+    let _: Box<Expr> = unsafe { mem::transmute(expr) };
+}
+
+/// Frees a string from `truth_table_json`
+#[no_mangle]
+pub extern "C" fn truth_free_string(string: *mut c_char) {
+    if string.is_null() { return }
+    unsafe { libc::free(string as *mut c_void) }
+}
+
+// Runs `f` in a task of its own, so that a panic ends that task and comes
+// back as None rather than unwinding through the caller's C frames
+fn catch<T: Send>(f: proc(): Send -> T) -> Option<T> {
+    task::try(f).ok()
+}
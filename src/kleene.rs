@@ -0,0 +1,185 @@
+//! Three-valued evaluation with Kleene's strong logic, where a variable may
+//! be `X`, unknown, as well as 0 or 1.
+//!
+//! An operation is `X` only when the known operands don't settle it, so
+//! `0 & X` is 0 and `1 | X` is 1 while `X ^ 1` is `X`. A `?:` with an
+//! unknown condition is known when both branches agree. A known result holds
+//! however the `X`s are filled in, but not every such result is found, as
+//! each operation only sees its own operands: `a | !a` is `X` for an
+//! unknown `a`.
+
+use std::cmp;
+use std::fmt;
+
+use error::{Span, TruthError};
+use expr::{undefined_function, Component, Expr, VarOrExpr};
+use lexer::Type;
+
+/// The most variables of a three-valued table, which has 3^n rows
+pub const MAX_KLEENE_VARS: uint = 12;
+
+/// A truth value which may be unknown, ordered from false to true
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+#[deriving(Eq)]
+#[deriving(PartialOrd)]
+#[deriving(Ord)]
+pub enum Trit {
+    False,
+    Unknown,
+    True
+}
+
+impl Trit {
+    pub fn from_bool(val: bool) -> Trit {
+        if val { Trit::True } else { Trit::False }
+    }
+
+    /// The value when it is known
+    pub fn to_bool(&self) -> Option<bool> {
+        match *self {
+            Trit::False => Some(false),
+            Trit::Unknown => None,
+            Trit::True => Some(true)
+        }
+    }
+
+    /// `0`, `1`, or `X` and `x` for unknown
+    pub fn parse(src: &str) -> Option<Trit> {
+        match src {
+            "0" => Some(Trit::False),
+            "1" => Some(Trit::True),
+            "X" | "x" => Some(Trit::Unknown),
+            _ => None
+        }
+    }
+
+    pub fn not(&self) -> Trit {
+        match *self {
+            Trit::False => Trit::True,
+            Trit::Unknown => Trit::Unknown,
+            Trit::True => Trit::False
+        }
+    }
+
+    pub fn and(&self, other: &Trit) -> Trit {
+        cmp::min(self.clone(), other.clone())
+    }
+
+    pub fn or(&self, other: &Trit) -> Trit {
+        cmp::max(self.clone(), other.clone())
+    }
+
+    pub fn xor(&self, other: &Trit) -> Trit {
+        match (self.to_bool(), other.to_bool()) {
+            (Some(left), Some(right)) => Trit::from_bool(left != right),
+            _ => Trit::Unknown
+        }
+    }
+
+    pub fn iff(&self, other: &Trit) -> Trit {
+        self.xor(other).not()
+    }
+
+    pub fn implies(&self, other: &Trit) -> Trit {
+        self.not().or(other)
+    }
+
+    // The value of a `?:` given this condition
+    fn select(&self, then: Trit, other: Trit) -> Trit {
+        match *self {
+            Trit::True => then,
+            Trit::False => other,
+            Trit::Unknown => if then == other { then } else { Trit::Unknown }
+        }
+    }
+}
+
+impl fmt::Show for Trit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match *self {
+            Trit::False => "0",
+            Trit::Unknown => "X",
+            Trit::True => "1"
+        })
+    }
+}
+
+/// The value of `expr` with `values` given to `vars`. Variables which
+/// aren't in `vars` are unknown
+pub fn eval(expr: &Expr, vars: &[String], values: &[Trit]) -> Result<Trit, TruthError> {
+    assert!(vars.len() == values.len(), "{} values for {} variables", values.len(), vars.len());
+    eval_expr(expr, vars, values)
+}
+
+fn eval_expr(expr: &Expr, vars: &[String], values: &[Trit]) -> Result<Trit, TruthError> {
+    let mut val = Trit::False;
+    if expr.components.len() > 0 {
+        val = try!(eval_component(&expr.components[0], vars, values));
+    }
+
+    for idx in range(1u, expr.components.len()) {
+        let eval = try!(eval_component(&expr.components[idx], vars, values));
+        val = match expr.ops[idx - 1].token_type {
+            Type::And => val.and(&eval),
+            Type::Or => val.or(&eval),
+            Type::Xor => val.xor(&eval),
+            Type::Xnor | Type::Iff => val.iff(&eval),
+            Type::Implies => val.implies(&eval),
+            ref other => {
+                return Err(TruthError::Eval(
+                    format!("Unexpected operation: {}", other),
+                    Some(Span::from_token(&expr.ops[idx - 1])),
+                ))
+            }
+        };
+    }
+
+    Ok(val)
+}
+
+fn eval_component(component: &Component, vars: &[String], values: &[Trit]) -> Result<Trit, TruthError> {
+    let val = match component.value {
+        VarOrExpr::Var(ref name) => match vars.iter().position(|var| var == name) {
+            Some(pos) => values[pos].clone(),
+            None => Trit::Unknown
+        },
+        VarOrExpr::Const(val) => Trit::from_bool(val),
+        VarOrExpr::Expr(ref expr) => try!(eval_expr(expr, vars, values)),
+        VarOrExpr::Ite(ref cond, ref then, ref other) => {
+            let cond = try!(eval_expr(&**cond, vars, values));
+            // Both branches are needed when the condition is unknown
+            match cond.to_bool() {
+                Some(true) => try!(eval_expr(&**then, vars, values)),
+                Some(false) => try!(eval_expr(&**other, vars, values)),
+                None => cond.select(try!(eval_expr(&**then, vars, values)), try!(eval_expr(&**other, vars, values)))
+            }
+        },
+        VarOrExpr::Call(ref name, _) => return Err(undefined_function(name.as_slice()))
+    };
+    Ok(if component.negated { val.not() } else { val })
+}
+
+/// Every row of the three-valued table of `expr` over `vars`, the inputs
+/// counting up in base 3 with 0 < X < 1, the first variable the most
+/// significant. Fails with `Overflow` over `MAX_KLEENE_VARS` variables
+pub fn table(expr: &Expr, vars: &[String]) -> Result<Vec<(Vec<Trit>, Trit)>, TruthError> {
+    if vars.len() > MAX_KLEENE_VARS { return Err(TruthError::Overflow(vars.len())) }
+    let digits = [Trit::False, Trit::Unknown, Trit::True];
+    let mut rows = Vec::new();
+    let mut inputs = Vec::from_elem(vars.len(), 0u);
+    loop {
+        let values: Vec<Trit> = inputs.iter().map(|&digit| digits[digit].clone()).collect();
+        let result = try!(eval(expr, vars, values.as_slice()));
+        rows.push((values, result));
+
+        // The next row, carrying from the last variable
+        match range(0, inputs.len()).rev().find(|&pos| inputs[pos] < 2) {
+            Some(pos) => {
+                inputs[pos] += 1;
+                for digit in inputs.slice_from_mut(pos + 1).iter_mut() { *digit = 0 }
+            },
+            None => return Ok(rows)
+        }
+    }
+}
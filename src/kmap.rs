@@ -0,0 +1,116 @@
+//! Karnaugh maps of small truth tables.
+//!
+//! The first half of the variables, rounded down, select the row and the
+//! rest the column. Both run in Gray code order so neighbouring cells differ
+//! in one variable, wrapping around at the edges.
+
+use std::cmp;
+use std::fmt;
+
+use expr::Expr;
+use minimize::Implicant;
+use table::TruthTable;
+
+/// A table of 2 to 5 variables laid out as a Karnaugh map, with `-` in the
+/// don't care cells. Each group is given a letter which is written in the
+/// cells it covers
+pub struct KarnaughMap<'a> {
+    table:  &'a TruthTable,
+    groups: Vec<Implicant>
+}
+
+impl<'a> KarnaughMap<'a> {
+    /// None unless the table has 2 to 5 variables
+    pub fn new(table: &'a TruthTable) -> Option<KarnaughMap<'a>> {
+        let vars = table.variables().len();
+        if vars < 2 || vars > 5 { return None }
+        Some(KarnaughMap { table: table, groups: Vec::new() })
+    }
+
+    /// Marks the cells of each implicant, such as those of a minimal cover
+    pub fn with_groups(self, groups: Vec<Implicant>) -> KarnaughMap<'a> {
+        KarnaughMap { table: self.table, groups: groups }
+    }
+
+    fn cell(&self, index: uint) -> String {
+        let mut cell = if self.table.is_dont_care(index) {
+            "-".to_string()
+        } else {
+            format!("{}", self.table.row(index).result() as u8)
+        };
+        let letters: String = range(0, self.groups.len())
+            .filter(|&idx| self.groups[idx].covers(index))
+            .map(|idx| group_letter(idx))
+            .collect();
+        if !letters.is_empty() {
+            cell.push(' ');
+            cell.push_str(letters.as_slice());
+        }
+        cell
+    }
+}
+
+impl<'a> fmt::Show for KarnaughMap<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let vars = self.table.variables();
+        let row_vars = vars.len() / 2;
+        let col_vars = vars.len() - row_vars;
+        let rows = gray_code(row_vars);
+        let cols = gray_code(col_vars);
+
+        let corner = format!("{}\\{}", vars.slice_to(row_vars).concat(), vars.slice_from(row_vars).concat());
+        let mut width = col_vars;
+        for &row in rows.iter() {
+            for &col in cols.iter() {
+                width = cmp::max(width, self.cell(row << col_vars | col).as_slice().char_len());
+            }
+        }
+
+        try!(write!(f, "{}", corner));
+        for &col in cols.iter() {
+            try!(write!(f, "  {}", pad(binary(col, col_vars), width)));
+        }
+        try!(write!(f, "\n"));
+
+        for &row in rows.iter() {
+            try!(write!(f, "{}", pad_left(binary(row, row_vars), corner.as_slice().char_len())));
+            for &col in cols.iter() {
+                try!(write!(f, "  {}", pad(self.cell(row << col_vars | col), width)));
+            }
+            try!(write!(f, "\n"));
+        }
+
+        for (idx, group) in self.groups.iter().enumerate() {
+            let product = Expr::from_component(group.to_component(vars));
+            try!(write!(f, "{}: {}\n", group_letter(idx), product));
+        }
+        Ok(())
+    }
+}
+
+// 0, 1, 3, 2, 6, 7, 5, 4, ...
+fn gray_code(bits: uint) -> Vec<uint> {
+    range(0u, 1 << bits).map(|idx| idx ^ (idx >> 1)).collect()
+}
+
+// `value` in `len` binary digits
+fn binary(value: uint, len: uint) -> String {
+    range(0, len).rev().map(|bit| if (value >> bit) & 1 == 1 { '1' } else { '0' }).collect()
+}
+
+fn group_letter(idx: uint) -> char {
+    (b'A' + (idx % 26) as u8) as char
+}
+
+fn pad(string: String, width: uint) -> String {
+    let mut string = string;
+    while string.as_slice().char_len() < width { string.push(' ') }
+    string
+}
+
+fn pad_left(string: String, width: uint) -> String {
+    let mut padded = String::new();
+    for _ in range(string.as_slice().char_len(), width) { padded.push(' ') }
+    padded.push_str(string.as_slice());
+    padded
+}
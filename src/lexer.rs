@@ -0,0 +1,507 @@
+#[cfg(not(feature = "std"))]
+use prelude::*;
+#[cfg(feature = "std")]
+use std::cmp;
+#[cfg(feature = "std")]
+use std::io;
+use std::mem;
+#[cfg(feature = "std")]
+use std::str;
+
+use error::{Span, TruthError};
+use symbol::{Symbol, SymbolTable};
+
+trait IsAlpha {
+    fn is_alpha(self) -> bool;
+}
+
+impl IsAlpha for char {
+    fn is_alpha(self) -> bool {
+        (self >= 'a' && self <= 'z') || (self >= 'A' && self <= 'Z')
+    }    
+}
+
+trait IsIdent {
+    fn is_ident_start(self) -> bool;
+    fn is_ident_char(self) -> bool;
+}
+
+impl IsIdent for char {
+    fn is_ident_start(self) -> bool {
+        self.is_alpha() || self == '_'
+    }
+
+    fn is_ident_char(self) -> bool {
+        self.is_ident_start() || (self >= '0' && self <= '9')
+    }
+}
+
+/// Columns between tab stops unless the lexer is told otherwise, as in
+/// most terminals
+pub const TAB_WIDTH: uint = 8;
+
+/// Characters for the lexer to read one at a time, tracking the line and
+/// display column of the last one read and the byte offset of the next.
+/// None is the end of input. Input which can't be read is an error saying
+/// why, which `read` steps over
+pub trait CharReader {
+    fn peak(&mut self) -> Result<Option<char>, &'static str>;
+    fn read(&mut self) -> Result<Option<char>, &'static str>;
+    fn line(&self) -> uint;
+    fn col(&self) -> uint;
+    fn pos(&self) -> uint;
+    /// Columns between tab stops, for placing what follows a tab
+    fn set_tab_width(&mut self, width: uint);
+}
+
+/// The line and column after reading `c` at `line` and `col`. A tab moves
+/// to the next tab stop, a carriage return takes no column so `\r\n` ends
+/// a line like `\n`, and other characters take one column however many
+/// bytes they are
+fn advance(line: uint, col: uint, c: char, tab_width: uint) -> (uint, uint) {
+    match c {
+        '\n' => (line + 1, 0),
+        '\r' => (line, col),
+        '\t' => (line, (col / tab_width + 1) * tab_width),
+        _ => (line, col + 1)
+    }
+}
+
+/// The display column after `src`, with tab stops every `tab_width`
+/// columns
+pub fn columns(src: &str, tab_width: uint) -> uint {
+    src.chars().fold(0, |col, c| advance(1, col, c, tab_width).val1())
+}
+
+pub struct StringReader {
+    pos:       uint,
+    source:    String,
+    col:       uint,
+    line:      uint,
+    tab_width: uint
+}
+
+impl StringReader {
+    pub fn new(source: String) -> StringReader {
+        StringReader {
+            pos: 0,
+            line: 1,
+            col: 0,
+            source: source,
+            tab_width: TAB_WIDTH
+        }
+    }
+}
+
+impl CharReader for StringReader {
+    fn peak(&mut self) -> Result<Option<char>, &'static str> {
+        if self.pos < self.source.len() {
+            Ok(Some(self.source.as_slice().char_at(self.pos)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn read(&mut self) -> Result<Option<char>, &'static str> {
+        let next = try!(self.peak());
+        match next {
+            Some(c) => {
+                let (line, col) = advance(self.line, self.col, c, self.tab_width);
+                self.line = line;
+                self.col = col;
+                // pos is a byte index, step over the whole character
+                self.pos = self.source.as_slice().char_range_at(self.pos).next;
+            },
+            None => {}
+        }
+        Ok(next)
+    }
+
+    fn line(&self) -> uint { self.line }
+
+    fn col(&self) -> uint { self.col }
+
+    fn pos(&self) -> uint { self.pos }
+
+    fn set_tab_width(&mut self, width: uint) { self.tab_width = width }
+}
+
+/// Reads characters from a buffer as the lexer asks for them, so a large
+/// source never has to be held in memory. Bytes which aren't UTF-8 are an
+/// error taking one column, and reading goes on after them. Any other
+/// read error is the last thing read
+#[cfg(feature = "std")]
+pub struct StreamReader<R> {
+    input:     R,
+    // What `peak` has read ahead and its length in bytes, when it has
+    next:      Option<(Result<Option<char>, &'static str>, uint)>,
+    // Set by a read error which isn't bad UTF-8
+    failed:    bool,
+    pos:       uint,
+    col:       uint,
+    line:      uint,
+    tab_width: uint
+}
+
+#[cfg(feature = "std")]
+impl<R: Buffer> StreamReader<R> {
+    pub fn new(input: R) -> StreamReader<R> {
+        StreamReader { input: input, next: None, failed: false, pos: 0, line: 1, col: 0, tab_width: TAB_WIDTH }
+    }
+
+    // The next character and how many bytes it took. A bad UTF-8 sequence
+    // is read up to the length its first byte gives
+    fn decode(&mut self) -> (Result<Option<char>, &'static str>, uint) {
+        if self.failed { return (Ok(None), 0) }
+        let mut bytes = [0u8, ..4];
+        let mut len = 0u;
+        let mut width = 1u;
+        while len < width {
+            match self.input.read_byte() {
+                Ok(byte) => {
+                    if len == 0 { width = cmp::max(str::utf8_char_width(byte), 1) }
+                    bytes[len] = byte;
+                    len += 1;
+                },
+                Err(ref err) if err.kind == io::EndOfFile && len == 0 => return (Ok(None), 0),
+                // Cut short inside a character
+                Err(ref err) if err.kind == io::EndOfFile => break,
+                Err(err) => {
+                    self.failed = true;
+                    return (Err(err.desc), len)
+                }
+            }
+        }
+        match str::from_utf8(bytes.slice_to(len)) {
+            Some(s) => (Ok(Some(s.char_at(0))), len),
+            _ => (Err("invalid UTF-8"), len)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Buffer> CharReader for StreamReader<R> {
+    fn peak(&mut self) -> Result<Option<char>, &'static str> {
+        if self.next.is_none() {
+            self.next = Some(self.decode());
+        }
+        match self.next {
+            Some((ref next, _)) => next.clone(),
+            None => unreachable!()
+        }
+    }
+
+    fn read(&mut self) -> Result<Option<char>, &'static str> {
+        let (next, len) = match self.next.take() {
+            Some(next) => next,
+            None => self.decode()
+        };
+        self.pos += len;
+        match next {
+            Ok(Some(c)) => {
+                let (line, col) = advance(self.line, self.col, c, self.tab_width);
+                self.line = line;
+                self.col = col;
+            },
+            Ok(None) => {},
+            Err(_) => self.col += 1
+        }
+        next
+    }
+
+    fn line(&self) -> uint { self.line }
+
+    fn col(&self) -> uint { self.col }
+
+    fn pos(&self) -> uint { self.pos }
+
+    fn set_tab_width(&mut self, width: uint) { self.tab_width = width }
+}
+
+pub struct Lexer {
+    reader: Box<CharReader + 'static>,
+    // Textbook notation: single letter identifiers which are ANDed when juxtaposed
+    pub implicit_and: bool,
+    // Whether iterating has reached the end of input or an error
+    done: bool,
+    // Byte offset of the first character of the token being read
+    start: uint,
+    // Identifiers are tokens holding a symbol, so each name is only
+    // copied the first time it is seen
+    symbols: SymbolTable,
+    // The identifier being read, kept to save allocating one per token
+    name: String
+}
+
+impl Lexer {
+    pub fn new(source: String) -> Lexer {
+        Lexer { reader: (box StringReader::new(source)) as Box<CharReader>, implicit_and: false, done: false, start: 0,
+                symbols: SymbolTable::new(), name: String::new() }
+    }
+
+    /// Lexes `input` as the tokens are asked for rather than reading it all first
+    #[cfg(feature = "std")]
+    pub fn from_buffer<R: Buffer + 'static>(input: R) -> Lexer {
+        Lexer { reader: (box StreamReader::new(input)) as Box<CharReader>, implicit_and: false, done: false, start: 0,
+                symbols: SymbolTable::new(), name: String::new() }
+    }
+
+    /// Columns between tab stops in the columns of tokens and errors,
+    /// `TAB_WIDTH` unless set
+    pub fn set_tab_width(&mut self, width: uint) {
+        self.reader.set_tab_width(width);
+    }
+
+    // Single letter identifiers joined by implicit ANDs, see `Parser`
+    pub fn with_implicit_and(source: String) -> Lexer {
+        Lexer { reader: (box StringReader::new(source)) as Box<CharReader>, implicit_and: true, done: false, start: 0,
+                symbols: SymbolTable::new(), name: String::new() }
+    }
+
+    /// The names of the identifiers read so far, by their symbols
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// Moves out the names of the identifiers read so far, leaving the
+    /// lexer with none
+    pub fn take_symbols(&mut self) -> SymbolTable {
+        mem::replace(&mut self.symbols, SymbolTable::new())
+    }
+
+    fn tok(&self, token_type: Type) -> Token {
+        Token { token_type: token_type, col: self.reader.col(), line: self.reader.line(), bytes: (self.start, self.reader.pos()) }
+    }
+
+    pub fn next_token(&mut self) -> Result<Token, TruthError> {
+        loop {
+            self.start = self.reader.pos();
+            let c = match try!(self.read()) {
+                Some(c) => c,
+                None => return Ok(self.tok(Type::EOF))
+            };
+
+            if      c == '(' { return Ok(self.tok(Type::LParen)) }
+            else if c == ')' { return Ok(self.tok(Type::RParen)) }
+            else if c == '&' || c == '*' || c == '∧' { return Ok(self.tok(Type::And)) }
+            else if c == '|' || c == '+' || c == '∨' { return Ok(self.tok(Type::Or)) }
+            else if c == '!' && self.reader.peak() == Ok(Some('^')) {
+                try!(self.read());
+                return Ok(self.tok(Type::Xnor))
+            }
+            else if c == '!' || c == '~' || c == '¬' { return Ok(self.tok(Type::Not)) }
+            else if c == '\'' { return Ok(self.tok(Type::Prime)) }
+            else if c == '^' || c == '⊕' { return Ok(self.tok(Type::Xor)) }
+            else if c == '?' { return Ok(self.tok(Type::Question)) }
+            else if c == ':' { return Ok(self.tok(Type::Colon)) }
+            else if c == ',' { return Ok(self.tok(Type::Comma)) }
+            else if c == '-' { return self.expect_arrow(Type::Implies) }
+            else if c == '→' { return Ok(self.tok(Type::Implies)) }
+            else if c == '↔' { return Ok(self.tok(Type::Iff)) }
+            else if c == '<' {
+                if self.reader.peak() == Ok(Some('-')) {
+                    try!(self.read());
+                    return self.expect_arrow(Type::Iff)
+                }
+                return Err(self.unexpected(c))
+            }
+
+            else if c == '0' || c == '⊥' { return Ok(self.tok(Type::Const(false))) }
+            else if c == '1' || c == '⊤' { return Ok(self.tok(Type::Const(true))) }
+
+            else if c.is_ident_start() { return self.next_ident(c) }
+
+            else if c == '#' { try!(self.skip_line_comment()); continue }
+            else if c == '/' {
+                match self.reader.peak() {
+                    Ok(Some('/')) => try!(self.skip_line_comment()),
+                    Ok(Some('*')) => try!(self.skip_block_comment()),
+                    _ => return Err(self.unexpected(c))
+                }
+                continue
+            }
+
+            else if c == ' ' || c == '\t' || c == '\r' || c == '\n' { continue }
+            else { return Err(self.unexpected(c)) }
+        }
+    }
+
+    // Input which can't be read is left for the next token to report
+    fn skip_line_comment(&mut self) -> Result<(), TruthError> {
+        loop {
+            match self.reader.peak() {
+                Ok(Some('\n')) | Ok(None) | Err(_) => return Ok(()),
+                _ => { try!(self.read()); }
+            }
+        }
+    }
+
+    // Called after the '/' of '/*', reads up to and including the closing '*/'
+    fn skip_block_comment(&mut self) -> Result<(), TruthError> {
+        let line = self.reader.line();
+        let col = self.reader.col();
+        try!(self.read());
+
+        loop {
+            match try!(self.read()) {
+                Some('*') => {
+                    if self.reader.peak() == Ok(Some('/')) {
+                        try!(self.read());
+                        return Ok(())
+                    }
+                },
+                Some(_) => {},
+                None => return Err(TruthError::Lex(
+                    "Unterminated block comment".to_string(), Span::new(line, col, col + 1).with_bytes(self.start, self.start + 2)
+                ))
+            }
+        }
+    }
+
+    // Consumes the '>' that ends '->' or '<->'
+    fn expect_arrow(&mut self, token_type: Type) -> Result<Token, TruthError> {
+        match try!(self.read()) {
+            Some('>') => Ok(self.tok(token_type)),
+            Some(c) => Err(self.unexpected(c)),
+            None => Err(TruthError::Lex(
+                "Unexpected end of input".to_string(), self.span()
+            ))
+        }
+    }
+
+    // The next character, failing on input the reader couldn't read once
+    // it has stepped over it
+    fn read(&mut self) -> Result<Option<char>, TruthError> {
+        let pos = self.reader.pos();
+        match self.reader.read() {
+            Ok(c) => Ok(c),
+            Err(err) => {
+                let (line, col) = (self.reader.line(), self.reader.col());
+                Err(TruthError::Lex(
+                    format!("Unreadable input: {}", err), Span::new(line, col, col).with_bytes(pos, self.reader.pos())
+                ))
+            }
+        }
+    }
+
+    fn unexpected(&self, c: char) -> TruthError {
+        TruthError::Lex(format!("Unexpected character: {}", c), self.span())
+    }
+
+    fn span(&self) -> Span {
+        Span::new(self.reader.line(), self.reader.col(), self.reader.col()).with_bytes(self.start, self.reader.pos())
+    }
+
+    fn next_ident(&mut self, current: char) -> Result<Token, TruthError> {
+        self.name.truncate(0);
+        self.name.push(current);
+
+        loop {
+            if self.implicit_and { break }
+            match self.reader.peak() {
+                Ok(Some(c)) if c.is_ident_char() => {
+                    self.name.push(c);
+                    try!(self.read());
+                },
+                _ => break
+            }
+        }
+
+        let token_type = match self.name.as_slice() {
+            "XNOR" => Type::Xnor,
+            "true" | "T" => Type::Const(true),
+            "false" | "F" => Type::Const(false),
+            name => Type::Ident(self.symbols.intern(name))
+        };
+        Ok(self.tok(token_type))
+    }
+}
+
+/// The tokens up to the end of input, ending after the first error
+impl Iterator<Result<Token, TruthError>> for Lexer {
+    fn next(&mut self) -> Option<Result<Token, TruthError>> {
+        if self.done { return None }
+        let token = self.next_token();
+        match token {
+            Ok(Token { token_type: Type::EOF, .. }) => {
+                self.done = true;
+                None
+            },
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            },
+            token => Some(token)
+        }
+    }
+}
+
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+#[cfg_attr(feature = "std", deriving(Encodable))]
+#[cfg_attr(feature = "std", deriving(Decodable))]
+pub enum Type {
+    LParen,
+    RParen,
+
+    /// A name, by its symbol in the lexer's `symbols`
+    Ident(Symbol),
+    Const(bool),
+
+    And,
+    Or,
+    Not,
+    Prime,
+    Xor,
+    Xnor,
+    Implies,
+    Iff,
+
+    Question,
+    Colon,
+    // Between the arguments of a call
+    Comma,
+
+    EOF
+}
+
+impl Type {
+    // Binding strength of binary operators, higher binds tighter
+    pub fn precedence(&self) -> Option<uint> {
+        match *self {
+            Type::And => Some(5),
+            Type::Xor | Type::Xnor => Some(4),
+            Type::Or => Some(3),
+            Type::Implies => Some(2),
+            Type::Iff => Some(1),
+            _ => None
+        }
+    }
+
+    pub fn starts_component(&self) -> bool {
+        match *self {
+            Type::Ident(_) | Type::Const(_) | Type::LParen | Type::Not => true,
+            _ => false
+        }
+    }
+
+    pub fn is_right_assoc(&self) -> bool {
+        match *self {
+            Type::Implies => true,
+            _ => false
+        }
+    }
+}
+
+#[deriving(Clone)]
+#[deriving(Show)]
+#[cfg_attr(feature = "std", deriving(Encodable))]
+#[cfg_attr(feature = "std", deriving(Decodable))]
+pub struct Token {
+    pub token_type: Type,
+    pub col:        uint,
+    pub line:       uint,
+    /// Byte offsets into the source of the start and the end, exclusive
+    pub bytes:      (uint, uint)
+}
@@ -0,0 +1,137 @@
+//! A boolean expression parser and evaluator.
+//!
+//! Without its default `std` feature the crate is `no_std`, needing only an
+//! allocator, and holds just the lexer, parser and evaluators.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(not(feature = "std"), feature(globs, macro_rules, phase))]
+
+#[cfg(not(feature = "std"))]
+#[phase(plugin, link)]
+extern crate core;
+#[cfg(not(feature = "std"))]
+#[phase(plugin, link)]
+extern crate collections;
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+extern crate libc;
+#[cfg(feature = "std")]
+extern crate serialize;
+
+pub use bytecode::{Instr, Program};
+pub use dag::{Dag, Node, NodeId};
+pub use display::{Display, Style};
+pub use env::{Assignment, Bindings, Environment, EnvironmentImpl, Functions};
+pub use error::{Span, TruthError};
+pub use expr::{Component, Expr, VarOrExpr};
+pub use lexer::{columns, CharReader, Lexer, StringReader, Token, Type, TAB_WIDTH};
+pub use parser::{Parser, DEFAULT_MAX_DEPTH};
+pub use simplify::{Law, Step};
+pub use symbol::{Symbol, SymbolTable};
+pub use visit::{Folder, Visitor};
+
+#[cfg(feature = "std")]
+pub use aig::{Aig, AigLit, AigNode};
+#[cfg(feature = "std")]
+pub use bdd::{Bdd, BddOp, BddRef, Cubes, Models};
+#[cfg(feature = "std")]
+pub use expr::{RowStream, PARALLEL_ROWS};
+#[cfg(feature = "std")]
+pub use kmap::KarnaughMap;
+#[cfg(feature = "std")]
+pub use lexer::StreamReader;
+#[cfg(feature = "std")]
+pub use minimize::Implicant;
+#[cfg(feature = "std")]
+pub use normal::{Anf, Cnf, CnfMethod, Dnf, DnfMethod, Literal};
+#[cfg(feature = "std")]
+pub use notation::{TermKind, TermList};
+#[cfg(feature = "std")]
+pub use sat::{Classification, Counterexample, SatLit, Solver};
+#[cfg(feature = "std")]
+pub use table::{Row, Rows, TruthTable, MAX_TABLE_VARS};
+
+// Without std, `format!` writes into a `String` from collections
+#[cfg(not(feature = "std"))]
+macro_rules! format(
+    ($($arg:tt)*) => ({
+        let mut out = ::collections::String::new();
+        let _ = write!(&mut out, $($arg)*);
+        out
+    })
+)
+
+// Without std, the paths the core modules use from it lead to core,
+// alloc and collections instead
+#[cfg(not(feature = "std"))]
+mod std {
+    pub use core::{clone, cmp, fmt, hash, iter, kinds, mem, option, result};
+    pub use collections::{slice, str, string, vec};
+
+    pub mod collections {
+        pub use collections::BTreeMap;
+    }
+}
+
+// What the std prelude gives the core modules, imported by each of them
+#[cfg(not(feature = "std"))]
+mod prelude {
+    pub use core::prelude::*;
+    pub use core::fmt::FormatWriter;
+    pub use alloc::boxed::Box;
+    pub use collections::{String, Vec};
+    pub use collections::slice::CloneSliceAllocPrelude;
+    pub use collections::str::{StrAllocating, StrVector, UnicodeStrPrelude};
+    pub use collections::string::ToString;
+}
+
+mod bytecode;
+mod dag;
+mod display;
+mod env;
+mod error;
+mod expr;
+mod lexer;
+mod parser;
+mod symbol;
+
+pub mod simplify;
+pub mod visit;
+
+#[cfg(feature = "std")]
+mod kmap;
+#[cfg(feature = "std")]
+mod notation;
+#[cfg(feature = "std")]
+mod sat;
+#[cfg(feature = "std")]
+mod table;
+
+#[cfg(feature = "std")]
+pub mod aig;
+#[cfg(feature = "std")]
+pub mod arbitrary;
+#[cfg(feature = "std")]
+pub mod bdd;
+#[cfg(feature = "std")]
+pub mod dot;
+#[cfg(feature = "std")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod kleene;
+#[cfg(feature = "std")]
+pub mod lint;
+#[cfg(feature = "std")]
+pub mod minimize;
+#[cfg(feature = "std")]
+pub mod normal;
+#[cfg(feature = "std")]
+pub mod npn;
+#[cfg(feature = "std")]
+pub mod post;
+#[cfg(feature = "std")]
+pub mod spectral;
+#[cfg(feature = "std")]
+pub mod svg;
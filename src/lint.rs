@@ -0,0 +1,191 @@
+//! Warnings about expressions which are valid but could be written more
+//! simply.
+//!
+//! The lints look at the tree as parsed, where the parser has already folded
+//! `!!a` into `a`, so only a double negation written around a group, as in
+//! `!(!a)`, is caught.
+//!
+//! `lint` only looks at how the expression is written. `lint_all` also
+//! finds constant parts and unused variables, which takes a call of the SAT
+//! solver for every part and every variable.
+
+use std::fmt;
+
+use display::Style;
+use env::Assignment;
+use error::TruthError;
+use expr::{Component, Expr, VarOrExpr};
+use lexer::Type;
+use sat::Classification;
+use visit::{Visitor, walk_component, walk_expr};
+
+#[deriving(Clone)]
+pub enum Warning {
+    /// A negated group holding just a negation, as in `!(!a)`
+    DoubleNegation(Expr),
+    /// Parentheses around a single operand, around the whole expression, or
+    /// around a run of the associative operator they are in, as in `a & (b & c)`
+    RedundantParens(Expr),
+    /// A part with the same value for every assignment of its variables, as
+    /// in `a & !a`
+    Constant(Expr, bool),
+    /// A variable the value of the expression doesn't depend on
+    Irrelevant(String)
+}
+
+impl fmt::Show for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Warning::DoubleNegation(ref expr) => {
+                write!(f, "double negation: `{}` is the same without either negation", expr.display(Style::Native))
+            },
+            Warning::RedundantParens(ref expr) => {
+                write!(f, "redundant parentheses: `{}` means the same without them", expr.display(Style::Native))
+            },
+            Warning::Constant(ref expr, val) => {
+                write!(f, "constant: `{}` is always {}", expr.display(Style::Native), if val { "true" } else { "false" })
+            },
+            Warning::Irrelevant(ref name) => write!(f, "unused variable: the value never depends on `{}`", name)
+        }
+    }
+}
+
+/// The double negations and redundant parentheses in `expr`, outermost first
+pub fn lint(expr: &Expr) -> Vec<Warning> {
+    let mut lints = Lints { warnings: outer_parens(expr), constants: false, error: None };
+    lints.visit_expr(expr);
+    lints.warnings
+}
+
+/// Every warning about `expr`, outermost first. Fails when the expression
+/// can't be evaluated, as with a call which hasn't been expanded
+pub fn lint_all(expr: &Expr) -> Result<Vec<Warning>, TruthError> {
+    let mut warnings = outer_parens(expr);
+
+    // A constant expression makes every part of it and every variable moot
+    let vars = expr.get_variables();
+    let constant = match try!(expr.classify()) {
+        Classification::Tautology => Some(true),
+        Classification::Contradiction => Some(false),
+        Classification::Contingent(..) => None
+    };
+
+    match constant {
+        Some(val) if !vars.is_empty() => warnings.push(Warning::Constant(expr.clone(), val)),
+        _ => {}
+    }
+
+    let mut lints = Lints { warnings: warnings, constants: constant.is_none(), error: None };
+    lints.visit_expr(expr);
+    match lints.error {
+        Some(err) => return Err(err),
+        None => {}
+    }
+    let mut warnings = lints.warnings;
+
+    if constant.is_some() { return Ok(warnings) }
+    for var in vars.into_iter() {
+        let low = expr.restrict(&Assignment::new(vec!(var.clone()), vec!(false)));
+        let high = expr.restrict(&Assignment::new(vec!(var.clone()), vec!(true)));
+        if try!(low.equivalent(&high)).is_none() { warnings.push(Warning::Irrelevant(var)) }
+    }
+    Ok(warnings)
+}
+
+// Parentheses around the whole expression
+fn outer_parens(expr: &Expr) -> Vec<Warning> {
+    match expr.components.as_slice() {
+        [Component { value: VarOrExpr::Expr(ref inner), negated: false }] if !inner.ops.is_empty() => {
+            vec!(Warning::RedundantParens(expr.clone()))
+        },
+        _ => vec!()
+    }
+}
+
+struct Lints {
+    warnings:  Vec<Warning>,
+    // Whether parts are checked for being constant, off inside a part
+    // already reported as constant
+    constants: bool,
+    // The first error classifying a part
+    error:     Option<TruthError>
+}
+
+impl Visitor for Lints {
+    fn visit_expr(&mut self, expr: &Expr) {
+        for (idx, component) in expr.components.iter().enumerate() {
+            let inner = match *component {
+                Component { value: VarOrExpr::Expr(ref inner), negated: false } => inner,
+                _ => continue
+            };
+            // The operators on either side of the group and inside it are all the same
+            let op = match inner.ops.get(0) {
+                Some(op) if is_associative(&op.token_type) => op.token_type.clone(),
+                _ => continue
+            };
+            let mut sides = Vec::new();
+            if idx > 0 { sides.push(&expr.ops[idx - 1]) }
+            sides.extend(expr.ops.get(idx).into_iter());
+            if !sides.is_empty() && sides.iter().all(|tok| tok.token_type == op)
+                && inner.ops.iter().all(|tok| tok.token_type == op) {
+                self.warnings.push(Warning::RedundantParens(Expr { components: vec!(component.clone()), ops: vec!() }));
+            }
+        }
+        walk_expr(self, expr)
+    }
+
+    fn visit_component(&mut self, component: &Component) {
+        let inner = match component.value {
+            VarOrExpr::Expr(ref inner) => inner,
+            _ => return walk_component(self, component)
+        };
+        let whole = Expr { components: vec!(component.clone()), ops: vec!() };
+
+        if inner.ops.is_empty() && inner.components.len() == 1 {
+            let only = &inner.components[0];
+            if component.negated && only.negated {
+                self.warnings.push(Warning::DoubleNegation(whole));
+            } else if is_atom(only) {
+                self.warnings.push(Warning::RedundantParens(whole));
+            }
+            return walk_component(self, component)
+        }
+
+        if !self.constants || whole.get_variables().is_empty() { return walk_component(self, component) }
+        let constant = match whole.classify() {
+            Ok(Classification::Tautology) => Some(true),
+            Ok(Classification::Contradiction) => Some(false),
+            Ok(Classification::Contingent(..)) => None,
+            Err(err) => {
+                if self.error.is_none() { self.error = Some(err) }
+                None
+            }
+        };
+        match constant {
+            Some(val) => {
+                self.warnings.push(Warning::Constant(whole, val));
+                self.constants = false;
+                walk_component(self, component);
+                self.constants = true;
+            },
+            None => walk_component(self, component)
+        }
+    }
+}
+
+// Operators which give the same value however a run of them is grouped
+fn is_associative(op: &Type) -> bool {
+    match *op {
+        Type::And | Type::Or | Type::Xor => true,
+        _ => false
+    }
+}
+
+// Operands which need no parentheses, negated or not. An if-then-else does
+// when negated, `!s ? a : b` negating just the condition
+fn is_atom(component: &Component) -> bool {
+    match component.value {
+        VarOrExpr::Ite(..) => false,
+        _ => true
+    }
+}
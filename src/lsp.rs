@@ -0,0 +1,272 @@
+//! A language server for `.truth` files, spoken over stdin and stdout.
+//!
+//! Each line of a file is read like a line of the REPL: one or more
+//! expressions separated by `;`, a `let` or a `def`. The server reports every
+//! lex and parse error, shows the truth table of the parenthesised
+//! group under the cursor on hover, and formats a file by printing each
+//! expression back in the native style.
+
+use std::io::BufferedReader;
+use std::io::stdio;
+
+use serialize::json;
+use serialize::json::Json;
+
+use truth::{Expr, Style, TruthError};
+
+use super::{parse, parse_all, parse_def, parse_let};
+
+// Hover shows just the variables of larger groups
+const HOVER_VARS: uint = 6;
+
+const CAPABILITIES: &'static str =
+    "{\"capabilities\": {\"textDocumentSync\": 1, \"hoverProvider\": true, \"documentFormattingProvider\": true}}";
+
+// The text of each open document by URI
+struct Server {
+    documents: Vec<(String, String)>
+}
+
+/// Serves requests until the client sends `exit` or closes stdin
+pub fn run() {
+    let mut input = BufferedReader::new(stdio::stdin_raw());
+    let mut server = Server { documents: Vec::new() };
+    loop {
+        let request = match read_message(&mut input).map(|body| json::from_str(body.as_slice())) {
+            Some(Ok(request)) => request,
+            Some(Err(_)) => continue,
+            None => break
+        };
+        let method = request.find("method").and_then(|method| method.as_string()).unwrap_or("");
+        let params = request.find("params");
+        let result = match method {
+            "initialize" => Some(CAPABILITIES.to_string()),
+            "shutdown" => Some("null".to_string()),
+            "exit" => break,
+            "textDocument/didOpen" => {
+                let text = params.and_then(|params| params.find_path(&["textDocument", "text"]))
+                    .and_then(|text| text.as_string()).unwrap_or("");
+                server.update(params, text.to_string());
+                None
+            },
+            // Whole documents are synced, so the last change holds all of it
+            "textDocument/didChange" => {
+                let text = params.and_then(|params| params.find("contentChanges"))
+                    .and_then(|changes| changes.as_array()).and_then(|changes| changes.last())
+                    .and_then(|change| change.find("text")).and_then(|text| text.as_string()).unwrap_or("");
+                server.update(params, text.to_string());
+                None
+            },
+            "textDocument/didClose" => {
+                server.update(params, String::new());
+                None
+            },
+            "textDocument/hover" => Some(server.hover(params)),
+            "textDocument/formatting" => Some(server.format(params)),
+            _ => None
+        };
+
+        match (request.find("id"), result) {
+            (Some(id), Some(result)) => {
+                send(format!("{{\"jsonrpc\": \"2.0\", \"id\": {}, \"result\": {}}}", id, result).as_slice());
+            },
+            (Some(id), None) => {
+                send(format!("{{\"jsonrpc\": \"2.0\", \"id\": {}, \"error\": {{\"code\": -32601, \"message\": {}}}}}",
+                             id, json::encode(&format!("Unknown method: {}", method))).as_slice());
+            },
+            (None, _) => {}
+        }
+    }
+}
+
+impl Server {
+    fn text(&self, uri: &str) -> &str {
+        match self.documents.iter().find(|&&(ref doc, _)| doc.as_slice() == uri) {
+            Some(&(_, ref text)) => text.as_slice(),
+            None => ""
+        }
+    }
+
+    // Stores the new text of the document named by `params` and publishes
+    // its diagnostics
+    fn update(&mut self, params: Option<&Json>, text: String) {
+        let uri = uri(params);
+        let diagnostics = diagnostics(text.as_slice());
+        match self.documents.iter().position(|&(ref doc, _)| *doc == uri) {
+            Some(pos) => self.documents[pos] = (uri.clone(), text),
+            None => self.documents.push((uri.clone(), text))
+        }
+        send(format!("{{\"jsonrpc\": \"2.0\", \"method\": \"textDocument/publishDiagnostics\", \
+                      \"params\": {{\"uri\": {}, \"diagnostics\": [{}]}}}}",
+                     json::encode(&uri), diagnostics.connect(", ")).as_slice());
+    }
+
+    // The table of the innermost group around the cursor, or of the whole
+    // expression when the cursor isn't in one
+    fn hover(&self, params: Option<&Json>) -> String {
+        let (line_no, character) = (position(params, "line"), position(params, "character"));
+        let line = match self.text(uri(params).as_slice()).lines_any().nth(line_no) {
+            Some(line) => line,
+            None => return "null".to_string()
+        };
+        let src = match group_at(line, character) {
+            Some(group) => group,
+            None => match line_exprs(line).iter().find(|&&(offset, src)| {
+                let start = line.slice_to(offset).char_len();
+                start <= character && character <= start + src.char_len()
+            }) {
+                Some(&(_, src)) => src.to_string(),
+                None => return "null".to_string()
+            }
+        };
+        let expr = match parse(src, false) {
+            Ok(expr) => expr,
+            Err(_) => return "null".to_string()
+        };
+
+        let vars = expr.get_variables();
+        let value = if vars.len() > HOVER_VARS {
+            format!("```\n{}\n```\n{} variables: {}", expr.display(Style::Native), vars.len(), vars.connect(", "))
+        } else {
+            match expr.truth_table() {
+                Ok(table) => format!("```\n{}\n\n{}```", expr.display(Style::Native), table),
+                Err(_) => return "null".to_string()
+            }
+        };
+        format!("{{\"contents\": {{\"kind\": \"markdown\", \"value\": {}}}}}", json::encode(&value))
+    }
+
+    // A single edit replacing the document with each line formatted,
+    // keeping its line endings
+    fn format(&self, params: Option<&Json>) -> String {
+        let text = self.text(uri(params).as_slice());
+        let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        let lines: Vec<String> = text.lines_any().map(|line| format_line(line).unwrap_or(line.to_string())).collect();
+        let mut formatted = lines.connect(newline);
+        if text.ends_with("\n") { formatted.push_str(newline) }
+        format!("[{{\"range\": {{\"start\": {{\"line\": 0, \"character\": 0}}, \
+                 \"end\": {{\"line\": {}, \"character\": 0}}}}, \"newText\": {}}}]",
+                text.lines_any().count() + 1, json::encode(&formatted))
+    }
+}
+
+fn uri(params: Option<&Json>) -> String {
+    params.and_then(|params| params.find_path(&["textDocument", "uri"]))
+        .and_then(|uri| uri.as_string()).unwrap_or("").to_string()
+}
+
+fn position(params: Option<&Json>, key: &str) -> uint {
+    params.and_then(|params| params.find_path(&["position", key])).and_then(|pos| pos.as_u64()).unwrap_or(0) as uint
+}
+
+// The body of the next message, after its headers. None at the end of input
+fn read_message<R: Buffer>(input: &mut R) -> Option<String> {
+    let mut length = None;
+    loop {
+        let line = match input.read_line() {
+            Ok(line) => line,
+            Err(_) => return None
+        };
+        let line = line.as_slice().trim();
+        if line.is_empty() { break }
+        if line.starts_with("Content-Length:") {
+            length = from_str::<uint>(line.slice_from("Content-Length:".len()).trim());
+        }
+    }
+    length.and_then(|length| input.read_exact(length).ok()).and_then(|body| String::from_utf8(body).ok())
+}
+
+fn send(body: &str) {
+    print!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+    stdio::flush();
+}
+
+// The expressions of a line with their byte offsets: the right hand side of
+// a `let` or `def`, or each expression separated by `;`. Comments and
+// REPL commands have none
+fn line_exprs(line: &str) -> Vec<(uint, &str)> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with("#") || trimmed.starts_with("//") || trimmed.starts_with(":") {
+        return Vec::new()
+    }
+    match parse_def(line).map(|(_, _, pos)| pos).or(parse_let(line).map(|(_, pos)| pos)) {
+        Some(pos) => return vec!((pos, line.slice_from(pos))),
+        None => {}
+    }
+    let mut exprs = Vec::new();
+    let mut start = 0;
+    for part in line.split(';') {
+        if !part.trim().is_empty() { exprs.push((start, part)) }
+        start += part.len() + 1;
+    }
+    exprs
+}
+
+// An LSP diagnostic for each lex or parse error in the expressions of `text`
+fn diagnostics(text: &str) -> Vec<String> {
+    let mut diagnostics = Vec::new();
+    for (line_no, line) in text.lines_any().enumerate() {
+        for &(offset, src) in line_exprs(line).iter() {
+            let errs: Vec<TruthError> = match parse_all(src.to_string(), false) {
+                Ok(_) => continue,
+                Err(errs) => errs
+            };
+            for err in errs.iter() {
+                // Byte offsets give the characters whatever the line holds,
+                // errors without them cover the whole expression
+                let (start, end) = err.span().and_then(|span| span.byte_range).unwrap_or((0, src.len()));
+                diagnostics.push(format!(
+                    "{{\"range\": {{\"start\": {{\"line\": {line}, \"character\": {start}}}, \
+                     \"end\": {{\"line\": {line}, \"character\": {end}}}}}, \
+                     \"severity\": 1, \"source\": \"truth\", \"message\": {message}}}",
+                    line = line_no, start = line.slice_to(offset + start).char_len(),
+                    end = line.slice_to(offset + end).char_len(), message = json::encode(&err.message())
+                ));
+            }
+        }
+    }
+    diagnostics
+}
+
+// The source inside the innermost pair of parentheses around `character`,
+// the first pair to close being the innermost
+fn group_at(line: &str, character: uint) -> Option<String> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut opens = Vec::new();
+    for (pos, &c) in chars.iter().enumerate() {
+        match c {
+            '(' => opens.push(pos),
+            ')' => match opens.pop() {
+                Some(open) if open <= character && character <= pos => {
+                    return Some(String::from_chars(chars.slice(open + 1, pos)))
+                },
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    None
+}
+
+// The line with its expressions printed in the native style, None when it
+// is left alone: when it fails to parse, or has comments which printing
+// would drop
+fn format_line(line: &str) -> Option<String> {
+    if line.contains("#") || line.contains("//") || line.contains("/*") { return None }
+    let exprs = line_exprs(line);
+    if exprs.is_empty() { return None }
+
+    let mut printed = Vec::new();
+    for &(_, src) in exprs.iter() {
+        let expr: Expr = match parse(src.to_string(), false) {
+            Ok(expr) => expr,
+            Err(_) => return None
+        };
+        printed.push(format!("{}", expr.display(Style::Native)));
+    }
+    // `let` and `def` keep everything up to the `=`
+    match parse_def(line).map(|(_, _, pos)| pos).or(parse_let(line).map(|(_, pos)| pos)) {
+        Some(pos) => Some(format!("{} {}", line.slice_to(pos).trim(), printed[0])),
+        None => Some(printed.connect("; "))
+    }
+}
@@ -1,16 +1,30 @@
-use std::collections::HashMap;
+extern crate libc;
+extern crate serialize;
+extern crate time;
+extern crate truth;
 
+use std::cmp;
+use std::io::{BufferedReader, File};
+use std::mem;
 use std::num::Int;
+use std::uint;
 
-trait IsAlpha {
-    fn is_alpha(self) -> bool;
-}
+use serialize::json;
 
-impl IsAlpha for char {
-    fn is_alpha(self) -> bool {
-        (self >= 'a' && self <= 'z') || (self >= 'A' && self <= 'Z')
-    }    
-}
+use truth::{Classification, Cnf, CnfMethod, Dnf, DnfMethod, Expr, KarnaughMap, Lexer, Parser, Style, TermList};
+use truth::{columns, Assignment, Bindings, Functions, TruthError, TruthTable, MAX_TABLE_VARS, TAB_WIDTH};
+use truth::kleene;
+use truth::kleene::Trit;
+use truth::lint;
+use truth::minimize;
+use truth::npn;
+use truth::post;
+use truth::spectral;
+
+use editor::Editor;
+
+mod editor;
+mod lsp;
 
 trait RepeatChar {
     fn repeat(self, times: uint) -> String;
@@ -25,393 +39,1818 @@ impl RepeatChar for char {
     }
 }
 
-struct StringReader {
-    pos:    uint,
-    source: String,
-    col:    uint,
-    line:   uint
+// Output formats other than the table
+enum Format {
+    Dimacs,
+    Pla,
+    Dot,
+    // The decision diagram instead of the tree
+    BddDot,
+    Svg,
+    Json,
+    Csv,
+    Tsv,
+    Latex,
+    Html,
+    Sexpr
 }
 
-impl StringReader {
-    fn new(source: String) -> StringReader {
-        StringReader {
-            pos: 0,
-            line: 1,
-            col: 0,
-            source: source
+impl Format {
+    fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "dimacs" => Some(Format::Dimacs),
+            "pla" => Some(Format::Pla),
+            "dot" => Some(Format::Dot),
+            "bdd" => Some(Format::BddDot),
+            "svg" => Some(Format::Svg),
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            "tsv" => Some(Format::Tsv),
+            "latex" => Some(Format::Latex),
+            "html" => Some(Format::Html),
+            "sexpr" => Some(Format::Sexpr),
+            _ => None
         }
     }
+}
+
+// The order of the columns of a table, the first being the most significant
+enum Order {
+    Alphabetical,
+    Appearance,
+    Given(Vec<String>)
+}
+
+// Which rows of a printed table to show
+struct Filter {
+    result: Option<bool>,
+    inputs: Vec<(String, bool)>
+}
+
+impl Filter {
+    // Whether row `index` of a table over `vars` giving `result` is shown
+    fn matches(&self, vars: &[String], index: uint, result: bool) -> bool {
+        self.result.map_or(true, |expected| result == expected) && self.inputs.iter().all(|&(ref name, value)| {
+            match vars.iter().position(|var| var == name) {
+                Some(pos) => ((index >> (vars.len() - 1 - pos)) & 1 == 1) == value,
+                None => false
+            }
+        })
+    }
+}
+
+// `a=1,b=0` as pairs of names and values
+fn parse_assignments(src: &str) -> Option<Vec<(String, bool)>> {
+    parse_values(src).and_then(|values| {
+        values.into_iter().map(|(name, value)| value.to_bool().map(|value| (name, value))).collect()
+    })
+}
 
-    fn peak(&mut self) -> Option<char> {
-        if self.pos < self.source.len() {
-            Some(self.source.as_slice().char_at(self.pos))
+// `a=1,b=0,c=X`, where a value may also be unknown
+fn parse_values(src: &str) -> Option<Vec<(String, Trit)>> {
+    src.split(',').map(|pair| {
+        let parts: Vec<&str> = pair.split('=').map(|part| part.trim()).collect();
+        match parts.as_slice() {
+            [name, value] if !name.is_empty() => Trit::parse(value).map(|value| (name.to_string(), value)),
+            _ => None
+        }
+    }).collect()
+}
+
+// `a=0.3,b=0.5` as pairs of names and probabilities, each from 0 to 1
+fn parse_probabilities(src: &str) -> Option<Vec<(String, f64)>> {
+    src.split(',').map(|pair| {
+        let parts: Vec<&str> = pair.split('=').map(|part| part.trim()).collect();
+        match parts.as_slice() {
+            [name, prob] if !name.is_empty() => match from_str::<f64>(prob) {
+                Some(prob) if prob >= 0.0 && prob <= 1.0 => Some((name.to_string(), prob)),
+                _ => None
+            },
+            _ => None
+        }
+    }).collect()
+}
+
+// `a=0.3,!a=0.7,b=2` as the weights of each variable false and true, 1 for
+// a literal which isn't listed
+fn parse_weights(src: &str) -> Option<Vec<(String, f64, f64)>> {
+    let parsed: Option<Vec<(String, bool, f64)>> = src.split(',').map(|pair| {
+        let parts: Vec<&str> = pair.split('=').map(|part| part.trim()).collect();
+        match parts.as_slice() {
+            [literal, weight] => {
+                let (name, negated) = if literal.starts_with("!") { (literal.slice_from(1), true) } else { (literal, false) };
+                match from_str::<f64>(weight) {
+                    Some(weight) if weight >= 0.0 && !name.is_empty() => Some((name.to_string(), negated, weight)),
+                    _ => None
+                }
+            },
+            _ => None
+        }
+    }).collect();
+    let literals = match parsed {
+        Some(literals) => literals,
+        None => return None
+    };
+
+    let mut names: Vec<&String> = Vec::new();
+    for &(ref name, _, _) in literals.iter() {
+        if !names.contains(&name) { names.push(name) }
+    }
+    // The last weight given for a literal counts
+    let weight = |name: &String, negated: bool| {
+        literals.iter().rev().find(|&&(ref var, neg, _)| var == name && neg == negated).map_or(1.0, |&(_, _, weight)| weight)
+    };
+    Some(names.into_iter().map(|name| (name.clone(), weight(name, true), weight(name, false))).collect())
+}
+
+// Tables of this many variables or more are printed as they are evaluated
+const STREAM_VARS: uint = 20;
+
+// Whole tables of more variables than this are refused unless `--max-vars`
+// raises the limit, pages of them may still be printed
+const MAX_VARS: uint = MAX_TABLE_VARS;
+
+// Flags shared by every mode
+struct Options {
+    implicit_and: bool,
+    style:        Style,
+    // Print this many random models in place of the table
+    sample:       Option<uint>,
+    // Print the probability of the expression being true given these
+    // probabilities of the variables being true, in place of the table
+    probs:        Vec<(String, f64)>,
+    // Print the model count weighted by these weights of each variable
+    // false and true, in place of the table
+    weights:      Option<Vec<(String, f64, f64)>>,
+    // Minimize with Espresso rather than exactly
+    heuristic:    bool,
+    // Mark the groups of a minimal cover on Karnaugh maps
+    groups:       bool,
+    // Print a normal form in place of the table
+    cnf:          Option<CnfMethod>,
+    dnf:          Option<DnfMethod>,
+    nnf:          bool,
+    anf:          bool,
+    // Print every rewrite made by `simplify`
+    steps:        bool,
+    // Also print a minimal form of a synthesized table
+    minimize:     bool,
+    // Rows given by `--dc` which minimal forms may make true or false
+    dont_cares:   Vec<uint>,
+    format:       Option<Format>,
+    // Leave out the header row of CSV and TSV tables
+    no_header:    bool,
+    // Color the cells of printed tables
+    color:        bool,
+    order:        Order,
+    // Make the first variable of the order the least significant bit
+    lsb_first:    bool,
+    filter:       Filter,
+    // A column for each sub-expression of the table
+    subexprs:     bool,
+    // A first column holding the row number
+    index:        bool,
+    // The rows of the table to print
+    limit:        Option<uint>,
+    offset:       Option<uint>,
+    max_vars:     uint,
+    // Values given to variables by `--set`, which may be unknown
+    set:          Vec<(String, Trit)>,
+    // Three-valued tables and evaluation
+    kleene:       bool,
+    // Print just the table, without the banners and the lines after it
+    quiet:        bool,
+    // Also warn about constant parts and unused variables
+    lint:         bool,
+    // Expressions given to variables by `--let`, substituted in order
+    bindings:     Vec<(String, Expr)>
+}
+
+// Every flag with the values it takes, a value in brackets being optional,
+// and its description for `--help`
+static FLAGS: &'static [(&'static str, &'static str, &'static str)] = &[
+    ("--help", "", "print this message"),
+    ("--implicit-and", "", "read juxtaposition as AND and a trailing ' as NOT"),
+    ("--style", "<native|c|math|python|verilog|latex>", "the notation to print expressions in"),
+    ("--format", "<dimacs|pla|dot|bdd|svg|json|csv|tsv|latex|html|sexpr>", "print the table or tree in another format"),
+    ("--emit", "<format>", "the same as --format"),
+    ("--no-header", "", "leave the header row out of CSV and TSV tables"),
+    ("--color", "<auto|always|never>", "color the cells of the table"),
+    ("--vars", "<a,b,...>", "the columns of the table, in order"),
+    ("--order", "<alphabetical|appearance>", "the order of the columns of the table"),
+    ("--lsb-first", "", "make the first column the least significant bit of the row number"),
+    ("--only-true", "", "print only the true rows"),
+    ("--only-false", "", "print only the false rows"),
+    ("--where", "<a=1,b=0,...>", "print only the rows with these inputs"),
+    ("--show-subexprs", "", "add a column for each sub-expression"),
+    ("--index", "", "add a column with the number of each row"),
+    ("--quiet", "", "print just the table, without the banners and the lines after it"),
+    ("--lint", "", "also warn about constant parts and variables the value doesn't depend on"),
+    ("--limit", "<n>", "print at most n rows"),
+    ("--offset", "<k>", "start printing from row k"),
+    ("--max-vars", "<n>", "the most variables a whole table may have"),
+    ("--set", "<a=1,b=0,c=X,...>", "the values of the variables for eval and restrict, X being unknown"),
+    ("--kleene", "", "print a three-valued table, with X for unknown inputs"),
+    ("--let", "<s=expr>", "an expression for subst to put in place of s, may be repeated"),
+    ("--sample", "<n>", "print n random assignments making the expression true"),
+    ("--prob", "<a=0.3,b=0.5,...>", "print the probability of the expression being true, 0.5 for unlisted variables"),
+    ("--weights", "<a=0.3,!a=0.7,...>", "print the sum of the products of the literal weights of each model, 1 for unlisted literals"),
+    ("--cnf", "", "print conjunctive normal form"),
+    ("--tseitin", "", "use the Tseitin encoding for --cnf and --format dimacs"),
+    ("--dnf", "[canonical|minimal]", "print disjunctive normal form"),
+    ("--nnf", "", "print negation normal form"),
+    ("--anf", "", "print algebraic normal form"),
+    ("--steps", "", "print each rewrite made by simplify"),
+    ("--minimize", "", "also print a minimal form of a table read by synth"),
+    ("--heuristic", "", "minimize with Espresso instead of exactly"),
+    ("--dc", "<2,5,7,...>", "rows which minimize, kmap and synth may make true or false"),
+    ("--groups", "", "mark the groups of a minimal cover on Karnaugh maps"),
+    ("--from", "<dimacs|pla> <file>", "read the expression from a file"),
+    ("-e", "<expr>", "an expression to tabulate, may be repeated"),
+    ("--stdio", "", "serve requests on stdin and stdout")
+];
+
+// Commands taking expressions, see `command`
+static COMMANDS: &'static [&'static str] = &[
+    "table", "eval", "restrict", "subst", "simplify", "minimize", "kmap", "sat", "check", "equiv", "crypto", "npn",
+    "classify"
+];
+
+// The positional arguments, such as the command and its expressions, with
+// the flags and their values taken out. Fails on an unknown flag
+fn positionals(args: &[String]) -> Result<Vec<String>, String> {
+    let mut positionals = Vec::new();
+    let mut idx = 1;
+    while idx < args.len() {
+        let arg = args[idx].as_slice();
+        idx += 1;
+        // `->` is an operator for `complete` rather than a flag
+        if arg == "-" || !arg.starts_with("-") || arg.starts_with("->") {
+            positionals.push(arg.to_string());
+            continue
+        }
+        let value = match FLAGS.iter().find(|&&(name, _, _)| name == arg) {
+            Some(&(_, value, _)) => value,
+            None => return Err(format!("Unknown option: {}", arg))
+        };
+        if value.starts_with("[") {
+            // Only taken when it is one of the choices
+            let choices = value.slice(1, value.len() - 1);
+            if idx < args.len() && choices.split('|').any(|choice| choice == args[idx].as_slice()) { idx += 1 }
         } else {
-            None
+            let count = value.words().count();
+            if idx + count > args.len() { return Err(format!("Missing value for {}: {}", arg, value)) }
+            idx += count;
         }
     }
+    Ok(positionals)
+}
 
-    fn read(&mut self) -> Option<char> {
-        let next = self.peak();
-        if next.is_some()  {
-            if next.unwrap() == '\n' {
-                self.line += 1;
-                self.col = 0;
-            } else {
-                self.col += 1;
-            }
-            self.pos += 1;
+fn print_help() {
+    println!("Usage: truth [command] [options]");
+    println!("");
+    println!("With no command, prints the truth table of each line of stdin, or of a");
+    println!("file given in its place.");
+    println!("");
+    println!("Commands:");
+    for name in COMMANDS.iter() {
+        println!("  truth {}", command(*name).unwrap().val0());
+    }
+    println!("  truth synth <file.csv>");
+    println!("  truth complete <operator or expr>...");
+    println!("  truth batch <file>");
+    println!("  truth bench [max variables]");
+    println!("  truth completions <bash|zsh|fish>");
+    println!("  truth serve --stdio");
+    println!("  truth lsp");
+    println!("  truth <file>");
+    println!("");
+    println!("Options:");
+    for &(name, value, help) in FLAGS.iter() {
+        let flag = if value.is_empty() { name.to_string() } else { format!("{} {}", name, value) };
+        if flag.len() > 28 {
+            println!("  {}", flag);
+            println!("  {:28}  {}", "", help);
+        } else {
+            println!("  {:28}  {}", flag, help);
         }
-        next
     }
 }
 
-#[deriving(Show)]
-struct ErrorPosition {
-    msg:        String,
-    line:       uint,
-    col_range:  (uint, uint)
+// The choices for the first value of a flag, as in `<auto|always|never>`,
+// empty when any value goes
+fn choices(value: &str) -> Vec<&str> {
+    match value.words().next() {
+        Some(first) if first.len() > 2 && first.contains_char('|') => first.slice(1, first.len() - 1).split('|').collect(),
+        _ => Vec::new()
+    }
 }
 
-impl ErrorPosition {
-    fn from_token(msg: String, tok: Token) -> ErrorPosition {
-        ErrorPosition {
-            msg:        msg,
-            line:       tok.line,
-            col_range:  (tok.col, tok.col)
-        }
+// A completion script for `shell`, generated from `COMMANDS` and `FLAGS`
+fn completions(shell: &str) -> Option<String> {
+    let mut commands: Vec<&str> = COMMANDS.to_vec();
+    commands.push_all(&["synth", "complete", "batch", "bench", "completions", "serve", "lsp"]);
+    let commands = commands.connect(" ");
+    let mut script = String::new();
+    match shell {
+        "bash" => {
+            script.push_str("_truth() {\n    local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n");
+            script.push_str("    case \"$prev\" in\n");
+            for &(name, value, _) in FLAGS.iter().filter(|&&(_, value, _)| !value.is_empty()) {
+                let words = choices(value);
+                if words.is_empty() {
+                    script.push_str(format!("        {}) return ;;\n", name).as_slice());
+                } else {
+                    script.push_str(format!("        {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;\n",
+                                            name, words.connect(" ")).as_slice());
+                }
+            }
+            script.push_str("        completions) COMPREPLY=($(compgen -W \"bash zsh fish\" -- \"$cur\")); return ;;\n");
+            script.push_str("    esac\n");
+            let flags: Vec<&str> = FLAGS.iter().map(|&(name, _, _)| name).collect();
+            script.push_str(format!("    if [[ $cur == -* ]]; then\n        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n",
+                                    flags.connect(" ")).as_slice());
+            script.push_str(format!("    elif [[ $COMP_CWORD -eq 1 ]]; then\n        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n    fi\n}}\n",
+                                    commands).as_slice());
+            script.push_str("complete -o default -F _truth truth\n");
+        },
+        "zsh" => {
+            script.push_str("#compdef truth\n\n_arguments \\\n");
+            for &(name, value, help) in FLAGS.iter() {
+                // Single quoted, with the brackets of the description escaped
+                let help = help.replace("'", "'\\''").replace("[", "\\[").replace("]", "\\]");
+                let repeat = if name == "--let" || name == "-e" { "*" } else { "" };
+                let arg = if value.is_empty() {
+                    String::new()
+                } else {
+                    let words = choices(value);
+                    let label = value.words().next().unwrap().trim_chars(['<', '>', '[', ']'].as_slice());
+                    if words.is_empty() { format!(":{}: ", label) } else { format!(":{}:({})", label, words.connect(" ")) }
+                };
+                script.push_str(format!("    '{}{}[{}]{}' \\\n", repeat, name, help, arg).as_slice());
+            }
+            script.push_str(format!("    '1:command:({})' \\\n    '*:file:_files'\n", commands).as_slice());
+        },
+        "fish" => {
+            script.push_str(format!("complete -c truth -n __fish_use_subcommand -a '{}'\n", commands).as_slice());
+            script.push_str("complete -c truth -n '__fish_seen_subcommand_from completions' -x -a 'bash zsh fish'\n");
+            for &(name, value, help) in FLAGS.iter() {
+                let flag = if name.starts_with("--") { format!("-l {}", name.slice_from(2)) } else { format!("-s {}", name.slice_from(1)) };
+                let help = help.replace("'", "\\'");
+                let words = choices(value);
+                let arg = if value.is_empty() {
+                    String::new()
+                } else if words.is_empty() {
+                    " -r".to_string()
+                } else {
+                    format!(" -x -a '{}'", words.connect(" "))
+                };
+                script.push_str(format!("complete -c truth {} -d '{}'{}\n", flag, help, arg).as_slice());
+            }
+        },
+        _ => return None
     }
+    Some(script)
 }
 
-struct Lexer {
-    reader: StringReader
+// The argument following `name`, as in `--style math`
+fn arg_value(args: &[String], name: &str) -> Option<String> {
+    args.iter().position(|arg| arg.as_slice() == name).and_then(|pos| args.get(pos + 1)).map(|arg| arg.clone())
 }
 
-impl Lexer {
-    fn tok(&self, token_type: Type) -> Token {
-        Token { token_type: token_type, col: self.reader.col, line: self.reader.line }
+fn main() {
+    let args = std::os::args();
+    let positional = match positionals(args.as_slice()) {
+        Ok(positional) => positional,
+        Err(msg) => {
+            println!("{} (see truth --help)", msg);
+            return
+        }
+    };
+    if args.iter().any(|arg| arg.as_slice() == "--help") {
+        print_help();
+        return
+    }
+    let implicit_and = args.iter().any(|arg| arg.as_slice() == "--implicit-and");
+    let style = match arg_value(args.as_slice(), "--style") {
+        Some(name) => match Style::from_name(name.as_slice()) {
+            Some(style) => style,
+            None => {
+                println!("Unknown style: {} (expected native, c, math, python, verilog or latex)", name);
+                return
+            }
+        },
+        None => Style::Native
+    };
+    let dont_cares = match arg_value(args.as_slice(), "--dc") {
+        Some(src) => match src.as_slice().split(',').map(|row| from_str::<uint>(row.trim())).collect() {
+            Some(rows) => rows,
+            None => {
+                println!("Invalid don't cares: {} (expected a list of rows like 2,5,7)", src);
+                return
+            }
+        },
+        None => Vec::new()
+    };
+    let probs = match arg_value(args.as_slice(), "--prob") {
+        Some(src) => match parse_probabilities(src.as_slice()) {
+            Some(probs) => probs,
+            None => {
+                println!("Invalid probabilities: {} (expected a list like a=0.3,b=0.5)", src);
+                return
+            }
+        },
+        None => Vec::new()
+    };
+    let weights = match arg_value(args.as_slice(), "--weights") {
+        Some(src) => match parse_weights(src.as_slice()) {
+            Some(weights) => Some(weights),
+            None => {
+                println!("Invalid weights: {} (expected a list like a=0.3,!a=0.7,b=2)", src);
+                return
+            }
+        },
+        None => None
+    };
+    let sample = match arg_value(args.as_slice(), "--sample") {
+        Some(count) => match from_str::<uint>(count.as_slice()) {
+            Some(count) => Some(count),
+            None => {
+                println!("Invalid sample count: {}", count);
+                return
+            }
+        },
+        None => None
+    };
+    let heuristic = args.iter().any(|arg| arg.as_slice() == "--heuristic");
+    let groups = args.iter().any(|arg| arg.as_slice() == "--groups");
+    let cnf = if !args.iter().any(|arg| arg.as_slice() == "--cnf") {
+        None
+    } else if args.iter().any(|arg| arg.as_slice() == "--tseitin") {
+        Some(CnfMethod::Tseitin)
+    } else {
+        Some(CnfMethod::Distribute)
+    };
+    let dnf = if !args.iter().any(|arg| arg.as_slice() == "--dnf") {
+        None
+    } else {
+        match arg_value(args.as_slice(), "--dnf") {
+            Some(ref method) if method.as_slice() == "canonical" => Some(DnfMethod::Canonical),
+            Some(ref method) if method.as_slice() == "minimal" => Some(DnfMethod::Minimal),
+            _ => Some(DnfMethod::Distribute)
+        }
+    };
+    // `--emit` is another name for `--format`
+    let format = match arg_value(args.as_slice(), "--format").or(arg_value(args.as_slice(), "--emit")) {
+        Some(name) => match Format::from_name(name.as_slice()) {
+            Some(format) => Some(format),
+            None => {
+                println!("Unknown format: {} (expected dimacs, pla, dot, bdd, svg, json, csv, tsv, latex, html or sexpr)", name);
+                return
+            }
+        },
+        None => None
+    };
+    // `auto` colors when writing to a terminal, unless NO_COLOR is set
+    let color = match arg_value(args.as_slice(), "--color") {
+        Some(ref when) if when.as_slice() == "always" => true,
+        Some(ref when) if when.as_slice() == "never" => false,
+        Some(ref when) if when.as_slice() != "auto" => {
+            println!("Unknown color mode: {} (expected auto, always or never)", when);
+            return
+        },
+        _ => std::os::getenv("NO_COLOR").is_none() && std::io::stdio::stdout_raw().isatty()
+    };
+    let order = match (arg_value(args.as_slice(), "--vars"), arg_value(args.as_slice(), "--order")) {
+        (Some(vars), _) => Order::Given(vars.as_slice().split(',').map(|var| var.trim().to_string()).collect()),
+        (None, Some(ref order)) if order.as_slice() == "appearance" => Order::Appearance,
+        (None, Some(ref order)) if order.as_slice() != "alphabetical" => {
+            println!("Unknown order: {} (expected alphabetical or appearance)", order);
+            return
+        },
+        _ => Order::Alphabetical
+    };
+    let result = if args.iter().any(|arg| arg.as_slice() == "--only-true") {
+        Some(true)
+    } else if args.iter().any(|arg| arg.as_slice() == "--only-false") {
+        Some(false)
+    } else {
+        None
+    };
+    let inputs = match arg_value(args.as_slice(), "--where") {
+        Some(src) => match parse_assignments(src.as_slice()) {
+            Some(inputs) => inputs,
+            None => {
+                println!("Invalid filter: {} (expected a list like a=1,b=0)", src);
+                return
+            }
+        },
+        None => Vec::new()
+    };
+    let mut counts = Vec::new();
+    for name in ["--limit", "--offset", "--max-vars"].iter() {
+        counts.push(match arg_value(args.as_slice(), *name) {
+            Some(count) => match from_str::<uint>(count.as_slice()) {
+                Some(count) => Some(count),
+                None => {
+                    println!("Invalid row count for {}: {}", name, count);
+                    return
+                }
+            },
+            None => None
+        });
     }
+    let set = match arg_value(args.as_slice(), "--set") {
+        Some(src) => match parse_values(src.as_slice()) {
+            Some(set) => set,
+            None => {
+                println!("Invalid values: {} (expected a list like a=1,b=0,c=X)", src);
+                return
+            }
+        },
+        None => Vec::new()
+    };
+    // `--let name=expr` may be repeated
+    let mut bindings = Vec::new();
+    for pair in args.windows(2).filter(|pair| pair[0].as_slice() == "--let") {
+        let src = pair[1].as_slice();
+        let pos = match src.find('=') {
+            Some(pos) if !src.slice_to(pos).trim().is_empty() => pos,
+            _ => {
+                println!("Invalid binding: {} (expected one like s=x|y)", src);
+                return
+            }
+        };
+        match parse(src.slice_from(pos + 1).to_string(), implicit_and) {
+            Ok(expr) => bindings.push((src.slice_to(pos).trim().to_string(), expr)),
+            Err(err) => {
+                print_error(&err, src.slice_from(pos + 1), "<argument>");
+                return
+            }
+        }
+    }
+    let options = Options {
+        implicit_and: implicit_and,
+        style: style,
+        sample: sample,
+        probs: probs,
+        weights: weights,
+        heuristic: heuristic,
+        groups: groups,
+        cnf: cnf,
+        dnf: dnf,
+        nnf: args.iter().any(|arg| arg.as_slice() == "--nnf"),
+        anf: args.iter().any(|arg| arg.as_slice() == "--anf"),
+        steps: args.iter().any(|arg| arg.as_slice() == "--steps"),
+        minimize: args.iter().any(|arg| arg.as_slice() == "--minimize"),
+        dont_cares: dont_cares,
+        format: format,
+        no_header: args.iter().any(|arg| arg.as_slice() == "--no-header"),
+        color: color,
+        order: order,
+        lsb_first: args.iter().any(|arg| arg.as_slice() == "--lsb-first"),
+        filter: Filter { result: result, inputs: inputs },
+        subexprs: args.iter().any(|arg| arg.as_slice() == "--show-subexprs"),
+        index: args.iter().any(|arg| arg.as_slice() == "--index"),
+        limit: counts[0],
+        offset: counts[1],
+        max_vars: counts[2].unwrap_or(MAX_VARS),
+        set: set,
+        kleene: args.iter().any(|arg| arg.as_slice() == "--kleene"),
+        quiet: args.iter().any(|arg| arg.as_slice() == "--quiet"),
+        lint: args.iter().any(|arg| arg.as_slice() == "--lint"),
+        bindings: bindings
+    };
 
-    fn next_token(&mut self) -> Result<Token, ErrorPosition> {
-        loop {
-            let c = match self.reader.read() {
-                Some(c) => c,
-                None => return Ok(self.tok(Type::EOF))
+    // `--from <format> <file>` reads the expression from a file, which may be
+    // given to a command taking one expression
+    match args.iter().position(|arg| arg.as_slice() == "--from") {
+        Some(pos) if args.len() > pos + 2 => {
+            let run = match positional.get(0).and_then(|name| command(name.as_slice())) {
+                Some((_, 1, run)) => run,
+                _ => show as fn(&[Expr], &Options) -> Result<(), TruthError>
             };
+            match from_file(args[pos + 1].as_slice(), args[pos + 2].as_slice()) {
+                Some(Ok(root)) => match run(&[root], &options) {
+                    Err(err) => print_error(&err, "", args[pos + 2].as_slice()),
+                    _ => {}
+                },
+                Some(Err(err)) => println!("Error: {}", err),
+                None => {}
+            }
+            return
+        },
+        Some(_) => {
+            println!("Usage: truth [command] --from <dimacs|pla> <file>");
+            return
+        },
+        None => {}
+    }
 
-            if      c == '(' { return Ok(self.tok(Type::LParen)) }
-            else if c == ')' { return Ok(self.tok(Type::RParen)) }
-            else if c == '&' || c == '*' { return Ok(self.tok(Type::And)) }
-            else if c == '|' || c == '+' { return Ok(self.tok(Type::Or)) }
-            else if c == '!' || c == '~' { return Ok(self.tok(Type::Not)) }
-            else if c == '^' { return Ok(self.tok(Type::Xor)) }
+    match positional.as_slice() {
+        [ref name, ref path] if name.as_slice() == "synth" => {
+            synth(path.as_slice(), &options);
+            return
+        },
+        [ref name, sources..] if name.as_slice() == "complete" => {
+            if sources.is_empty() {
+                println!("Usage: truth complete <operator or expr>...");
+            } else {
+                match complete(sources, &options) {
+                    Err(err) => println!("Error: {}", err),
+                    _ => {}
+                }
+            }
+            return
+        },
+        [ref name, ref path] if name.as_slice() == "batch" => {
+            batch(path.as_slice(), &options);
+            return
+        },
+        [ref name] if name.as_slice() == "bench" => {
+            bench(BENCH_VARS);
+            return
+        },
+        [ref name, ref vars] if name.as_slice() == "bench" => {
+            match from_str::<uint>(vars.as_slice()) {
+                Some(vars) => bench(vars),
+                None => println!("Usage: truth bench [max variables]")
+            }
+            return
+        },
+        [ref name] if name.as_slice() == "lsp" => {
+            lsp::run();
+            return
+        },
+        [ref name] if name.as_slice() == "serve" => {
+            if args.iter().any(|arg| arg.as_slice() == "--stdio") {
+                serve(&options);
+            } else {
+                println!("Usage: truth serve --stdio");
+            }
+            return
+        },
+        [ref name, ref shell] if name.as_slice() == "completions" => {
+            match completions(shell.as_slice()) {
+                Some(script) => print!("{}", script),
+                None => println!("Unknown shell: {} (expected bash, zsh or fish)", shell)
+            }
+            return
+        },
+        _ => {}
+    }
 
-            else if c.is_alpha() { return Ok(self.next_ident(c)) }
+    if positional.len() > 0 {
+        match command(positional[0].as_slice()) {
+            Some((usage, arity, run)) => {
+                if positional.len() != 1 + arity {
+                    println!("Usage: truth {}", usage);
+                    return
+                }
+                let sources = positional.slice_from(1);
+                match parse_args(sources, &options).map(|exprs| run(exprs.as_slice(), &options)) {
+                    // Only a single expression is known to be the source
+                    Some(Err(err)) => print_error(&err, if arity == 1 { sources[0].as_slice() } else { "" }, "<argument>"),
+                    _ => {}
+                }
+                return
+            },
+            None => {}
+        }
+    }
+
+    // `-e <expr>` may be repeated for a table with an output per expression
+    let sources: Vec<String> = args.windows(2).filter(|pair| pair[0].as_slice() == "-e")
+        .map(|pair| pair[1].clone()).collect();
+    if !sources.is_empty() {
+        match parse_args(sources.as_slice(), &options).map(|exprs| show(exprs.as_slice(), &options)) {
+            Some(Err(err)) => print_error(&err, "", "<argument>"),
+            _ => {}
+        }
+        return
+    }
+
+    // A file of expressions, or `-` for stdin
+    if positional.len() > 1 {
+        println!("Unknown command: {} (see truth --help)", positional[0]);
+        return
+    }
+    if positional.len() == 1 {
+        let path = positional[0].as_slice();
+        if path == "-" {
+            repl(&mut std::io::stdin(), None, options);
+            return
+        }
+        match File::open(&Path::new(path)) {
+            Ok(file) => repl(&mut BufferedReader::new(file), Some(path), options),
+            Err(err) => println!("Couldn't read {}: {}", path, err)
+        }
+        return
+    }
 
-            else if c == ' ' || c == '\n' { continue }
-            else {
-                return Err(ErrorPosition {
-                    msg:        format!("Unexpected character: {}", c).to_string(),
-                    line:       self.reader.line,
-                    col_range:  (self.reader.col, self.reader.col)    
-                })
-                
+    repl(&mut std::io::stdin(), None, options);
+}
+
+const PROMPT: &'static str = "truth> ";
+
+const REPL_HELP: &'static str = "\
+Enter an expression to print its table, `let name = expr` to name an
+expression for use in later ones, `def name(a, b) = expr` to define a
+function which later ones may call as `name(x, y)`, or one of:
+  :show [format]   print the last expression again, as a table or in another format
+  :style <name>    print expressions in another style from now on
+  :vars            list the variables of the last expression
+  :history         list the expressions entered so far
+  :bindings        list the names defined with `let name = expr`
+  :functions       list the functions defined with `def name(a, b) = expr`
+  :help            print this message
+  :quit            exit (as does end of input)
+";
+
+// What the REPL remembers between lines
+struct Session {
+    // The lines entered other than commands
+    history:   Vec<String>,
+    // The last expressions printed, with their names expanded
+    last:      Vec<Expr>,
+    bindings:  Bindings,
+    functions: Functions
+}
+
+// Reads expressions one per line, remembering the last for the `:`
+// commands. `path` names the file being read, which is stdin when it is
+// `None`. When stdin is a terminal each line is prompted for and edited
+// with `Editor`
+fn repl<R: Buffer>(input: &mut R, path: Option<&str>, mut options: Options) {
+    let interactive = path.is_none() && std::io::stdio::stdin_raw().isatty();
+    let mut editor = Editor::new();
+    let mut line_no = 0u;
+    let mut session = Session { history: Vec::new(), last: Vec::new(), bindings: Bindings::new(), functions: Functions::new() };
+    loop {
+        let line = if interactive {
+            match editor.read_line(PROMPT) {
+                Some(line) => line,
+                None => break
+            }
+        } else {
+            match input.read_line() {
+                Ok(line) => line,
+                Err(_) => break
             }
+        };
+        line_no += 1;
+        // Only the newline is trimmed, so columns in errors stay right
+        let src = line.as_slice().trim_right();
+        if src.trim().is_empty() { continue }
+        if interactive { editor.add_history(src.to_string()) }
+
+        if src.trim().starts_with(":") {
+            if !repl_command(src.trim().slice_from(1), &mut options, &session) { break }
+            continue
+        }
+
+        session.history.push(src.to_string());
+        match repl_line(src, &mut session, &options) {
+            Err(errs) => for (err, offset) in errs.into_iter() {
+                let err = err.shifted(columns(src.slice_to(offset), TAB_WIDTH), offset).on_line(line_no);
+                print_error(&err, src, path.unwrap_or("<stdin>"));
+            },
+            _ => {}
         }
     }
+}
 
-    fn next_ident(&mut self, current: char) -> Token {
-        let mut string = String::new();
-        string.push(current);
+// Runs a line other than a command, reporting all the parse errors of its
+// expressions. Errors come with the byte offset of the part of the line
+// their columns count from
+fn repl_line(src: &str, session: &mut Session, options: &Options) -> Result<(), Vec<(TruthError, uint)>> {
+    match parse_def(src) {
+        Some((name, params, pos)) => {
+            let body = try!(parse_all(src.slice_from(pos).to_string(), options.implicit_and).map_err(|errs| at(errs, pos)));
+            return session.functions.define(name.to_string(), params, &body).map_err(|err| vec!((err, 0)))
+        },
+        None => {}
+    }
+    match parse_let(src) {
+        Some((name, pos)) => {
+            let expr = try!(parse_all(src.slice_from(pos).to_string(), options.implicit_and).map_err(|errs| at(errs, pos)));
+            let expr = try!(session.functions.expand(&expr).map_err(|err| vec!((err, 0))));
+            session.bindings.define(name.to_string(), &expr);
+            return Ok(())
+        },
+        None => {}
+    }
 
-        loop {
-            let peak = self.reader.peak();
-            if peak.is_some() && peak.unwrap().is_alpha() {
-                string.push(peak.unwrap());
-                self.reader.read();
-            } else {
-                break
+    // Expressions separated by `;` share a table
+    let mut roots = Vec::new();
+    let mut errors = Vec::new();
+    let mut start = 0;
+    for part in src.split(';') {
+        if !part.trim().is_empty() {
+            match parse_all(part.to_string(), options.implicit_and) {
+                Ok(root) => {
+                    let root = try!(session.functions.expand(&root).map_err(|err| vec!((err, 0))));
+                    roots.push(session.bindings.expand(&root));
+                },
+                Err(errs) => errors.extend(at(errs, start).into_iter())
             }
         }
-        
-        self.tok(Type::Ident(string))
+        start += part.len() + 1;
     }
+    if !errors.is_empty() { return Err(errors) }
+    if roots.is_empty() { return Ok(()) }
+    session.last = roots.clone();
+    show(roots.as_slice(), options).map_err(|err| vec!((err, 0)))
 }
 
-#[deriving(Show)]
-#[deriving(Clone)]
-enum Type {
-    LParen,
-    RParen,
+// Whether `src` may name a variable or function
+fn is_name(src: &str) -> bool {
+    !src.is_empty() && src.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
 
-    Ident(String),
+// The name bound by `let name = expr` and the byte offset of the expression
+fn parse_let(src: &str) -> Option<(&str, uint)> {
+    let start = src.len() - src.trim_left().len();
+    if !src.slice_from(start).starts_with("let ") { return None }
+    let eq = match src.find('=') {
+        Some(eq) => eq,
+        None => return None
+    };
+    let name = src.slice(start + 4, eq).trim();
+    if !is_name(name) { return None }
+    Some((name, eq + 1))
+}
 
-    And,
-    Or,
-    Not,
-    Xor,
+// The name and parameters of `def name(a, b) = expr` and the byte offset of
+// the expression
+fn parse_def(src: &str) -> Option<(&str, Vec<String>, uint)> {
+    let start = src.len() - src.trim_left().len();
+    if !src.slice_from(start).starts_with("def ") { return None }
+    let (open, close, eq) = match (src.find('('), src.find(')'), src.find('=')) {
+        (Some(open), Some(close), Some(eq)) if open < close && close < eq => (open, close, eq),
+        _ => return None
+    };
+    let name = src.slice(start + 4, open).trim();
+    let params: Vec<String> = src.slice(open + 1, close).split(',').map(|param| param.trim().to_string()).collect();
+    if !is_name(name) || !params.iter().all(|param| is_name(param.as_slice())) { return None }
+    Some((name, params, eq + 1))
+}
 
-    EOF
+// Runs a REPL command, returning false to quit
+fn repl_command(src: &str, options: &mut Options, session: &Session) -> bool {
+    let words: Vec<&str> = src.words().collect();
+    let last = session.last.as_slice();
+    match (words.as_slice(), last.is_empty()) {
+        (["quit"], _) | (["q"], _) => return false,
+        (["help"], _) => print!("{}", REPL_HELP),
+        (["history"], _) => {
+            for (idx, line) in session.history.iter().enumerate() {
+                println!("{:>4}  {}", idx + 1, line);
+            }
+        },
+        (["bindings"], _) => {
+            for name in session.bindings.names().iter() {
+                println!("{} = {}", name, session.bindings.get(name.as_slice()).unwrap().display(options.style));
+            }
+        },
+        (["functions"], _) => {
+            for name in session.functions.names().iter() {
+                let (params, body) = session.functions.get(name.as_slice()).unwrap();
+                println!("{}({}) = {}", name, params.connect(", "), body.display(options.style));
+            }
+        },
+        (["style", name], _) => match Style::from_name(name) {
+            Some(style) => options.style = style,
+            None => println!("Unknown style: {} (expected native, c, math, python, verilog or latex)", name)
+        },
+        (["vars"], true) | (["show"], true) | (["show", _], true) => println!("No expression entered yet"),
+        (["vars"], false) => println!("{}", shared_variables(last, options)),
+        (["show"], false) | (["show", "table"], false) => rerun(last, None, options),
+        (["show", name], false) => match Format::from_name(name) {
+            Some(format) => rerun(last, Some(format), options),
+            None => println!("Unknown format: {} (expected table, dimacs, pla, dot, bdd, svg, json, csv, tsv, latex, html or sexpr)", name)
+        },
+        _ => println!("Unknown command: :{} (enter :help for a list)", src)
+    }
+    true
 }
 
-#[deriving(Clone)]
-#[deriving(Show)]
-struct Token {
-    token_type: Type,
-    col:        uint,
-    line:       uint
+// Shows `exprs` again in `format` in place of the format given on the command line
+fn rerun(exprs: &[Expr], format: Option<Format>, options: &mut Options) {
+    let saved = mem::replace(&mut options.format, format);
+    match show(exprs, options) {
+        Err(err) => print_error(&err, "", "<stdin>"),
+        _ => {}
+    }
+    options.format = saved;
 }
 
-#[deriving(Show)]
-struct Operation {
-    components: Vec<Component>,
-    ops: Vec<Token>
+// Prints the error with the line of `src` it is on underlined, `origin`
+// naming where `src` came from
+fn print_error(err: &TruthError, src: &str, origin: &str) {
+    print!("{}", err.render(src, origin));
 }
 
-impl Operation {
-    fn eval(&self, env: &Environment) -> Result<bool, ErrorPosition> {
-        let mut val = false;
-        if self.components.len() > 0 {
-            val = try!(self.components[0].eval(env));
+// Parses expressions given as arguments, printing the errors of the first
+// which fails
+fn parse_args(sources: &[String], options: &Options) -> Option<Vec<Expr>> {
+    let mut exprs = Vec::new();
+    for src in sources.iter() {
+        match parse_all(src.clone(), options.implicit_and) {
+            Ok(expr) => exprs.push(expr),
+            Err(errs) => {
+                for err in errs.iter() { print_error(err, src.as_slice(), "<argument>") }
+                return None
+            }
         }
+    }
+    Some(exprs)
+}
 
-        for idx in range(1u, self.components.len()) {
-            let eval = try!(self.components[idx].eval(env));
-            //let token = self.ops[idx - 1];
-            match self.ops[idx - 1].token_type {
-                Type::And => val &= eval,
-                Type::Or => val |= eval,
-                Type::Xor => val ^= eval,
-                ref other => {
-                    return Err(ErrorPosition::from_token(
-                        format!("Unexpected operation: {}", other),
-                        self.ops[idx - 1].clone(),
-                    ))
-                }
-            }
+// An expression, or a function written as its minterms or maxterms
+fn parse(src: String, implicit_and: bool) -> Result<Expr, TruthError> {
+    if TermList::matches(src.as_slice()) {
+        return Ok(try!(TermList::parse(src.as_slice())).to_expr())
+    }
+    let mut lexer  = if implicit_and { Lexer::with_implicit_and(src) } else { Lexer::new(src) };
+    let mut parser = try!(Parser::new(&mut lexer));
+    parser.parse()
+}
+
+// Like `parse`, but with every lex and parse error of `src`
+fn parse_all(src: String, implicit_and: bool) -> Result<Expr, Vec<TruthError>> {
+    if TermList::matches(src.as_slice()) {
+        return TermList::parse(src.as_slice()).map(|terms| terms.to_expr()).map_err(|err| vec!(err))
+    }
+    let mut lexer = if implicit_and { Lexer::with_implicit_and(src) } else { Lexer::new(src) };
+    Parser::parse_recovering(&mut lexer)
+}
+
+// Errors paired with the byte offset their columns count from
+fn at(errs: Vec<TruthError>, offset: uint) -> Vec<(TruthError, uint)> {
+    errs.into_iter().map(|err| (err, offset)).collect()
+}
+
+// Subcommands which take their expressions as arguments instead of reading
+// stdin: the usage line, the number of expressions and the command
+fn command(name: &str) -> Option<(&'static str, uint, fn(&[Expr], &Options) -> Result<(), TruthError>)> {
+    match name {
+        "table" => Some(("table <expr>", 1, show)),
+        "sat" => Some(("sat <expr>", 1, sat)),
+        "check" => Some(("check <expr>", 1, check)),
+        "equiv" => Some(("equiv <expr> <expr>", 2, equiv)),
+        "minimize" => Some(("minimize <expr>", 1, minimize)),
+        "kmap" => Some(("kmap <expr>", 1, kmap)),
+        "crypto" => Some(("crypto <expr>", 1, crypto)),
+        "npn" => Some(("npn <expr>", 1, npn)),
+        "classify" => Some(("classify <expr>", 1, classify)),
+        "simplify" => Some(("simplify <expr>", 1, simplify)),
+        "eval" => Some(("eval <expr> --set a=1,b=0", 1, eval)),
+        "restrict" => Some(("restrict <expr> --set a=1,b=0", 1, restrict)),
+        "subst" => Some(("subst <expr> --let s=x|y", 1, subst)),
+        _ => None
+    }
+}
+
+// Searches for a model instead of enumerating the table
+fn sat(exprs: &[Expr], _options: &Options) -> Result<(), TruthError> {
+    match try!(exprs[0].satisfiable()) {
+        Some(model) => println!("Satisfiable: {}", model),
+        None => println!("Unsatisfiable")
+    }
+    Ok(())
+}
+
+fn check(exprs: &[Expr], _options: &Options) -> Result<(), TruthError> {
+    match try!(exprs[0].classify()) {
+        Classification::Tautology => println!("Tautology"),
+        Classification::Contradiction => println!("Contradiction"),
+        Classification::Contingent(model, counter) => {
+            println!("Contingent");
+            println!("True for: {}", model);
+            println!("False for: {}", counter);
         }
+    }
+    Ok(())
+}
 
-        Ok(val)
+fn equiv(exprs: &[Expr], _options: &Options) -> Result<(), TruthError> {
+    match try!(exprs[0].equivalent(&exprs[1])) {
+        Some(counter) => {
+            println!("Not equivalent");
+            println!("{} gives {} for the first and {} for the second",
+                     counter.assignment, counter.left as u8, !counter.left as u8);
+        },
+        None => println!("Equivalent")
     }
+    Ok(())
+}
 
-    fn get_variables(&self) -> Vec<String> {
-        let mut vars: Vec<String> = Vec::new();
+// Prints every minimal form, one per line
+fn minimize(exprs: &[Expr], options: &Options) -> Result<(), TruthError> {
+    let table = try!(dont_care_table(&exprs[0], options));
+    if options.heuristic {
+        println!("{}", minimize::minimize_heuristic(&table).display(options.style));
+        return Ok(())
+    }
+    for form in minimize::minimal_forms(&table).iter() {
+        println!("{}", form.display(options.style));
+    }
+    Ok(())
+}
 
-        for component in self.components.iter() {
-            match component.value {
-                VarOrExpr::Var(ref var) => {
-                    if !vars.contains(var) { vars.push(var.clone()) }
-                }
-                VarOrExpr::Expr(ref op) => {
-                    let other_vars = op.get_variables();
-                    for var in other_vars.iter() {
-                        if !vars.contains(var) { vars.push(var.clone()) }
-                    }
-                }
-            }
+fn kmap(exprs: &[Expr], options: &Options) -> Result<(), TruthError> {
+    let table = try!(dont_care_table(&exprs[0], options));
+    let map = match KarnaughMap::new(&table) {
+        Some(map) => map,
+        None => {
+            println!("Karnaugh maps need 2 to 5 variables, found {}", table.variables().len());
+            return Ok(())
         }
+    };
 
-        vars
+    if options.groups {
+        let primes = minimize::prime_implicants(&table);
+        let cover = minimize::minimal_covers(&table, primes.as_slice()).swap_remove(0).unwrap();
+        print!("{}", map.with_groups(cover));
+    } else {
+        print!("{}", map);
     }
+    Ok(())
+}
 
-    // TODO make structure to clean up this return type
-    fn truth_table(&self) -> Result<Vec<(HashMap<String, bool>, bool)>, ErrorPosition> {
-        let mut result = Vec::new();
+// The Walsh spectrum is printed for tables of at most this many variables
+const SPECTRUM_VARS: uint = 6;
 
-        let vars = self.get_variables();
-        let tests = 2i.pow(vars.len());
+// How far the function is from being affine, how its output changes with
+// each input and how much it reveals about them, as for a component of an
+// S-box
+fn crypto(exprs: &[Expr], options: &Options) -> Result<(), TruthError> {
+    let table = try!(table(&exprs[0], options));
+    if table.variables().len() <= SPECTRUM_VARS {
+        println!("Walsh spectrum: {}", spectral::walsh(&table));
+    }
+    println!("Nonlinearity: {}", spectral::nonlinearity(&table));
+    println!("Nearest affine function: {}", spectral::nearest_affine(&table).display(options.style));
+    println!("Bent: {}", if spectral::is_bent(&table) { "yes" } else { "no" });
 
-        for num in range(0i, tests) {
-            let mut env = EnvironmentImpl { vars: HashMap::new() };
-            for pos in range(0u, vars.len()) {
-                env.vars.insert(vars[pos].clone(), ((num >> (vars.len() - 1 - pos)) & 1) == 1);
-            }
-            result.push((env.vars.clone(), try!(self.eval(&env))));
-        }
+    println!("Strict avalanche criterion: {}", if spectral::satisfies_sac(&table) { "yes" } else { "no" });
+    for (var, changed) in table.variables().iter().zip(spectral::avalanche(&table).into_iter()) {
+        println!("  flipping {} changes {} of {} rows", var, changed, table.len());
+    }
+    let order = spectral::correlation_immunity(&table);
+    println!("Correlation immunity: order {}", order);
+    if spectral::is_balanced(&table) {
+        println!("Resilient: order {}", order);
+    } else {
+        println!("Resilient: no, it isn't balanced");
+    }
+    Ok(())
+}
+
+// The canonical member of the function's NPN class, its key for bucketing
+// functions and the transform from it back to the function
+fn npn(exprs: &[Expr], options: &Options) -> Result<(), TruthError> {
+    let table = try!(table(&exprs[0], options));
+    let class = try!(npn::canonical(&table));
+    println!("Key: {}", class.key());
+    println!("Representative: g = {}", minimize::minimize(&class.table).display(options.style));
+    println!("Transform: f = {}", class);
+    Ok(())
+}
 
-        Ok(result)
+// Which of Post's classes the function is in
+fn classify(exprs: &[Expr], options: &Options) -> Result<(), TruthError> {
+    let classes = post::classify(&try!(table(&exprs[0], options)));
+    let answer = |member: bool| if member { "yes" } else { "no" };
+    println!("Monotone: {}", answer(classes.monotone));
+    println!("Linear: {}", answer(classes.linear));
+    println!("Self-dual: {}", answer(classes.self_dual));
+    println!("0-preserving: {}", answer(classes.preserves_false));
+    println!("1-preserving: {}", answer(classes.preserves_true));
+    Ok(())
+}
+
+// The expression for an operator given by name or symbol, over `a` and `b`
+fn operator(name: &str) -> Option<&'static str> {
+    match name {
+        "AND" | "and" | "&" | "∧" => Some("a & b"),
+        "OR" | "or" | "|" | "∨" => Some("a | b"),
+        "NOT" | "not" | "!" | "¬" => Some("!a"),
+        "XOR" | "xor" | "^" | "⊕" => Some("a ^ b"),
+        "XNOR" | "xnor" | "!^" => Some("a XNOR b"),
+        "NAND" | "nand" | "↑" => Some("!(a & b)"),
+        "NOR" | "nor" | "↓" => Some("!(a | b)"),
+        "IMPLIES" | "implies" | "->" | "→" => Some("a -> b"),
+        "IFF" | "iff" | "<->" | "↔" => Some("a <-> b"),
+        _ => None
     }
 }
 
-#[deriving(Show)]
-enum VarOrExpr {
-    Var(String),
-    Expr(Operation)
+// Whether the operators and expressions can together express every
+// function, and if not the class of Post's holding all of them
+fn complete(sources: &[String], options: &Options) -> Result<(), TruthError> {
+    let sources: Vec<String> = sources.iter().map(|src| {
+        operator(src.as_slice()).map_or(src.clone(), |expr| expr.to_string())
+    }).collect();
+    let exprs = match parse_args(sources.as_slice(), options) {
+        Some(exprs) => exprs,
+        None => return Ok(())
+    };
+
+    let mut set = Vec::new();
+    for expr in exprs.iter() {
+        set.push(post::classify(&try!(table(expr, options))));
+    }
+    let closed = post::closed_classes(set.as_slice());
+    if closed.is_empty() {
+        println!("Functionally complete");
+    } else {
+        println!("Not functionally complete: every function is {}", closed.connect(", "));
+    }
+    Ok(())
 }
 
-#[deriving(Show)]
-struct Component {
-    value: VarOrExpr,
-    negated: bool
+// The known values given by `--set`, leaving out those set to X
+fn assignment(options: &Options) -> Assignment {
+    let known: Vec<(String, bool)> = options.set.iter().filter_map(|&(ref name, ref value)| {
+        value.to_bool().map(|value| (name.clone(), value))
+    }).collect();
+    let names = known.iter().map(|&(ref name, _)| name.clone()).collect();
+    let values = known.iter().map(|&(_, value)| value).collect();
+    Assignment::new(names, values)
 }
 
-impl Component {
-    fn eval(&self, env: &Environment) -> Result<bool, ErrorPosition> {
-        let mut val = match self.value {
-            VarOrExpr::Var(ref name) => env.get_variable(name.clone()),
-            VarOrExpr::Expr(ref op) => try!(op.eval(env))
-        };
-        if self.negated { val = !val };
-        Ok(val)
+// Three-valued when a value is X or `--kleene` is given, leaving the
+// variables which aren't set unknown
+fn eval(exprs: &[Expr], options: &Options) -> Result<(), TruthError> {
+    if options.kleene || options.set.iter().any(|&(_, ref value)| *value == Trit::Unknown) {
+        let names: Vec<String> = options.set.iter().map(|&(ref name, _)| name.clone()).collect();
+        let values: Vec<Trit> = options.set.iter().map(|&(_, ref value)| value.clone()).collect();
+        println!("{}", try!(kleene::eval(&exprs[0], names.as_slice(), values.as_slice())));
+        return Ok(())
     }
+    println!("{}", try!(exprs[0].eval_with(&assignment(options))) as u8);
+    Ok(())
 }
 
-struct Parser {
-    tokens: Vec<Token>,
-    pos: uint
+// What's left of the expression with the variables given by `--set` fixed
+fn restrict(exprs: &[Expr], options: &Options) -> Result<(), TruthError> {
+    println!("{}", exprs[0].restrict(&assignment(options)).display(options.style));
+    Ok(())
 }
 
-impl Parser {
-    fn new(lexer: &mut Lexer) -> Result<Parser, ErrorPosition> {
-        let mut tokens = vec!();
-        let mut token;
-        loop {
-            token = try!(lexer.next_token());
-            match token.token_type {
-                Type::EOF => {
-                    tokens.push(token);
-                    break
-                },
-                _   => {
-                    tokens.push(token);
-                }
-            }
+// Splices the expressions given by `--let` into the first and shows the result
+fn subst(exprs: &[Expr], options: &Options) -> Result<(), TruthError> {
+    let root = options.bindings.iter().fold(exprs[0].clone(), |root, &(ref var, ref expr)| {
+        root.substitute(var.as_slice(), expr)
+    });
+    show(&[root], options)
+}
+
+fn simplify(exprs: &[Expr], options: &Options) -> Result<(), TruthError> {
+    if !options.steps {
+        println!("{}", exprs[0].simplify().display(options.style));
+        return Ok(())
+    }
+    println!("  {}", exprs[0].display(options.style));
+    for step in exprs[0].simplify_steps().iter() {
+        println!("= {}    ({})", step.expr.display(options.style), step.law);
+    }
+    Ok(())
+}
+
+// The contents of a file, or None after printing why it couldn't be read
+fn read_file(path: &str) -> Option<String> {
+    match File::open(&Path::new(path)).read_to_string() {
+        Ok(src) => Some(src),
+        Err(err) => {
+            println!("Couldn't read {}: {}", path, err);
+            None
         }
-        Ok(Parser { tokens: tokens, pos: 0 })
-    }
-    
-    fn next(&mut self) -> Token {
-        let tok = self.tokens[self.pos].clone();
-        self.pos += 1;
-        tok
-    }
-    
-    fn back(&mut self) { self.pos -= 1; }
-    
-    fn parse(&mut self) -> Result<Operation, ErrorPosition> {
-        let mut op = Operation { components: vec!(), ops: vec!() };
-        
-        op.components.push(try!(self.component()));
-        let mut token = self.next();
-
-        loop {
-            match token.token_type {
-                Type::Or | Type::Xor | Type::And => {
-                    op.ops.push(token.clone());
-                    op.components.push(try!(self.component()));
-                },
-                _ => {
-                    break;
-                }
-            };
-            token = self.next();
-        }
-        self.back();
-        
-        Ok(op)
-    }
-    
-    fn component(&mut self) -> Result<Component, ErrorPosition> {
-        let mut token = self.next();
-        let mut neg = false;
-        let mut val: VarOrExpr;
-        
-        loop {
-            match token.token_type {
-                Type::Not => neg = !neg,
-                Type::LParen => {
-                    val = VarOrExpr::Expr(try!(self.parse()));
-                    let next = self.next();
-                    match next.token_type {
-                        Type::RParen  => {},
-                        ref other   => {
-                            return Err(ErrorPosition::from_token(
-                                format!("Unexpected token: {}", other), next.clone()
-                            ))
-                        }
-                    };
-                    break;
-                },
-                Type::Ident(name) => {
-                    val = VarOrExpr::Var(name);
-                    break;
+    }
+}
+
+// An expression read from a file in the named format
+fn from_file(format: &str, path: &str) -> Option<Result<Expr, TruthError>> {
+    match format {
+        "dimacs" => read_file(path).map(|src| Cnf::from_dimacs(src.as_slice()).map(|cnf| cnf.to_expr())),
+        "pla" => read_file(path).map(|src| {
+            TruthTable::from_pla(src.as_slice()).map(|table| Dnf::canonical(&table).to_expr())
+        }),
+        _ => {
+            println!("Unknown input format: {} (expected dimacs or pla)", format);
+            None
+        }
+    }
+}
+
+// Reads a truth table from a CSV file and prints an expression for it
+fn synth(path: &str, options: &Options) {
+    let src = match read_file(path) {
+        Some(src) => src,
+        None => return
+    };
+    let table = match TruthTable::from_csv(src.as_slice()).and_then(|table| {
+        table.with_dont_cares(options.dont_cares.as_slice())
+    }) {
+        Ok(table) => table,
+        Err(err) => {
+            println!("Error: {}", err);
+            return
+        }
+    };
+
+    println!("Canonical: {}", Dnf::canonical(&table).to_expr().display(options.style));
+    if options.minimize {
+        let minimal = if options.heuristic { minimize::minimize_heuristic(&table) } else { minimize::minimize(&table) };
+        println!("Minimized: {}", minimal.display(options.style));
+    }
+}
+
+// Answers a JSON request on each line of stdin with a JSON response on a
+// line of stdout, so one process can serve an editor or a web backend.
+// Responses hold the `id` of the request, if it has one, and either a
+// `result` or an `error`
+fn serve(options: &Options) {
+    for line in std::io::stdin().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break
+        };
+        if line.as_slice().trim().is_empty() { continue }
+
+        let (id, response) = match json::from_str(line.as_slice()) {
+            Ok(request) => (request.find("id").map(|id| id.to_string()), respond(&request, options)),
+            Err(err) => (None, Err(format!("Invalid request: {}", err)))
+        };
+        let mut fields = Vec::new();
+        match id {
+            Some(id) => fields.push(format!("\"id\": {}", id)),
+            None => {}
+        }
+        match response {
+            Ok(result) => fields.push(format!("\"result\": {}", result)),
+            Err(msg) => fields.push(format!("\"error\": {}", json::encode(&msg)))
+        }
+        println!("{{{}}}", fields.connect(", "));
+        std::io::stdio::flush();
+    }
+}
+
+// The result of a request, such as `{"op": "table", "expr": "a & b"}`, as JSON
+fn respond(request: &json::Json, options: &Options) -> Result<String, String> {
+    let op = match request.find("op").and_then(|op| op.as_string()) {
+        Some(op) => op,
+        None => return Err("Missing op".to_string())
+    };
+    let expr = try!(request_expr(request, "expr", options));
+    let result = match op {
+        "table" => try!(table(&expr, options).map_err(|err| err.to_string())).to_json().replace("\n", ""),
+        "eval" => {
+            let mut names = Vec::new();
+            let mut values = Vec::new();
+            match request.find("set").and_then(|set| set.as_object()) {
+                Some(set) => for (name, value) in set.iter() {
+                    match value.as_boolean() {
+                        Some(value) => {
+                            names.push(name.clone());
+                            values.push(value);
+                        },
+                        None => return Err(format!("Value of {} isn't true or false", name))
+                    }
                 },
-                ref other => {
-                    return Err(ErrorPosition::from_token(
-                        format!("Unexpected token: {}", other), token.clone()
-                    ))
-                }
+                None => return Err("Missing set".to_string())
             }
-            token = self.next();
-        }
+            let assignment = Assignment::new(names, values);
+            try!(expr.eval_with(&assignment).map_err(|err| err.to_string())).to_string()
+        },
+        "simplify" => json::encode(&expr.simplify().display(options.style).to_string()),
+        "minimize" => {
+            let minimal = try!(expr.minimize_heuristic().map_err(|err| err.to_string()));
+            json::encode(&minimal.display(options.style).to_string())
+        },
+        "check" => match try!(expr.classify().map_err(|err| err.to_string())) {
+            Classification::Tautology => "\"tautology\"".to_string(),
+            Classification::Contradiction => "\"contradiction\"".to_string(),
+            Classification::Contingent(..) => "\"contingent\"".to_string()
+        },
+        "equiv" => {
+            let other = try!(request_expr(request, "other", options));
+            try!(expr.equivalent(&other).map_err(|err| err.to_string())).is_none().to_string()
+        },
+        _ => return Err(format!("Unknown op: {} (expected table, eval, simplify, minimize, check or equiv)", op))
+    };
+    Ok(result)
+}
 
-        Ok(Component { value: val, negated: neg })
+// The expression in the string field `field` of a request
+fn request_expr(request: &json::Json, field: &str, options: &Options) -> Result<Expr, String> {
+    match request.find(field).and_then(|src| src.as_string()) {
+        Some(src) => parse(src.to_string(), options.implicit_and).map_err(|err| err.to_string()),
+        None => Err(format!("Missing {}", field))
     }
-    
 }
 
-trait Environment {
-    fn get_variable(&self, name: String) -> bool;
+// The facts `batch` reports for an expression
+struct Report {
+    classification: &'static str,
+    vars:           uint,
+    // None when there are too many to count in a u64
+    models:         Option<u64>
 }
 
-struct EnvironmentImpl {
-    vars: HashMap<String, bool>
+// Fails with every parse error of `src`, or with the one evaluating it
+fn report(src: &str, options: &Options) -> Result<Report, Vec<TruthError>> {
+    let expr = try!(parse_all(src.to_string(), options.implicit_and));
+    let classification = match try!(expr.classify().map_err(|err| vec!(err))) {
+        Classification::Tautology => "tautology",
+        Classification::Contradiction => "contradiction",
+        Classification::Contingent(..) => "contingent"
+    };
+    let models = try!(expr.model_count().map_err(|err| vec!(err)));
+    Ok(Report { classification: classification, vars: expr.get_variables().len(), models: models })
 }
 
-impl Environment for EnvironmentImpl {
-    fn get_variable(&self, name: String) -> bool {
-        match self.vars.get(&name) {
-            Some(var) => *var,
-            None => false
+// Reports on each expression of a file, one per line, going on past the
+// ones which fail. With `--format json` the reports are a JSON array
+fn batch(path: &str, options: &Options) {
+    let mut input = match File::open(&Path::new(path)) {
+        Ok(file) => BufferedReader::new(file),
+        Err(err) => {
+            println!("Couldn't read {}: {}", path, err);
+            return
         }
+    };
+    let json = match options.format {
+        Some(Format::Json) => true,
+        _ => false
+    };
+
+    // The file is read a line at a time and each report printed as it is
+    // made, so a large file is never held in memory
+    if json { print!("[") }
+    let mut first = true;
+    for (idx, line) in input.lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                println!("Couldn't read {}: {}", path, err);
+                break
+            }
+        };
+        let line = line.as_slice().trim_right_chars(['\r', '\n'].as_slice());
+        if line.trim().is_empty() { continue }
+        let line_no = idx + 1;
+        let entry = match (report(line, options), json) {
+            (Ok(report), true) => {
+                let models = report.models.map_or("null".to_string(), |models| models.to_string());
+                format!("  {{\"line\": {}, \"expr\": {}, \"classification\": \"{}\", \"variables\": {}, \"models\": {}}}",
+                        line_no, json::encode(&line.trim().to_string()), report.classification, report.vars, models)
+            },
+            (Err(errs), true) => {
+                let errs: Vec<String> = errs.iter().map(|err| json::encode(&err.to_string())).collect();
+                format!("  {{\"line\": {}, \"expr\": {}, \"errors\": [{}]}}",
+                        line_no, json::encode(&line.trim().to_string()), errs.connect(", "))
+            },
+            (Ok(report), false) => {
+                let models = report.models.map_or("too many to count".to_string(), |models| models.to_string());
+                println!("{}: {}, {} variables, {} models", line_no, report.classification, report.vars, models);
+                continue
+            },
+            (Err(errs), false) => {
+                for err in errs.iter() { println!("{}: error: {}", line_no, err) }
+                continue
+            }
+        };
+        print!("{}\n{}", if first { "" } else { "," }, entry);
+        first = false;
     }
+    if json { print!("\n]\n") }
 }
 
-fn main() {
-    for line in std::io::stdin().lines() {
-        if line.is_ok() {
-            let mut string = line.unwrap();
-            string.pop();
-            let eval = parse_expr(string);
-            match eval {
-                Err(err) => {
-                    let rng = err.col_range;
-                    print!("{}", '~'.repeat(rng.val0() - 1));
-                    print!("{}\n", '^'.repeat(rng.val1() - rng.val0() + 1));
-                    println!("Error: \"{}\" at column {}, line {}", err.msg, rng.val0(), err.line);
-                },
-                _ => {}
+// The most variables `truth bench` goes up to unless told otherwise, about
+// a million rows
+const BENCH_VARS: uint = 20;
+
+// Tree walking stops here, past it the other ways are worth timing alone
+const BENCH_WALK_VARS: uint = 16;
+
+// Times evaluating every row of synthetic expressions of 4, 8 and so on
+// up to `max_vars` variables: walking the tree a row at a time, and the
+// compiled tables on one thread and on all of them
+fn bench(max_vars: uint) {
+    println!("{:>4}  {:>10}  {:>14}  {:>14}  {:>14}", "vars", "rows", "walk rows/s", "1 thread", "all threads");
+    let mut vars = 4;
+    while vars <= max_vars {
+        let expr = match synthetic(vars) {
+            Ok(expr) => expr,
+            Err(err) => {
+                println!("Error: {}", err);
+                return
+            }
+        };
+        let names = expr.get_variables();
+        let rows = match TruthTable::row_count(vars) {
+            Ok(rows) => rows,
+            Err(err) => {
+                println!("Error: {}", err);
+                return
+            }
+        };
+
+        let walk = if vars <= BENCH_WALK_VARS {
+            let start = time::precise_time_ns();
+            for row in range(0, rows) {
+                let values = range(0, vars).map(|bit| (row >> bit) & 1 == 1).collect();
+                let _ = expr.eval(&Assignment::new(names.clone(), values));
+            }
+            format!("{:.0}", per_second(rows, start))
+        } else {
+            "-".to_string()
+        };
+
+        let start = time::precise_time_ns();
+        let single = expr.truth_table_threads(1).map(|_| per_second(rows, start));
+        let start = time::precise_time_ns();
+        let threaded = expr.truth_table().map(|_| per_second(rows, start));
+        match (single, threaded) {
+            (Ok(single), Ok(threaded)) => {
+                println!("{:>4}  {:>10}  {:>14}  {:>14.0}  {:>14.0}", vars, rows, walk, single, threaded);
+            },
+            (Err(err), _) | (_, Err(err)) => {
+                println!("Error: {}", err);
+                return
             }
         }
+        vars += 4;
     }
 }
 
-fn parse_expr(src: String) -> Result<(), ErrorPosition> {
-    let mut lexer  = Lexer { reader: StringReader::new(src) };
-    let mut parser = try!(Parser::new(&mut lexer));
-    let root = try!(parser.parse());
+// `(v0 & !v1) ^ (v2 & !v3) ^ ...` over `vars` variables
+fn synthetic(vars: uint) -> Result<Expr, TruthError> {
+    let pairs: Vec<String> = range(0, vars / 2).map(|pair| format!("(v{} & !v{})", 2 * pair, 2 * pair + 1)).collect();
+    let mut src = pairs.connect(" ^ ");
+    if vars % 2 == 1 {
+        src.push_str(format!(" ^ v{}", vars - 1).as_slice());
+    }
+    Expr::parse(src.as_slice())
+}
+
+// Rows per second for `rows` rows since `start`, from `time::precise_time_ns`
+fn per_second(rows: uint, start: u64) -> f64 {
+    let elapsed = cmp::max(time::precise_time_ns() - start, 1);
+    rows as f64 * 1e9 / elapsed as f64
+}
 
-    let table = try!(root.truth_table());
-    let mut vars = root.get_variables();
+// The table with its columns in the order the options ask for
+fn table(expr: &Expr, options: &Options) -> Result<TruthTable, TruthError> {
+    let vars = variables(expr, options);
+    try!(check_size(vars.as_slice(), options));
+    Ok(shown_table(try!(expr.truth_table_ordered(vars)), options))
+}
+
+// The table with the rows given by `--dc` made don't cares
+fn dont_care_table(expr: &Expr, options: &Options) -> Result<TruthTable, TruthError> {
+    try!(table(expr, options)).with_dont_cares(options.dont_cares.as_slice())
+}
+
+fn check_size(vars: &[String], options: &Options) -> Result<(), TruthError> {
+    if vars.len() > options.max_vars { Err(TruthError::Overflow(vars.len())) } else { Ok(()) }
+}
 
-    vars.sort_by(|a, b| a.cmp(b));
+fn variables(expr: &Expr, options: &Options) -> Vec<String> {
+    order_variables(expr.get_variables(), options)
+}
 
-    println!("> Truth table:");
-    for var in vars.iter() {
-        print!("{}    ", var);
+// The variables of all the expressions, for a table of them side by side
+fn shared_variables(exprs: &[Expr], options: &Options) -> Vec<String> {
+    let mut vars: Vec<String> = Vec::new();
+    for expr in exprs.iter() {
+        for var in expr.get_variables().into_iter() {
+            if !vars.contains(&var) { vars.push(var) }
+        }
     }
+    order_variables(vars, options)
+}
+
+// Puts `vars`, listed in order of appearance, in the order the options ask for
+fn order_variables(vars: Vec<String>, options: &Options) -> Vec<String> {
+    let mut vars = match options.order {
+        Order::Alphabetical => {
+            let mut vars = vars;
+            vars.sort();
+            vars
+        },
+        Order::Appearance => vars,
+        Order::Given(ref given) => given.clone()
+    };
+    // Tables are built over the variables in reverse, so the first is the
+    // least significant bit of the row numbers, and shown in order again
+    if options.lsb_first { vars.reverse() }
+    vars
+}
+
+// Values for the variables from `order_variables` in the order they are
+// printed, undoing `--lsb-first`
+fn shown<T>(mut values: Vec<T>, options: &Options) -> Vec<T> {
+    if options.lsb_first { values.reverse() }
+    values
+}
+
+// `table` printed as `--lsb-first` asks
+fn shown_table(table: TruthTable, options: &Options) -> TruthTable {
+    if options.lsb_first { table.lsb_first() } else { table }
+}
+
+// The line above a section of the output, left out by `--quiet`
+fn banner(title: &str, options: &Options) {
+    if !options.quiet { println!("> {}:", title) }
+}
+
+// The row number column when it's asked for, headed by `#` and padded to
+// the widest number of a table of `rows` rows
+fn index_cell(rows: uint, index: Option<uint>, options: &Options) -> String {
+    if !options.index { return String::new() }
+    let cell = match index {
+        Some(index) => index.to_string(),
+        None => "#".to_string()
+    };
+    let width = (rows - 1).to_string().len();
+    format!("{}{}    ", ' '.repeat(width - cmp::min(cell.len(), width)), cell)
+}
+
+fn print_row(vars: &[String], rows: uint, index: uint, result: bool, options: &Options) {
+    if options.filter.matches(vars, index, result) {
+        let line = TruthTable::format_line(shown(vars.to_vec(), options).as_slice(), index, result, options.color,
+                                           options.lsb_first);
+        println!("{}{}", index_cell(rows, Some(index), options), line);
+    }
+}
+
+// The table with a column for each sub-expression between the inputs and
+// the result, each cell padded to the width of its heading
+fn print_subexpr_table(root: &Expr, table: &TruthTable, options: &Options) -> Result<(), TruthError> {
+    let mut headings: Vec<String> = table.columns();
+    let mut columns = Vec::new();
+    for sub in root.subexpressions().iter() {
+        headings.push(format!("{}", sub.display(options.style)));
+        columns.push(try!(sub.truth_table_ordered(table.variables().to_vec())));
+    }
+
+    banner("Truth table", options);
+    print!("{}", index_cell(table.len(), None, options));
+    for heading in headings.iter() { print!("{}    ", heading) }
     print!("Result\n\n");
+    for row in table.iter_rows().filter(|row| options.filter.matches(table.variables(), row.index(), row.result())) {
+        print!("{}", index_cell(table.len(), Some(row.index()), options));
+        let mut values = shown(row.inputs(), options);
+        values.extend(columns.iter().map(|column| column.row(row.index()).result()));
+        for (value, heading) in values.iter().zip(headings.iter()) {
+            print!("{}{}    ", *value as u8, ' '.repeat(heading.as_slice().char_len() - 1));
+        }
+        println!("{}", row.result() as u8);
+    }
+    Ok(())
+}
+
+// One table over the variables of all the expressions with a result column
+// for each, headed by the expression. A row is shown when the filter
+// matches it for every result
+fn print_multi_table(exprs: &[Expr], options: &Options) -> Result<(), TruthError> {
+    let vars = shared_variables(exprs, options);
+    match options.filter.inputs.iter().find(|&&(ref name, _)| !vars.contains(name)) {
+        Some(&(ref name, _)) => {
+            println!("Unknown variable in filter: {}", name);
+            return Ok(())
+        },
+        None => {}
+    }
+    try!(check_size(vars.as_slice(), options));
 
-    for &(ref vars, ref res) in table.iter() {
-        let mut sorted = Vec::with_capacity(table.len());
-        for pair in vars.iter() { sorted.push(pair); }
-        sorted.sort_by(|a, b| a.val0().cmp(b.val0()));
-        for &(ref name, ref val) in sorted.iter() {
-            print!("{}{}    ", **val as u8, ' '.repeat(name.len()));
+    let mut tables = Vec::new();
+    for expr in exprs.iter() {
+        tables.push(try!(expr.truth_table_ordered(vars.clone())));
+    }
+    let mut headings = shown(vars.clone(), options);
+    headings.extend(exprs.iter().map(|expr| format!("{}", expr.display(options.style))));
+
+    let rows = tables[0].len();
+    banner("Truth table", options);
+    print!("{}{}\n\n", index_cell(rows, None, options), headings.connect("    "));
+    for index in range(0, rows) {
+        let results: Vec<bool> = tables.iter().map(|table| table.row(index).result()).collect();
+        if !results.iter().all(|&result| options.filter.matches(vars.as_slice(), index, result)) { continue }
+        let values: Vec<bool> = range(0, vars.len()).map(|pos| (index >> (vars.len() - 1 - pos)) & 1 == 1).collect();
+        let mut values = shown(values, options);
+        values.extend(results.into_iter());
+        let cells: Vec<String> = values.iter().zip(headings.iter()).map(|(value, heading)| {
+            format!("{}{}", *value as u8, ' '.repeat(heading.as_slice().char_len() - 1))
+        }).collect();
+        println!("{}{}", index_cell(rows, Some(index), options), cells.connect("    ").as_slice().trim_right());
+    }
+    if !options.quiet { println!("> Variables: {}", shown(vars, options)) }
+    Ok(())
+}
+
+// The table over 0, X and 1 for each variable, with cells padded to the
+// width of the headings like the two-valued table
+fn print_kleene_table(root: &Expr, options: &Options) -> Result<(), TruthError> {
+    let vars = variables(root, options);
+    let rows = try!(kleene::table(root, vars.as_slice()));
+    banner("Three-valued truth table", options);
+    let columns = shown(vars.clone(), options);
+    print!("{}\n\n", TruthTable::format_heading(columns.as_slice()));
+    for &(ref inputs, ref result) in rows.iter() {
+        let mut line = String::new();
+        for (value, var) in shown(inputs.clone(), options).iter().zip(columns.iter()) {
+            line.push_str(format!("{}{}    ", value, ' '.repeat(var.as_slice().char_len() - 1)).as_slice());
+        }
+        println!("{}{}", line, result);
+    }
+    if !options.quiet {
+        println!("> Parsed tree:\n{}", root.display(options.style));
+        println!("> Variables: {}", vars);
+    }
+    Ok(())
+}
+
+// Prints the table, or whatever the options ask for instead. Several
+// expressions share one table
+fn show(exprs: &[Expr], options: &Options) -> Result<(), TruthError> {
+    if exprs.len() > 1 { return print_multi_table(exprs, options) }
+    let root = &exprs[0];
+    if options.kleene { return print_kleene_table(root, options) }
+
+    match options.sample {
+        Some(count) => {
+            let samples = try!(root.sample(count));
+            if samples.is_empty() { println!("Unsatisfiable") }
+            for model in samples.iter() {
+                println!("{}", model);
+            }
+            return Ok(())
+        },
+        None => {}
+    }
+
+    if !options.probs.is_empty() {
+        let vars = root.get_variables();
+        match options.probs.iter().find(|&&(ref name, _)| !vars.contains(name)) {
+            Some(&(ref name, _)) => println!("Unknown variable in probabilities: {}", name),
+            None => println!("{}", try!(root.probability(options.probs.as_slice())))
+        }
+        return Ok(())
+    }
+
+    match options.weights {
+        Some(ref weights) => {
+            let vars = root.get_variables();
+            match weights.iter().find(|&&(ref name, _, _)| !vars.contains(name)) {
+                Some(&(ref name, _, _)) => println!("Unknown variable in weights: {}", name),
+                None => println!("{}", try!(root.weighted_count(weights.as_slice())))
+            }
+            return Ok(())
+        },
+        None => {}
+    }
+
+    match options.format {
+        // Tseitin unless `--cnf` is given without `--tseitin`
+        Some(Format::Dimacs) => {
+            let method = options.cnf.unwrap_or(CnfMethod::Tseitin);
+            print!("{}", try!(root.cnf(method)).to_dimacs());
+            return Ok(())
+        },
+        Some(Format::Pla) => {
+            print!("{}", try!(dont_care_table(root, options)).to_pla());
+            return Ok(())
+        },
+        Some(Format::Dot) => {
+            print!("{}", root.to_dot());
+            return Ok(())
+        },
+        Some(Format::Sexpr) => {
+            println!("{}", root.to_sexpr());
+            return Ok(())
+        },
+        Some(Format::BddDot) => {
+            let (bdd, f) = try!(root.to_bdd());
+            print!("{}", bdd.to_dot(f));
+            return Ok(())
+        },
+        Some(Format::Svg) => {
+            print!("{}", root.to_svg());
+            return Ok(())
+        },
+        Some(Format::Json) => {
+            print!("{}", try!(table(root, options)).to_json());
+            return Ok(())
+        },
+        Some(Format::Csv) => {
+            print!("{}", try!(table(root, options)).to_delimited(',', !options.no_header));
+            return Ok(())
+        },
+        Some(Format::Tsv) => {
+            print!("{}", try!(table(root, options)).to_delimited('\t', !options.no_header));
+            return Ok(())
+        },
+        // The expression heads the result column
+        Some(Format::Latex) => {
+            let result = format!("${}$", root.display(Style::Latex));
+            print!("{}", try!(table(root, options)).to_latex(result.as_slice()));
+            return Ok(())
+        },
+        Some(Format::Html) => {
+            let caption = format!("{}", root.display(options.style));
+            print!("{}", try!(table(root, options)).to_html(Some(caption.as_slice())));
+            return Ok(())
+        },
+        None => {}
+    }
+
+    match options.cnf {
+        Some(method) => {
+            println!("{}", try!(root.cnf(method)).to_expr().display(options.style));
+            return Ok(())
+        },
+        None => {}
+    }
+
+    match options.dnf {
+        Some(method) => {
+            println!("{}", try!(root.dnf(method)).to_expr().display(options.style));
+            return Ok(())
+        },
+        None => {}
+    }
+
+    if options.nnf {
+        println!("{}", try!(root.to_nnf()).display(options.style));
+        return Ok(())
+    }
+
+    if options.anf {
+        println!("{}", try!(root.to_anf()).display(options.style));
+        return Ok(())
+    }
+
+    let vars = variables(root, options);
+    match options.filter.inputs.iter().find(|&&(ref name, _)| !vars.contains(name)) {
+        Some(&(ref name, _)) => {
+            println!("Unknown variable in filter: {}", name);
+            return Ok(())
+        },
+        None => {}
+    }
+
+    // Pages and large tables are printed as the rows are evaluated
+    let paged = options.limit.is_some() || options.offset.is_some();
+    if !paged { try!(check_size(vars.as_slice(), options)) }
+    if paged || (vars.len() >= STREAM_VARS && !options.subexprs) {
+        let start = options.offset.unwrap_or(0);
+        let end = options.limit.map_or(uint::MAX, |limit| start.checked_add(limit).unwrap_or(uint::MAX));
+        let stream = try!(root.stream(vars.clone(), start, end));
+        let rows = 1u << vars.len();
+        banner("Truth table", options);
+        let columns = shown(vars.clone(), options);
+        print!("{}{}\n\n", index_cell(rows, None, options), TruthTable::format_heading(columns.as_slice()));
+        for (index, result) in stream {
+            print_row(vars.as_slice(), rows, index, result, options);
+        }
+        if !options.quiet {
+            println!("> Parsed tree:\n{}", root.display(options.style));
+            println!("> Variables: {}", columns);
+        }
+        return print_warnings(root, options)
+    }
+
+    let table = shown_table(try!(root.truth_table_ordered(vars)), options);
+    if options.subexprs {
+        try!(print_subexpr_table(root, &table, options));
+    } else {
+        banner("Truth table", options);
+        print!("{}{}\n\n", index_cell(table.len(), None, options), table.format_header());
+        for row in table.iter_rows() {
+            print_row(table.variables(), table.len(), row.index(), row.result(), options);
         }
-        print!("{}\n", *res as u8);    
     }
-    
-    println!("> Parsed tree:\n{}", root);
-    println!("> Variables: {}", vars);
+    if !options.quiet {
+        println!("> Minterms: {}", table.format_minterms());
+        println!("> Maxterms: {}", table.format_maxterms());
+        println!("> Parsed tree:\n{}", root.display(options.style));
+        println!("> Variables: {}", table.columns());
+    }
+    print_warnings(root, options)
+}
+
+// What the linter has to say about the expression, after its table.
+// Left out by `--quiet`
+fn print_warnings(root: &Expr, options: &Options) -> Result<(), TruthError> {
+    if options.quiet { return Ok(()) }
+    let warnings = if options.lint { try!(lint::lint_all(root)) } else { lint::lint(root) };
+    for warning in warnings.iter() {
+        println!("warning: {}", warning);
+    }
     Ok(())
 }
@@ -0,0 +1,316 @@
+//! Two-level minimization of truth tables.
+//!
+//! Prime implicants are found with the Quine-McCluskey method: minterms
+//! differing in one variable are merged until nothing merges, and the terms
+//! left unmerged are prime. A cover of the minterms is then picked from them,
+//! either greedily or exactly with Petrick's method.
+//!
+//! Exact minimization grows too quickly past a dozen or so variables, so
+//! `espresso` improves a cover directly from the minterms instead, with the
+//! expand, irredundant and reduce steps of the Espresso heuristic.
+//!
+//! The don't care rows of a table may be covered or not, whichever makes
+//! the cover smaller: they are merged into primes like minterms but never
+//! need covering, and the heuristic grows cubes over them.
+
+use std::num::Int;
+
+use expr::{Component, Expr, VarOrExpr};
+use lexer::Type;
+use table::TruthTable;
+
+/// A product of literals, as rows of a table: a row is covered when it
+/// matches `bits` everywhere outside `mask`. Bits are numbered like row
+/// indices, the first variable being the most significant
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+#[deriving(Eq)]
+#[deriving(Hash)]
+pub struct Implicant {
+    pub bits: uint,
+    /// Variables the product doesn't depend on, their bits are clear in `bits`
+    pub mask: uint
+}
+
+impl Implicant {
+    pub fn minterm(row: uint) -> Implicant {
+        Implicant { bits: row, mask: 0 }
+    }
+
+    pub fn covers(&self, row: uint) -> bool {
+        row & !self.mask == self.bits
+    }
+
+    /// The number of literals in a product over `vars` variables
+    pub fn literals(&self, vars: uint) -> uint {
+        vars - self.mask.count_ones()
+    }
+
+    /// The product over `vars`, a constant true for the empty product
+    pub fn to_component(&self, vars: &[String]) -> Component {
+        let mut literals = Vec::new();
+        for (pos, var) in vars.iter().enumerate() {
+            let bit = 1 << (vars.len() - 1 - pos);
+            if self.mask & bit == 0 {
+                literals.push(Component { value: VarOrExpr::Var(var.clone()), negated: self.bits & bit == 0 });
+            }
+        }
+        if literals.is_empty() {
+            return Component { value: VarOrExpr::Const(true), negated: false }
+        }
+        Expr::join(literals, Type::And).into_component()
+    }
+
+    /// Whether every row covered by `other` is covered by this
+    pub fn contains(&self, other: &Implicant) -> bool {
+        self.mask & other.mask == other.mask && other.bits & !self.mask == self.bits
+    }
+
+    // The merge of two implicants differing in one variable
+    fn merge(&self, other: &Implicant) -> Option<Implicant> {
+        let diff = self.bits ^ other.bits;
+        if self.mask != other.mask || diff.count_ones() != 1 { return None }
+        Some(Implicant { bits: self.bits & !diff, mask: self.mask | diff })
+    }
+}
+
+/// Every prime implicant of the rows where `table` is true or a don't care,
+/// leaving out those covering only don't cares
+pub fn prime_implicants(table: &TruthTable) -> Vec<Implicant> {
+    let mut rows = table.minterms();
+    rows.push_all(table.dont_cares());
+    let mut terms: Vec<Implicant> = rows.into_iter().map(|row| Implicant::minterm(row)).collect();
+    let mut primes = Vec::new();
+
+    while !terms.is_empty() {
+        let mut merged = Vec::new();
+        let mut used = Vec::from_elem(terms.len(), false);
+        for i in range(0, terms.len()) {
+            for j in range(i + 1, terms.len()) {
+                match terms[i].merge(&terms[j]) {
+                    Some(term) => {
+                        used[i] = true;
+                        used[j] = true;
+                        if !merged.contains(&term) { merged.push(term) }
+                    },
+                    None => {}
+                }
+            }
+        }
+
+        for (idx, term) in terms.iter().enumerate() {
+            if !used[idx] { primes.push(term.clone()) }
+        }
+        terms = merged;
+    }
+    let on = table.minterms();
+    primes.retain(|prime| on.iter().any(|&row| prime.covers(row)));
+    primes
+}
+
+/// Primes covering every row where `table` is true: the essential primes,
+/// then whichever covers the most rows left until none are
+pub fn cover(table: &TruthTable, primes: &[Implicant]) -> Vec<Implicant> {
+    let vars = table.variables().len();
+    let mut left = table.minterms();
+    let mut chosen = essentials(primes, left.as_slice());
+    left.retain(|&row| !chosen.iter().any(|term| term.covers(row)));
+
+    while !left.is_empty() {
+        // Most rows covered, then fewest literals
+        let best = primes.iter().max_by(|prime| {
+            let covered = left.iter().filter(|&&row| prime.covers(row)).count();
+            (covered, vars - prime.literals(vars))
+        }).unwrap().clone();
+        left.retain(|&row| !best.covers(row));
+        chosen.push(best);
+    }
+
+    chosen.sort_by(|a, b| a.bits.cmp(&b.bits));
+    chosen
+}
+
+/// Every cover with the fewest primes, and of those the fewest literals,
+/// found with Petrick's method. The product over the rows left after the
+/// essential primes of the sum of the primes covering each row is expanded,
+/// each of its products being a cover
+pub fn minimal_covers(table: &TruthTable, primes: &[Implicant]) -> Vec<Vec<Implicant>> {
+    let vars = table.variables().len();
+    let mut left = table.minterms();
+    let essential = essentials(primes, left.as_slice());
+    left.retain(|&row| !essential.iter().any(|term| term.covers(row)));
+
+    // Products as sorted indices into `primes`, starting from the empty product
+    let mut products: Vec<Vec<uint>> = vec!(vec!());
+    for &row in left.iter() {
+        let covering: Vec<uint> = range(0, primes.len()).filter(|&idx| primes[idx].covers(row)).collect();
+        let mut expanded: Vec<Vec<uint>> = Vec::new();
+        for product in products.iter() {
+            if product.iter().any(|idx| covering.contains(idx)) {
+                expanded.push(product.clone());
+                continue
+            }
+            for &idx in covering.iter() {
+                let mut term = product.clone();
+                term.push(idx);
+                term.sort();
+                expanded.push(term);
+            }
+        }
+        products = absorb(expanded);
+    }
+
+    let best = products.iter().map(|product| cost(primes, vars, product.as_slice())).min().unwrap();
+
+    products.iter().filter(|product| cost(primes, vars, product.as_slice()) == best).map(|product| {
+        let mut chosen = essential.clone();
+        chosen.extend(product.iter().map(|&idx| primes[idx].clone()));
+        chosen.sort_by(|a, b| a.bits.cmp(&b.bits));
+        chosen
+    }).collect()
+}
+
+/// The products joined by ORs, a constant false when there are none
+pub fn sum_of_products(vars: &[String], terms: &[Implicant]) -> Expr {
+    match terms.len() {
+        0 => return Expr::from_component(Component { value: VarOrExpr::Const(false), negated: false }),
+        1 => return Expr::from_component(terms[0].to_component(vars)),
+        _ => {}
+    }
+    Expr::join(terms.iter().map(|term| term.to_component(vars)).collect(), Type::Or)
+}
+
+/// A sum of prime implicants equal to the table outside its don't cares
+/// with as few terms as possible, the first of `minimal_covers`
+pub fn minimize(table: &TruthTable) -> Expr {
+    let primes = prime_implicants(table);
+    let covers = minimal_covers(table, primes.as_slice());
+    sum_of_products(table.variables(), covers[0].as_slice())
+}
+
+/// Every minimal sum of products equal to the table outside its don't cares
+pub fn minimal_forms(table: &TruthTable) -> Vec<Expr> {
+    let primes = prime_implicants(table);
+    minimal_covers(table, primes.as_slice()).iter()
+        .map(|cover| sum_of_products(table.variables(), cover.as_slice())).collect()
+}
+
+/// A cover of the table found with the Espresso heuristic, which is
+/// irredundant but not always minimal
+pub fn espresso(table: &TruthTable) -> Vec<Implicant> {
+    let vars = table.variables().len();
+    let on = table.minterms();
+    let off = table.maxterms();
+
+    let cover = on.iter().map(|&row| Implicant::minterm(row)).collect();
+    let mut cover = irredundant(expand(cover, off.as_slice(), vars), on.as_slice());
+    // Reducing the cubes lets the next expansion grow them in other directions
+    loop {
+        let reduced = reduce(cover.clone(), on.as_slice());
+        let next = irredundant(expand(reduced, off.as_slice(), vars), on.as_slice());
+        if total_cost(next.as_slice(), vars) >= total_cost(cover.as_slice(), vars) { break }
+        cover = next;
+    }
+
+    cover.sort_by(|a, b| a.bits.cmp(&b.bits));
+    cover
+}
+
+/// `espresso` as a sum of products
+pub fn minimize_heuristic(table: &TruthTable) -> Expr {
+    sum_of_products(table.variables(), espresso(table).as_slice())
+}
+
+// Grows each cube one variable at a time while it stays clear of the false
+// rows, dropping cubes an earlier one has grown to contain
+fn expand(cover: Vec<Implicant>, off: &[uint], vars: uint) -> Vec<Implicant> {
+    let mut cover = cover;
+    // Largest first, so they get the chance to swallow the small ones
+    cover.sort_by(|a, b| b.mask.count_ones().cmp(&a.mask.count_ones()));
+
+    let mut expanded: Vec<Implicant> = Vec::new();
+    for cube in cover.into_iter() {
+        if expanded.iter().any(|big| big.contains(&cube)) { continue }
+        let mut cube = cube;
+        for var in range(0, vars) {
+            let bit = 1 << var;
+            if cube.mask & bit != 0 { continue }
+            let raised = Implicant { bits: cube.bits & !bit, mask: cube.mask | bit };
+            if !off.iter().any(|&row| raised.covers(row)) { cube = raised }
+        }
+        expanded.push(cube);
+    }
+    expanded
+}
+
+// Drops cubes whose true rows are all covered by the other cubes, trying
+// the smallest first
+fn irredundant(cover: Vec<Implicant>, on: &[uint]) -> Vec<Implicant> {
+    let mut cover = cover;
+    cover.sort_by(|a, b| a.mask.count_ones().cmp(&b.mask.count_ones()));
+
+    let mut idx = 0;
+    while idx < cover.len() {
+        let redundant = on.iter().filter(|&&row| cover[idx].covers(row)).all(|&row| {
+            range(0, cover.len()).any(|other| other != idx && cover[other].covers(row))
+        });
+        if redundant { cover.remove(idx); } else { idx += 1 }
+    }
+    cover
+}
+
+// Shrinks each cube to the smallest one covering the true rows no other
+// cube covers
+fn reduce(cover: Vec<Implicant>, on: &[uint]) -> Vec<Implicant> {
+    let mut cover = cover;
+    let mut idx = 0;
+    while idx < cover.len() {
+        let unique: Vec<uint> = on.iter().map(|&row| row).filter(|&row| {
+            cover[idx].covers(row) && !range(0, cover.len()).any(|other| other != idx && cover[other].covers(row))
+        }).collect();
+        if unique.is_empty() {
+            cover.remove(idx);
+            continue
+        }
+
+        let mask = unique.iter().fold(0, |mask, &row| mask | (row ^ unique[0]));
+        cover[idx] = Implicant { bits: unique[0] & !mask, mask: mask };
+        idx += 1;
+    }
+    cover
+}
+
+fn total_cost(cover: &[Implicant], vars: uint) -> (uint, uint) {
+    (cover.len(), cover.iter().fold(0, |sum, cube| sum + cube.literals(vars)))
+}
+
+// The primes which are the only one covering some row
+fn essentials(primes: &[Implicant], rows: &[uint]) -> Vec<Implicant> {
+    let mut chosen: Vec<Implicant> = Vec::new();
+    for &row in rows.iter() {
+        let covering: Vec<&Implicant> = primes.iter().filter(|prime| prime.covers(row)).collect();
+        if covering.len() == 1 && !chosen.contains(covering[0]) {
+            chosen.push(covering[0].clone());
+        }
+    }
+    chosen
+}
+
+// Primes in a product, then literals in them
+fn cost(primes: &[Implicant], vars: uint, product: &[uint]) -> (uint, uint) {
+    (product.len(), product.iter().fold(0, |sum, &idx| sum + primes[idx].literals(vars)))
+}
+
+// Drops duplicate products and those containing another product, by
+// absorption `x + xy = x`
+fn absorb(products: Vec<Vec<uint>>) -> Vec<Vec<uint>> {
+    let mut kept: Vec<Vec<uint>> = Vec::new();
+    let mut products = products;
+    products.sort_by(|a, b| a.len().cmp(&b.len()));
+    for product in products.into_iter() {
+        let absorbed = kept.iter().any(|smaller| smaller.iter().all(|idx| product.contains(idx)));
+        if !absorbed { kept.push(product) }
+    }
+    kept
+}
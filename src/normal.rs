@@ -0,0 +1,580 @@
+//! Normal forms of expressions.
+//!
+//! Negations are pushed down to the variables first, giving a tree of ANDs
+//! and ORs over literals in the order the operands were written. The
+//! two-level forms are distributed from there, while the Tseitin encoding
+//! goes through the `Dag` so shared gates get a single variable.
+
+use std::num::Int;
+
+use dag::{Dag, Node, NodeId};
+use error::{Span, TruthError};
+use expr::{undefined_function, Component, Expr, VarOrExpr};
+use lexer::Type;
+use minimize::Implicant;
+use table::TruthTable;
+
+/// A variable, by its index in the variables of the form, or its negation
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+#[deriving(Eq)]
+#[deriving(Hash)]
+pub struct Literal {
+    pub var:     uint,
+    pub negated: bool
+}
+
+impl Literal {
+    pub fn negate(&self) -> Literal {
+        Literal { var: self.var, negated: !self.negated }
+    }
+}
+
+/// How `Expr::cnf` builds its clauses
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+pub enum CnfMethod {
+    /// Distributes ORs over ANDs, giving an equivalent form which may be
+    /// exponentially larger
+    Distribute,
+    /// Adds a variable per gate, giving a form of linear size which is
+    /// satisfiable exactly when the expression is
+    Tseitin
+}
+
+/// An AND of clauses, each an OR of literals
+#[deriving(Show)]
+#[deriving(Clone)]
+pub struct Cnf {
+    pub vars:    Vec<String>,
+    pub clauses: Vec<Vec<Literal>>
+}
+
+impl Cnf {
+    /// A clause for each row where `table` is false, ruling that row out.
+    /// Don't cares get no clause, so they are left free
+    pub fn canonical(table: &TruthTable) -> Cnf {
+        let len = table.variables().len();
+        let clauses = table.maxterms().iter().map(|&row| {
+            range(0, len).map(|pos| Literal { var: pos, negated: row & (1 << (len - 1 - pos)) != 0 }).collect()
+        }).collect();
+        Cnf { vars: table.variables().to_vec(), clauses: clauses }
+    }
+
+    /// A constant true when there are no clauses and false for an empty clause
+    pub fn to_expr(&self) -> Expr {
+        two_level(self.vars.as_slice(), self.clauses.as_slice(), Type::And, Type::Or)
+    }
+
+    /// Reads clauses in DIMACS format. Comments like the ones `to_dimacs`
+    /// writes name the variables, the rest are called `x1`, `x2` and so on
+    pub fn from_dimacs(src: &str) -> Result<Cnf, TruthError> {
+        let mut names: Vec<Option<String>> = Vec::new();
+        let mut clauses = Vec::new();
+        let mut clause = Vec::new();
+        let mut header: Option<(uint, uint)> = None;
+
+        for (idx, line) in src.lines().enumerate() {
+            let line_num = idx + 1;
+            let words: Vec<&str> = line.words().collect();
+            if words.is_empty() || words[0] == "%" { continue }
+
+            if words[0] == "c" {
+                // `c 3 name` names variable 3
+                if words.len() == 3 {
+                    match from_str::<uint>(words[1]) {
+                        Some(var) if var > 0 => {
+                            while names.len() < var { names.push(None) }
+                            names[var - 1] = Some(words[2].to_string());
+                        },
+                        _ => {}
+                    }
+                }
+                continue
+            }
+
+            if words[0] == "p" {
+                let counts = if words.len() == 4 && words[1] == "cnf" {
+                    from_str::<uint>(words[2]).and_then(|vars| from_str::<uint>(words[3]).map(|count| (vars, count)))
+                } else {
+                    None
+                };
+                if counts.is_none() || header.is_some() {
+                    return Err(TruthError::Parse("Invalid problem line".to_string(), Some(Span::new(line_num, 1, line.char_len()))))
+                }
+                header = counts;
+                continue
+            }
+
+            let vars = match header {
+                Some((vars, _)) => vars,
+                None => return Err(TruthError::Parse("Clause before the problem line".to_string(), Some(Span::new(line_num, 1, 1))))
+            };
+            for word in words.iter() {
+                let lit = match from_str::<int>(*word) {
+                    Some(lit) if lit.abs() as uint <= vars => lit,
+                    _ => return Err(TruthError::Parse(format!("Invalid literal: {}", word), Some(Span::new(line_num, 1, line.char_len()))))
+                };
+                if lit == 0 {
+                    clauses.push(clause);
+                    clause = Vec::new();
+                } else {
+                    clause.push(Literal { var: lit.abs() as uint - 1, negated: lit < 0 });
+                }
+            }
+        }
+        // The last clause may leave out its 0
+        if !clause.is_empty() { clauses.push(clause) }
+
+        let vars = match header {
+            Some((vars, _)) => vars,
+            None => return Err(TruthError::Parse("Missing problem line".to_string(), None))
+        };
+        let vars = range(0, vars).map(|var| match names.get(var) {
+            Some(&Some(ref name)) => name.clone(),
+            _ => format!("x{}", var + 1)
+        }).collect();
+        Ok(Cnf { vars: vars, clauses: clauses })
+    }
+
+    /// The clauses in the DIMACS format read by SAT solvers. Variables are
+    /// numbered from 1 in the order of `vars`, and comment lines at the top
+    /// map each number back to its name
+    pub fn to_dimacs(&self) -> String {
+        let mut dimacs = String::new();
+        for (idx, var) in self.vars.iter().enumerate() {
+            dimacs.push_str(format!("c {} {}\n", idx + 1, var).as_slice());
+        }
+        dimacs.push_str(format!("p cnf {} {}\n", self.vars.len(), self.clauses.len()).as_slice());
+        for clause in self.clauses.iter() {
+            for lit in clause.iter() {
+                let sign = if lit.negated { "-" } else { "" };
+                dimacs.push_str(format!("{}{} ", sign, lit.var + 1).as_slice());
+            }
+            dimacs.push_str("0\n");
+        }
+        dimacs
+    }
+}
+
+/// How `Expr::dnf` builds its terms
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+pub enum DnfMethod {
+    /// Distributes ANDs over ORs in the expression
+    Distribute,
+    /// A term for each true row of the truth table, using every variable
+    Canonical,
+    /// A minimal cover of the true rows, see `minimize`
+    Minimal
+}
+
+/// An OR of terms, each an AND of literals
+#[deriving(Show)]
+#[deriving(Clone)]
+pub struct Dnf {
+    pub vars:  Vec<String>,
+    pub terms: Vec<Vec<Literal>>
+}
+
+impl Dnf {
+    /// The terms of the rows where `table` is true
+    pub fn canonical(table: &TruthTable) -> Dnf {
+        let rows: Vec<Implicant> = table.iter_rows().filter(|row| row.result())
+            .map(|row| Implicant::minterm(row.index())).collect();
+        Dnf::from_implicants(table.variables().to_vec(), rows.as_slice())
+    }
+
+    pub fn from_implicants(vars: Vec<String>, implicants: &[Implicant]) -> Dnf {
+        let len = vars.len();
+        let terms = implicants.iter().map(|implicant| {
+            range(0, len).filter(|&pos| implicant.mask & (1 << (len - 1 - pos)) == 0).map(|pos| {
+                Literal { var: pos, negated: implicant.bits & (1 << (len - 1 - pos)) == 0 }
+            }).collect()
+        }).collect();
+        Dnf { vars: vars, terms: terms }
+    }
+
+    /// A constant false when there are no terms and true for an empty term
+    pub fn to_expr(&self) -> Expr {
+        two_level(self.vars.as_slice(), self.terms.as_slice(), Type::Or, Type::And)
+    }
+}
+
+/// Algebraic normal form: an XOR of ANDs of variables, the Zhegalkin
+/// polynomial of the function
+#[deriving(Show)]
+#[deriving(Clone)]
+pub struct Anf {
+    pub vars:      Vec<String>,
+    /// The variables of each AND, numbered like the bits of a row index with
+    /// the first variable the most significant. 0 is the constant true
+    pub monomials: Vec<uint>
+}
+
+impl Anf {
+    /// Finds the coefficients with the binary Moebius transform of the results
+    pub fn from_table(table: &TruthTable) -> Anf {
+        let mut coeffs: Vec<bool> = table.iter_rows().map(|row| row.result()).collect();
+        for bit in range(0, table.variables().len()) {
+            let step = 1u << bit;
+            for idx in range(0, coeffs.len()) {
+                if idx & step != 0 {
+                    let lower = coeffs[idx ^ step];
+                    coeffs[idx] = coeffs[idx] != lower;
+                }
+            }
+        }
+
+        let mut monomials: Vec<uint> = range(0, coeffs.len()).filter(|&idx| coeffs[idx]).collect();
+        // Lowest degree first, then in variable order
+        monomials.sort_by(|a, b| (a.count_ones(), *b).cmp(&(b.count_ones(), *a)));
+        Anf { vars: table.variables().to_vec(), monomials: monomials }
+    }
+
+    /// A constant false when there are no monomials
+    pub fn to_expr(&self) -> Expr {
+        let len = self.vars.len();
+        let terms: Vec<Component> = self.monomials.iter().map(|&monomial| {
+            if monomial == 0 {
+                return Component { value: VarOrExpr::Const(true), negated: false }
+            }
+            let vars = range(0, len).filter(|&pos| monomial & (1 << (len - 1 - pos)) != 0).map(|pos| {
+                Component { value: VarOrExpr::Var(self.vars[pos].clone()), negated: false }
+            }).collect();
+            Expr::join(vars, Type::And).into_component()
+        }).collect();
+
+        if terms.is_empty() { return constant(false) }
+        Expr::from_component(Expr::join(terms, Type::Xor).into_component())
+    }
+}
+
+// An expression with negations only on variables
+#[deriving(Clone)]
+enum Formula {
+    Const(bool),
+    Lit(Literal),
+    And(Vec<Formula>),
+    Or(Vec<Formula>)
+}
+
+/// Equivalent clauses by distribution, over `vars` followed by any other
+/// variables of `expr`
+pub fn cnf_distribute(expr: &Expr, vars: &[String]) -> Result<Cnf, TruthError> {
+    let mut vars = vars.to_vec();
+    let formula = try!(negation_normal(expr, &mut vars));
+    Ok(Cnf { vars: vars, clauses: simplify(distribute(&formula, true)) })
+}
+
+/// Equivalent terms by distribution, over `vars` followed by any other
+/// variables of `expr`
+pub fn dnf_distribute(expr: &Expr, vars: &[String]) -> Result<Dnf, TruthError> {
+    let mut vars = vars.to_vec();
+    let formula = try!(negation_normal(expr, &mut vars));
+    Ok(Dnf { vars: vars, terms: simplify(distribute(&formula, false)) })
+}
+
+/// An equivalent expression using only AND, OR and negated variables.
+/// Implications and equivalences are written out and negations pushed
+/// inwards with De Morgan's laws
+pub fn nnf(expr: &Expr) -> Result<Expr, TruthError> {
+    let mut vars = Vec::new();
+    let formula = try!(negation_normal(expr, &mut vars));
+    Ok(Expr::from_component(formula_component(&formula, vars.as_slice())))
+}
+
+/// Equisatisfiable clauses with a fresh variable for each gate and constant, named
+/// `_t0`, `_t1`, ... unless those are taken
+pub fn cnf_tseitin(expr: &Expr, vars: &[String]) -> Result<Cnf, TruthError> {
+    let dag = try!(Dag::from_expr(expr, vars));
+    let mut vars = dag.variables().to_vec();
+    let mut prefix = "_t".to_string();
+    while vars.iter().any(|var| var.as_slice().starts_with(prefix.as_slice())) {
+        prefix.push('_');
+    }
+
+    // The literal standing for each node
+    let mut lits: Vec<Literal> = Vec::with_capacity(dag.nodes().len());
+    let mut clauses: Vec<Vec<Literal>> = Vec::new();
+    for node in dag.nodes().iter() {
+        let lit = match *node {
+            Node::Var(idx) => Literal { var: idx, negated: false },
+            Node::Not(a) => lits[a].negate(),
+            _ => {
+                let out = Literal { var: vars.len(), negated: false };
+                vars.push(format!("{}{}", prefix, vars.len() - dag.variables().len()));
+                let ins: Vec<Literal> = operands(node).iter().map(|&id| lits[id]).collect();
+                gate_clauses(node, out, ins.as_slice(), &mut clauses);
+                out
+            }
+        };
+        lits.push(lit);
+    }
+
+    clauses.push(vec!(lits[dag.root()]));
+    Ok(Cnf { vars: vars, clauses: clauses })
+}
+
+fn operands(node: &Node) -> Vec<NodeId> {
+    match *node {
+        Node::Var(_) | Node::Const(_) => vec!(),
+        Node::Not(a) => vec!(a),
+        Node::And(a, b) | Node::Or(a, b) | Node::Xor(a, b) => vec!(a, b),
+        Node::Ite(c, t, e) => vec!(c, t, e)
+    }
+}
+
+// Clauses making `out` equal to the gate applied to `ins`
+fn gate_clauses(node: &Node, out: Literal, ins: &[Literal], clauses: &mut Vec<Vec<Literal>>) {
+    match *node {
+        Node::Const(val) => clauses.push(vec!(if val { out } else { out.negate() })),
+        Node::And(..) => {
+            clauses.push(vec!(out.negate(), ins[0]));
+            clauses.push(vec!(out.negate(), ins[1]));
+            clauses.push(vec!(out, ins[0].negate(), ins[1].negate()));
+        },
+        Node::Or(..) => {
+            clauses.push(vec!(out, ins[0].negate()));
+            clauses.push(vec!(out, ins[1].negate()));
+            clauses.push(vec!(out.negate(), ins[0], ins[1]));
+        },
+        Node::Xor(..) => {
+            clauses.push(vec!(out.negate(), ins[0], ins[1]));
+            clauses.push(vec!(out.negate(), ins[0].negate(), ins[1].negate()));
+            clauses.push(vec!(out, ins[0].negate(), ins[1]));
+            clauses.push(vec!(out, ins[0], ins[1].negate()));
+        },
+        Node::Ite(..) => {
+            clauses.push(vec!(out.negate(), ins[0].negate(), ins[1]));
+            clauses.push(vec!(out.negate(), ins[0], ins[2]));
+            clauses.push(vec!(out, ins[0].negate(), ins[1].negate()));
+            clauses.push(vec!(out, ins[0], ins[2].negate()));
+        },
+        Node::Var(_) | Node::Not(_) => {}
+    }
+}
+
+// The expression with its operators applied left to right as in `Expr::eval`
+enum Tree {
+    Var(uint),
+    Const(bool),
+    Not(Box<Tree>),
+    Binary(Type, Box<Tree>, Box<Tree>),
+    Ite(Box<Tree>, Box<Tree>, Box<Tree>)
+}
+
+impl Tree {
+    // Variables missing from `vars` are added to it
+    fn from_expr(expr: &Expr, vars: &mut Vec<String>) -> Result<Tree, TruthError> {
+        if expr.components.len() == 0 { return Ok(Tree::Const(false)) }
+        let mut tree = try!(Tree::from_component(&expr.components[0], vars));
+        for idx in range(1, expr.components.len()) {
+            let rhs = try!(Tree::from_component(&expr.components[idx], vars));
+            let op = &expr.ops[idx - 1];
+            match op.token_type {
+                Type::And | Type::Or | Type::Xor | Type::Xnor | Type::Implies | Type::Iff => {},
+                ref other => return Err(TruthError::Eval(
+                    format!("Unexpected operation: {}", other), Some(Span::from_token(op))
+                ))
+            }
+            tree = Tree::Binary(op.token_type.clone(), box tree, box rhs);
+        }
+        Ok(tree)
+    }
+
+    fn from_component(component: &Component, vars: &mut Vec<String>) -> Result<Tree, TruthError> {
+        let tree = match component.value {
+            VarOrExpr::Var(ref name) => match vars.iter().position(|var| var == name) {
+                Some(idx) => Tree::Var(idx),
+                None => {
+                    vars.push(name.clone());
+                    Tree::Var(vars.len() - 1)
+                }
+            },
+            VarOrExpr::Const(val) => Tree::Const(val),
+            VarOrExpr::Expr(ref expr) => try!(Tree::from_expr(expr, vars)),
+            VarOrExpr::Ite(ref cond, ref then, ref other) => Tree::Ite(
+                box try!(Tree::from_expr(&**cond, vars)),
+                box try!(Tree::from_expr(&**then, vars)),
+                box try!(Tree::from_expr(&**other, vars))
+            ),
+            VarOrExpr::Call(ref name, _) => return Err(undefined_function(name.as_slice()))
+        };
+        Ok(if component.negated { Tree::Not(box tree) } else { tree })
+    }
+}
+
+fn negation_normal(expr: &Expr, vars: &mut Vec<String>) -> Result<Formula, TruthError> {
+    Ok(push_negation(&try!(Tree::from_expr(expr, vars)), false))
+}
+
+// `tree` with negations pushed down to the variables, negated when `negate` is set
+fn push_negation(tree: &Tree, negate: bool) -> Formula {
+    match *tree {
+        Tree::Var(idx) => Formula::Lit(Literal { var: idx, negated: negate }),
+        Tree::Const(val) => Formula::Const(val != negate),
+        Tree::Not(ref inner) => push_negation(&**inner, !negate),
+        Tree::Binary(ref op, ref a, ref b) => {
+            let (a, b) = (&**a, &**b);
+            match *op {
+                // De Morgan
+                Type::And if negate => or(vec!(push_negation(a, true), push_negation(b, true))),
+                Type::And => and(vec!(push_negation(a, false), push_negation(b, false))),
+                Type::Or if negate => and(vec!(push_negation(a, true), push_negation(b, true))),
+                Type::Or => or(vec!(push_negation(a, false), push_negation(b, false))),
+                // a -> b is !a | b, and its negation a & !b
+                Type::Implies if negate => and(vec!(push_negation(a, false), push_negation(b, true))),
+                Type::Implies => or(vec!(push_negation(a, true), push_negation(b, false))),
+                // a ^ b is (a | b) & (!a | !b), and its negation (a | !b) & (!a | b)
+                Type::Xor => and(vec!(
+                    or(vec!(push_negation(a, false), push_negation(b, negate))),
+                    or(vec!(push_negation(a, true), push_negation(b, !negate)))
+                )),
+                // Equivalence is a negated XOR
+                _ => and(vec!(
+                    or(vec!(push_negation(a, false), push_negation(b, !negate))),
+                    or(vec!(push_negation(a, true), push_negation(b, negate)))
+                ))
+            }
+        },
+        // c ? t : e is (!c | t) & (c | e)
+        Tree::Ite(ref c, ref t, ref e) => and(vec!(
+            or(vec!(push_negation(&**c, true), push_negation(&**t, negate))),
+            or(vec!(push_negation(&**c, false), push_negation(&**e, negate)))
+        ))
+    }
+}
+
+fn formula_component(formula: &Formula, vars: &[String]) -> Component {
+    let (terms, op) = match *formula {
+        Formula::Const(val) => return Component { value: VarOrExpr::Const(val), negated: false },
+        Formula::Lit(lit) => return Component { value: VarOrExpr::Var(vars[lit.var].clone()), negated: lit.negated },
+        Formula::And(ref terms) => (terms, Type::And),
+        Formula::Or(ref terms) => (terms, Type::Or)
+    };
+    Expr::join(terms.iter().map(|term| formula_component(term, vars)).collect(), op).into_component()
+}
+
+// Flattens nested ANDs and folds constants
+fn and(terms: Vec<Formula>) -> Formula {
+    let mut flat = Vec::new();
+    for term in terms.into_iter() {
+        match term {
+            Formula::Const(true) => {},
+            Formula::Const(false) => return Formula::Const(false),
+            Formula::And(inner) => flat.extend(inner.into_iter()),
+            other => flat.push(other)
+        }
+    }
+    match flat.len() {
+        0 => Formula::Const(true),
+        1 => flat.pop().unwrap(),
+        _ => Formula::And(flat)
+    }
+}
+
+fn or(terms: Vec<Formula>) -> Formula {
+    let mut flat = Vec::new();
+    for term in terms.into_iter() {
+        match term {
+            Formula::Const(false) => {},
+            Formula::Const(true) => return Formula::Const(true),
+            Formula::Or(inner) => flat.extend(inner.into_iter()),
+            other => flat.push(other)
+        }
+    }
+    match flat.len() {
+        0 => Formula::Const(false),
+        1 => flat.pop().unwrap(),
+        _ => Formula::Or(flat)
+    }
+}
+
+// The terms of `formula` as a two-level form, an AND of ORs when `outer_and`
+// is set and an OR of ANDs otherwise
+fn distribute(formula: &Formula, outer_and: bool) -> Vec<Vec<Literal>> {
+    match *formula {
+        Formula::Const(val) => if val == outer_and { vec!() } else { vec!(vec!()) },
+        Formula::Lit(lit) => vec!(vec!(lit)),
+        Formula::And(ref terms) | Formula::Or(ref terms) => {
+            let is_and = match *formula { Formula::And(_) => true, _ => false };
+            if is_and == outer_and {
+                // Same as the outer operator: the terms' lists are joined
+                let mut all = Vec::new();
+                for term in terms.iter() {
+                    all.extend(distribute(term, outer_and).into_iter());
+                }
+                all
+            } else {
+                // Distributes: one inner term is picked from each operand
+                let mut product: Vec<Vec<Literal>> = vec!(vec!());
+                for term in terms.iter() {
+                    let parts = distribute(term, outer_and);
+                    let mut next = Vec::with_capacity(product.len() * parts.len());
+                    for left in product.iter() {
+                        for right in parts.iter() {
+                            let mut joined = left.clone();
+                            joined.push_all(right.as_slice());
+                            next.push(joined);
+                        }
+                    }
+                    product = simplify(next);
+                }
+                product
+            }
+        }
+    }
+}
+
+// Sorts and dedupes the literals of each term, drops terms with both
+// polarities of a variable and terms containing another term
+fn simplify(terms: Vec<Vec<Literal>>) -> Vec<Vec<Literal>> {
+    let mut cleaned: Vec<Vec<Literal>> = Vec::new();
+    for term in terms.into_iter() {
+        let mut term = term;
+        term.sort_by(|a, b| (a.var, a.negated).cmp(&(b.var, b.negated)));
+        term.dedup();
+        if range(1, term.len()).any(|idx| term[idx].var == term[idx - 1].var) { continue }
+        cleaned.push(term);
+    }
+
+    cleaned.sort_by(|a, b| a.len().cmp(&b.len()));
+    let mut kept: Vec<Vec<Literal>> = Vec::new();
+    for term in cleaned.into_iter() {
+        if !kept.iter().any(|small| small.iter().all(|lit| term.contains(lit))) {
+            kept.push(term);
+        }
+    }
+    kept
+}
+
+// Terms of literals joined by `inner`, themselves joined by `outer`
+fn two_level(vars: &[String], terms: &[Vec<Literal>], outer: Type, inner: Type) -> Expr {
+    let outer_is_and = outer == Type::And;
+    if terms.is_empty() {
+        return constant(outer_is_and)
+    }
+    if terms.iter().any(|term| term.is_empty()) {
+        return constant(!outer_is_and)
+    }
+
+    let components: Vec<Component> = terms.iter().map(|term| {
+        let literals: Vec<Component> = term.iter().map(|lit| {
+            Component { value: VarOrExpr::Var(vars[lit.var].clone()), negated: lit.negated }
+        }).collect();
+        Expr::join(literals, inner.clone()).into_component()
+    }).collect();
+
+    if components.len() == 1 {
+        return Expr::from_component(components.into_iter().next().unwrap())
+    }
+    Expr::join(components, outer)
+}
+
+fn constant(val: bool) -> Expr {
+    Expr::from_component(Component { value: VarOrExpr::Const(val), negated: false })
+}
@@ -0,0 +1,193 @@
+//! Functions written as lists of row indices, as in `f(a,b,c) = Σm(1,3,5)`
+//! or `f(a,b,c) = ΠM(0,2,4,6,7)`. Rows are numbered like those of a
+//! `TruthTable`, the first variable being the most significant bit.
+//!
+//! `m(...)` and `M(...)` may be written without the `Σ` and `Π`.
+
+use error::{Span, TruthError};
+use expr::Expr;
+use normal::{Cnf, Dnf};
+use table::TruthTable;
+
+/// Whether the indices are the true rows or the false rows
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+pub enum TermKind {
+    Minterms,
+    Maxterms
+}
+
+/// A function named `name` of `vars`, given by the indices of its rows
+#[deriving(Show)]
+#[deriving(Clone)]
+pub struct TermList {
+    pub name:    String,
+    pub vars:    Vec<String>,
+    pub kind:    TermKind,
+    pub indices: Vec<uint>
+}
+
+impl TermList {
+    /// Whether `src` is written in this notation rather than as an
+    /// expression, which never contains `=`
+    pub fn matches(src: &str) -> bool {
+        src.contains_char('=')
+    }
+
+    pub fn parse(src: &str) -> Result<TermList, TruthError> {
+        let mut cursor = Cursor { chars: src.chars().collect(), pos: 0 };
+
+        let name = try!(cursor.ident());
+        try!(cursor.expect('('));
+        let mut vars = vec!(try!(cursor.ident()));
+        while try!(cursor.separator()) {
+            let (col, var) = (cursor.col(), try!(cursor.ident()));
+            if vars.contains(&var) {
+                return Err(TruthError::Parse(format!("Repeated variable: {}", var), Some(Span::new(1, col, col))))
+            }
+            vars.push(var);
+        }
+        let rows = try!(TruthTable::row_count(vars.len()));
+        try!(cursor.expect('='));
+
+        let kind = match try!(cursor.next()) {
+            'Σ' => { try!(cursor.expect('m')); TermKind::Minterms },
+            'Π' | '∏' => { try!(cursor.expect('M')); TermKind::Maxterms },
+            'm' => TermKind::Minterms,
+            'M' => TermKind::Maxterms,
+            other => { cursor.pos -= 1; return Err(cursor.unexpected(other)) }
+        };
+        try!(cursor.expect('('));
+        let mut indices = Vec::new();
+        if !cursor.skip(')') {
+            loop {
+                let col = cursor.col();
+                let index = try!(cursor.number());
+                if index >= rows {
+                    return Err(TruthError::Parse(
+                        format!("Row {} out of range for {} variables", index, vars.len()), Some(Span::new(1, col, col))
+                    ))
+                }
+                if !indices.contains(&index) { indices.push(index) }
+                if !try!(cursor.separator()) { break }
+            }
+        }
+        try!(cursor.end());
+
+        indices.sort();
+        Ok(TermList { name: name, vars: vars, kind: kind, indices: indices })
+    }
+
+    pub fn truth_table(&self) -> TruthTable {
+        let listed = self.kind == TermKind::Minterms;
+        let results = range(0, 1u << self.vars.len()).map(|row| self.indices.contains(&row) == listed).collect();
+        TruthTable::new(self.vars.clone(), results)
+    }
+
+    /// The canonical sum of products for minterms and product of sums for maxterms
+    pub fn to_expr(&self) -> Expr {
+        let table = self.truth_table();
+        match self.kind {
+            TermKind::Minterms => Dnf::canonical(&table).to_expr(),
+            TermKind::Maxterms => Cnf::canonical(&table).to_expr()
+        }
+    }
+}
+
+// Reads characters skipping whitespace, columns counting from 1 like the lexer
+struct Cursor {
+    chars: Vec<char>,
+    pos:   uint
+}
+
+impl Cursor {
+    fn col(&mut self) -> uint {
+        self.skip_whitespace();
+        self.pos + 1
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() { self.pos += 1 }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.pos).map(|&c| c)
+    }
+
+    fn next(&mut self) -> Result<char, TruthError> {
+        match self.peek() {
+            Some(c) => { self.pos += 1; Ok(c) },
+            None => Err(self.end_of_input())
+        }
+    }
+
+    // Consumes `c` if it is next
+    fn skip(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) { self.pos += 1; true } else { false }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), TruthError> {
+        match try!(self.next()) {
+            next if next == c => Ok(()),
+            other => { self.pos -= 1; Err(self.unexpected(other)) }
+        }
+    }
+
+    // True after a comma and false after the closing parenthesis of a list
+    fn separator(&mut self) -> Result<bool, TruthError> {
+        match try!(self.next()) {
+            ',' => Ok(true),
+            ')' => Ok(false),
+            other => { self.pos -= 1; Err(self.unexpected(other)) }
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, TruthError> {
+        let mut ident = String::new();
+        match try!(self.next()) {
+            c if c.is_alphabetic() || c == '_' => ident.push(c),
+            other => { self.pos -= 1; return Err(self.unexpected(other)) }
+        }
+        while self.pos < self.chars.len() && (self.chars[self.pos].is_alphanumeric() || self.chars[self.pos] == '_') {
+            ident.push(self.chars[self.pos]);
+            self.pos += 1;
+        }
+        Ok(ident)
+    }
+
+    fn number(&mut self) -> Result<uint, TruthError> {
+        let col = self.col();
+        let mut digits = String::new();
+        while self.pos < self.chars.len() && self.chars[self.pos].is_digit(10) {
+            digits.push(self.chars[self.pos]);
+            self.pos += 1;
+        }
+        match from_str::<uint>(digits.as_slice()) {
+            Some(number) => Ok(number),
+            None if digits.is_empty() => {
+                let c = try!(self.next());
+                self.pos -= 1;
+                Err(self.unexpected(c))
+            },
+            None => Err(TruthError::Parse(format!("Invalid row: {}", digits), Some(Span::new(1, col, col))))
+        }
+    }
+
+    fn end(&mut self) -> Result<(), TruthError> {
+        match self.peek() {
+            Some(c) => Err(self.unexpected(c)),
+            None => Ok(())
+        }
+    }
+
+    // An error at the current position for the character `c` found there
+    fn unexpected(&self, c: char) -> TruthError {
+        TruthError::Parse(format!("Unexpected character: {}", c), Some(Span::new(1, self.pos + 1, self.pos + 1)))
+    }
+
+    fn end_of_input(&self) -> TruthError {
+        TruthError::Parse("Unexpected end of input".to_string(), Some(Span::new(1, self.pos + 1, self.pos + 1)))
+    }
+}
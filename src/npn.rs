@@ -0,0 +1,114 @@
+//! NPN classification: functions are equivalent when one becomes the other
+//! by negating some inputs, permuting the inputs and negating the output.
+//!
+//! The canonical representative of a class is the member whose table, read
+//! as a binary number with row `i` as bit `i`, is smallest. It is found by
+//! trying every transform, so it is limited to small functions: 6 variables
+//! already take `6! * 2^6 * 2` transforms of 64 rows each.
+
+use std::fmt;
+
+use error::TruthError;
+use table::TruthTable;
+
+/// The most variables `canonical` accepts, whose tables fit in a u64
+pub const MAX_NPN_VARS: uint = 6;
+
+/// The representative of a function's class and the transform taking it
+/// back to the function. With the representative `g` and the function `f`,
+/// `f(x) = g(y) ^ output` where `y[i]` is `x[perm[i]]`, negated when bit
+/// `i` of `negations` is set, bits being numbered from the first column
+pub struct Npn {
+    pub table:     TruthTable,
+    pub perm:      Vec<uint>,
+    pub negations: uint,
+    pub output:    bool
+}
+
+impl Npn {
+    /// The number of variables and the representative's table in hex, row 0
+    /// being the least significant bit, as in `3:17`. Equivalent functions
+    /// of the same number of variables have equal keys
+    pub fn key(&self) -> String {
+        let digits = (self.table.len() + 3) / 4;
+        format!("{}:{:02$x}", self.table.variables().len(), bits(&self.table), digits)
+    }
+}
+
+impl fmt::Show for Npn {
+    /// The transform as a call of the representative `g`, as in `!g(!b, a)`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let vars = self.table.variables();
+        let args: Vec<String> = range(0, vars.len()).map(|i| {
+            let negated = self.negations >> i & 1 == 1;
+            format!("{}{}", if negated { "!" } else { "" }, vars[self.perm[i]])
+        }).collect();
+        write!(f, "{}g({})", if self.output { "!" } else { "" }, args.connect(", "))
+    }
+}
+
+/// The canonical representative of the class of `table`, over the same
+/// variables. Fails with `Overflow` past `MAX_NPN_VARS` variables
+pub fn canonical(table: &TruthTable) -> Result<Npn, TruthError> {
+    let vars = table.variables().len();
+    if vars > MAX_NPN_VARS { return Err(TruthError::Overflow(vars)) }
+    let rows = table.len();
+    let full = if rows == 64 { !0u64 } else { (1u64 << rows) - 1 };
+
+    let mut best: Option<(u64, Vec<uint>, uint, bool)> = None;
+    for perm in permutations(vars).into_iter() {
+        for negations in range(0, 1u << vars) {
+            let mut result = 0u64;
+            for row in range(0, rows) {
+                // The row of the function giving row `row` of the transform
+                let mut source = 0;
+                for i in range(0, vars) {
+                    let value = (row >> (vars - 1 - i) & 1) ^ (negations >> i & 1);
+                    source |= value << (vars - 1 - perm[i]);
+                }
+                if table.row(source).result() { result |= 1 << row }
+            }
+            for &output in [false, true].iter() {
+                let candidate = if output { !result & full } else { result };
+                let better = match best {
+                    Some((ref bits, _, _, _)) => candidate < *bits,
+                    None => true
+                };
+                if better { best = Some((candidate, perm.clone(), negations, output)) }
+            }
+        }
+    }
+
+    let (result, perm, negations, output) = best.unwrap();
+    let results = range(0, rows).map(|row| result >> row & 1 == 1).collect();
+    Ok(Npn {
+        table: TruthTable::new(table.variables().to_vec(), results),
+        perm: perm,
+        negations: negations,
+        output: output
+    })
+}
+
+/// Whether the two tables are in the same class
+pub fn equivalent(a: &TruthTable, b: &TruthTable) -> Result<bool, TruthError> {
+    Ok(try!(canonical(a)).key() == try!(canonical(b)).key())
+}
+
+// The table as a number, row `i` being bit `i`
+fn bits(table: &TruthTable) -> u64 {
+    range(0, table.len()).fold(0, |bits, row| bits | (table.row(row).result() as u64) << row)
+}
+
+// Every ordering of `0..count`, in lexicographic order
+fn permutations(count: uint) -> Vec<Vec<uint>> {
+    if count == 0 { return vec!(vec!()) }
+    let mut perms = Vec::new();
+    for first in range(0, count) {
+        for rest in permutations(count - 1).into_iter() {
+            let mut perm = vec!(first);
+            perm.extend(rest.into_iter().map(|idx| if idx >= first { idx + 1 } else { idx }));
+            perms.push(perm);
+        }
+    }
+    perms
+}
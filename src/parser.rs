@@ -0,0 +1,321 @@
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use std::cmp;
+
+use error::{Span, TruthError};
+use expr::{Component, Expr, VarOrExpr};
+use lexer::{Lexer, Token, Type};
+use symbol::SymbolTable;
+
+/// How deeply groups and operators may nest before parsing fails, well
+/// short of overflowing the stack in the recursive parser and evaluators
+pub const DEFAULT_MAX_DEPTH: uint = 1000;
+
+pub struct Parser {
+    tokens: Vec<Token>,
+    // The names of the identifiers in `tokens`
+    symbols: SymbolTable,
+    pos: uint,
+    implicit_and: bool,
+    // Whether errors are collected into `errors` and parsing goes on
+    recover: bool,
+    errors: Vec<TruthError>,
+    // Calls of `parse_prec` and `?:` branches in progress, which every group,
+    // operator and `?:` nests
+    depth: uint,
+    max_depth: uint
+}
+
+impl Parser {
+    pub fn new(lexer: &mut Lexer) -> Result<Parser, TruthError> {
+        let mut tokens = vec!();
+        let mut token;
+        loop {
+            token = try!(lexer.next_token());
+            match token.token_type {
+                Type::EOF => {
+                    tokens.push(token);
+                    break
+                },
+                _   => {
+                    tokens.push(token);
+                }
+            }
+        }
+        Ok(Parser { tokens: tokens, symbols: lexer.take_symbols(), pos: 0, implicit_and: lexer.implicit_and, recover: false, errors: vec!(),
+                    depth: 0, max_depth: DEFAULT_MAX_DEPTH })
+    }
+
+    /// Lexes and parses all of the input, going on past each error so that
+    /// every lex and parse error is reported in one pass
+    pub fn parse_recovering(lexer: &mut Lexer) -> Result<Expr, Vec<TruthError>> {
+        let mut errors = vec!();
+        let mut tokens = vec!();
+        loop {
+            // The lexer has already stepped over a character it couldn't read
+            match lexer.next_token() {
+                Ok(token) => {
+                    let eof = token.token_type == Type::EOF;
+                    tokens.push(token);
+                    if eof { break }
+                },
+                Err(err) => errors.push(err)
+            }
+        }
+
+        let mut parser = Parser { tokens: tokens, symbols: lexer.take_symbols(), pos: 0, implicit_and: lexer.implicit_and, recover: true, errors: vec!(),
+                                  depth: 0, max_depth: DEFAULT_MAX_DEPTH };
+        let expr = parser.parse();
+        errors.extend(parser.errors.into_iter());
+        match expr {
+            Ok(expr) if errors.is_empty() => Ok(expr),
+            Ok(_) => Err(errors),
+            Err(err) => {
+                errors.push(err);
+                Err(errors)
+            }
+        }
+    }
+    
+    /// Fails on input nested more deeply than `depth`, `DEFAULT_MAX_DEPTH`
+    /// unless set
+    pub fn set_max_depth(&mut self, depth: uint) {
+        self.max_depth = depth;
+    }
+
+    // Reading past the end keeps returning the EOF token
+    fn next(&mut self) -> Token {
+        let tok = self.peek().clone();
+        self.pos += 1;
+        tok
+    }
+
+    // The next token without reading it
+    fn peek(&self) -> &Token {
+        &self.tokens[cmp::min(self.pos, self.tokens.len() - 1)]
+    }
+
+    fn skip(&mut self) { self.pos += 1; }
+
+    fn back(&mut self) { self.pos -= 1; }
+
+    // Fails on `token`, or when recovering records the error and goes on
+    fn unexpected(&mut self, token: &Token) -> Result<(), TruthError> {
+        // An identifier is shown with its name rather than its symbol
+        let found = match token.token_type {
+            Type::Ident(symbol) => format!("Ident({})", self.symbols.name(symbol)),
+            ref token_type => token_type.to_string()
+        };
+        let err = TruthError::Parse(format!("Unexpected token: {}", found), Some(Span::from_token(token)));
+        if !self.recover { return Err(err) }
+        // A callee which stepped back over the token has already reported it
+        if self.errors.last().and_then(|last| last.span()) != err.span() {
+            self.errors.push(err);
+        }
+        Ok(())
+    }
+
+    // Synchronizes after an error inside parentheses by skipping to the `)`
+    // which closes them, stopping before the end of input
+    fn skip_to_close(&mut self) {
+        let mut depth = 0u;
+        loop {
+            match self.next().token_type {
+                Type::LParen => depth += 1,
+                Type::RParen if depth == 0 => return,
+                Type::RParen => depth -= 1,
+                Type::EOF => {
+                    self.back();
+                    return
+                },
+                _ => {}
+            }
+        }
+    }
+    
+    /// Parses the whole input, failing on anything left after the expression
+    pub fn parse(&mut self) -> Result<Expr, TruthError> {
+        let expr = try!(self.expr());
+        loop {
+            let token = self.next();
+            match token.token_type {
+                Type::EOF => return Ok(expr),
+                _ => try!(self.unexpected(&token))
+            }
+            // Recovering, whatever follows the stray token is parsed for its errors
+            if self.peek().token_type.starts_component() { try!(self.expr()); }
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, TruthError> {
+        let cond = try!(self.parse_prec(0));
+
+        if self.peek().token_type != Type::Question { return Ok(cond) }
+        self.skip();
+
+        // The branches nest, so a long chain of `?:` counts towards the limit
+        try!(self.enter());
+        let ite = self.ite(cond);
+        self.depth -= 1;
+        ite
+    }
+
+    // The branches of a `?:` after the `?`
+    fn ite(&mut self, cond: Expr) -> Result<Expr, TruthError> {
+        let then = try!(self.expr());
+        let colon = self.next();
+        match colon.token_type {
+            Type::Colon => {},
+            _ => {
+                // Recovering, the colon is taken as missing
+                try!(self.unexpected(&colon));
+                self.back();
+            }
+        }
+        // Right associative, `a ? b : c ? d : e` nests in the else branch
+        let other = try!(self.expr());
+
+        let ite = VarOrExpr::Ite(box cond, box then, box other);
+        Ok(Expr { components: vec!(Component { value: ite, negated: false }), ops: vec!() })
+    }
+
+    // Counts one more level of nesting, failing past the limit. Too deep is
+    // fatal even when recovering, going on would recurse further
+    fn enter(&mut self) -> Result<(), TruthError> {
+        if self.depth >= self.max_depth {
+            let token = self.next();
+            return Err(TruthError::Parse(
+                format!("Nested too deeply, more than {} levels", self.max_depth), Some(Span::from_token(&token))
+            ))
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    // Counts the nesting around `parse_prec_inner`, failing past the limit
+    fn parse_prec(&mut self, min: uint) -> Result<Expr, TruthError> {
+        try!(self.enter());
+        let expr = self.parse_prec_inner(min);
+        self.depth -= 1;
+        expr
+    }
+
+    // Precedence climbing: operators binding at least as tightly as `min`
+    // are collected into `op`, tighter ones are parsed recursively
+    fn parse_prec_inner(&mut self, min: uint) -> Result<Expr, TruthError> {
+        let mut op = Expr { components: vec!(), ops: vec!() };
+
+        op.components.push(try!(self.component()));
+
+        loop {
+            // Juxtaposed components are joined by an AND at the same position,
+            // which isn't a token to read
+            let implicit = self.implicit_and && self.peek().token_type.starts_component();
+            let token = if implicit {
+                let next = self.peek();
+                Token { token_type: Type::And, col: next.col, line: next.line, bytes: (next.bytes.val0(), next.bytes.val0()) }
+            } else {
+                self.next()
+            };
+            let prec = match token.token_type.precedence() {
+                Some(prec) if prec >= min => prec,
+                _ => {
+                    if !implicit { self.back() }
+                    break;
+                }
+            };
+
+            // A looser operator takes everything collected so far as its left side
+            if op.ops.len() > 0 && op.ops[0].token_type.precedence() != Some(prec) {
+                op = Expr { components: vec!(op.into_component()), ops: vec!() };
+            }
+
+            let rhs_min = if token.token_type.is_right_assoc() { prec } else { prec + 1 };
+            let rhs = try!(self.parse_prec(rhs_min));
+            op.ops.push(token);
+            op.components.push(rhs.into_component());
+        }
+
+        Ok(op)
+    }
+
+    fn component(&mut self) -> Result<Component, TruthError> {
+        let mut token = self.next();
+        let mut neg = false;
+        let mut val: VarOrExpr;
+        
+        loop {
+            match token.token_type {
+                Type::Not => neg = !neg,
+                Type::LParen => {
+                    val = VarOrExpr::Expr(try!(self.expr()));
+                    let next = self.next();
+                    match next.token_type {
+                        Type::RParen  => {},
+                        _ => {
+                            try!(self.unexpected(&next));
+                            self.back();
+                            self.skip_to_close();
+                        }
+                    };
+                    break;
+                },
+                // A call, as in `maj(a, b, c)`, unless juxtaposition means AND
+                Type::Ident(symbol) => {
+                    let name = self.symbols.name(symbol).to_string();
+                    if !self.implicit_and && self.peek().token_type == Type::LParen {
+                        self.skip();
+                        val = VarOrExpr::Call(name, try!(self.args()));
+                    } else {
+                        val = VarOrExpr::Var(name);
+                    }
+                    break;
+                },
+                Type::Const(b) => {
+                    val = VarOrExpr::Const(b);
+                    break;
+                },
+                _ => {
+                    // Recovering, a missing operand becomes `0`. Tokens which
+                    // could follow one are left for the caller
+                    try!(self.unexpected(&token));
+                    if token.token_type.precedence().is_some() || token.token_type == Type::RParen
+                        || token.token_type == Type::Comma || token.token_type == Type::Colon
+                        || token.token_type == Type::Question || token.token_type == Type::EOF {
+                        self.back();
+                    }
+                    val = VarOrExpr::Const(false);
+                    break;
+                }
+            }
+            token = self.next();
+        }
+
+        // Postfix negation, as in A'
+        while self.peek().token_type == Type::Prime {
+            neg = !neg;
+            self.skip();
+        }
+
+        Ok(Component { value: val, negated: neg })
+    }
+
+    // The arguments of a call, following its opening parenthesis
+    fn args(&mut self) -> Result<Vec<Expr>, TruthError> {
+        let mut args = vec!(try!(self.expr()));
+        loop {
+            let token = self.next();
+            match token.token_type {
+                Type::Comma => args.push(try!(self.expr())),
+                Type::RParen => return Ok(args),
+                _ => {
+                    try!(self.unexpected(&token));
+                    self.back();
+                    self.skip_to_close();
+                    return Ok(args)
+                }
+            }
+        }
+    }
+    
+}
@@ -0,0 +1,63 @@
+//! The five maximal classes of Post's lattice which a function may belong
+//! to. A set of functions is functionally complete, able to express every
+//! function, exactly when none of these classes holds all of them.
+
+use spectral;
+use table::TruthTable;
+
+/// Which of Post's classes a function belongs to
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+pub struct PostClasses {
+    /// Changing an input from 0 to 1 never changes the result from 1 to 0
+    pub monotone:        bool,
+    /// An XOR of some variables, or its complement
+    pub linear:          bool,
+    /// Negating every input negates the result
+    pub self_dual:       bool,
+    /// The result is 0 when every input is 0
+    pub preserves_false: bool,
+    /// The result is 1 when every input is 1
+    pub preserves_true:  bool
+}
+
+/// The classes the function of `table` belongs to
+pub fn classify(table: &TruthTable) -> PostClasses {
+    let results: Vec<bool> = table.iter_rows().map(|row| row.result()).collect();
+    let rows = results.len();
+    let vars = table.variables().len();
+
+    let monotone = range(0, rows).all(|row| {
+        !results[row] || range(0, vars).all(|var| results[row | 1 << var])
+    });
+    // Complementary rows are symmetric about the middle of the table. A
+    // constant of no variables can't be its own dual
+    let self_dual = rows > 1 && range(0, rows / 2).all(|row| results[row] != results[rows - 1 - row]);
+
+    PostClasses {
+        monotone: monotone,
+        linear: spectral::nonlinearity(table) == 0,
+        self_dual: self_dual,
+        preserves_false: !results[0],
+        preserves_true: results[rows - 1]
+    }
+}
+
+/// The names of the classes holding every function of `set`, which is
+/// functionally complete when there are none
+pub fn closed_classes(set: &[PostClasses]) -> Vec<&'static str> {
+    let mut names = Vec::new();
+    if set.iter().all(|classes| classes.monotone) { names.push("monotone") }
+    if set.iter().all(|classes| classes.linear) { names.push("linear") }
+    if set.iter().all(|classes| classes.self_dual) { names.push("self-dual") }
+    if set.iter().all(|classes| classes.preserves_false) { names.push("0-preserving") }
+    if set.iter().all(|classes| classes.preserves_true) { names.push("1-preserving") }
+    names
+}
+
+/// Whether the functions of `set` together can express every function, by
+/// Post's criterion
+pub fn is_complete(set: &[PostClasses]) -> bool {
+    closed_classes(set).is_empty()
+}
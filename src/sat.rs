@@ -0,0 +1,238 @@
+//! Satisfiability by DPLL search over clauses.
+//!
+//! Expressions are turned into clauses with the Tseitin encoding of their
+//! `Aig`, one solver variable per node, so the clauses grow linearly with
+//! the expression instead of with the number of rows.
+
+use std::mem;
+
+use aig::{Aig, AigNode};
+use env::Assignment;
+
+/// A variable index shifted left by one with the low bit set when negated,
+/// the same encoding as `AigLit`
+pub type SatLit = uint;
+
+/// Whether an expression is always, never or sometimes true
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+pub enum Classification {
+    Tautology,
+    Contradiction,
+    /// An assignment making the expression true and one making it false
+    Contingent(Assignment, Assignment)
+}
+
+/// An assignment for which two expressions differ
+#[deriving(Show)]
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+pub struct Counterexample {
+    pub assignment: Assignment,
+    /// The value of the first expression, the second has the opposite value
+    pub left: bool
+}
+
+pub struct Solver {
+    clauses:  Vec<Vec<SatLit>>,
+    // Clauses watching each literal, indexed by the literal
+    watches:  Vec<Vec<uint>>,
+    assigns:  Vec<Option<bool>>,
+    trail:    Vec<SatLit>,
+    // Trail length at the start of each decision level
+    levels:   Vec<uint>,
+    // The decision of each level and whether it is already the flipped branch
+    decided:  Vec<(SatLit, bool)>,
+    queue:    uint,
+    // Set by an empty clause or conflicting units
+    conflict: bool
+}
+
+impl Solver {
+    pub fn new(vars: uint) -> Solver {
+        Solver {
+            clauses: Vec::new(),
+            watches: Vec::from_fn(vars * 2, |_| Vec::new()),
+            assigns: Vec::from_elem(vars, None),
+            trail: Vec::new(),
+            levels: Vec::new(),
+            decided: Vec::new(),
+            queue: 0,
+            conflict: false
+        }
+    }
+
+    /// The Tseitin encoding of `lit` in `aig` being true, solver variable
+    /// `i` standing for node `i`
+    pub fn from_aig(aig: &Aig, lit: SatLit) -> Solver {
+        let mut solver = Solver::new(aig.nodes().len());
+        // Node 0 is the constant false
+        solver.add_clause(&[1]);
+        for (idx, node) in aig.nodes().iter().enumerate() {
+            match *node {
+                AigNode::And(a, b) => {
+                    let out = idx << 1;
+                    solver.add_clause(&[out ^ 1, a]);
+                    solver.add_clause(&[out ^ 1, b]);
+                    solver.add_clause(&[out, a ^ 1, b ^ 1]);
+                },
+                _ => {}
+            }
+        }
+        solver.add_clause(&[lit]);
+        solver
+    }
+
+    /// Solves the encoding of `lit`, giving the values of the inputs of `aig`
+    pub fn model(aig: &Aig, lit: SatLit) -> Option<Assignment> {
+        match Solver::from_aig(aig, lit).solve() {
+            Some(model) => {
+                let values = range(0, aig.variables().len()).map(|idx| model[aig.input(idx) >> 1]).collect();
+                Some(Assignment::new(aig.variables().to_vec(), values))
+            },
+            None => None
+        }
+    }
+
+    pub fn vars(&self) -> uint {
+        self.assigns.len()
+    }
+
+    /// Clauses may only be added before `solve`
+    pub fn add_clause(&mut self, lits: &[SatLit]) {
+        let mut clause: Vec<SatLit> = Vec::new();
+        for &lit in lits.iter() {
+            // A clause with both polarities of a variable is always true
+            if clause.contains(&(lit ^ 1)) { return }
+            if !clause.contains(&lit) { clause.push(lit) }
+        }
+
+        match clause.len() {
+            0 => self.conflict = true,
+            1 => {
+                match self.value(clause[0]) {
+                    Some(false) => self.conflict = true,
+                    Some(true) => {},
+                    None => self.assign(clause[0])
+                }
+            },
+            _ => {
+                let idx = self.clauses.len();
+                self.watches[clause[0]].push(idx);
+                self.watches[clause[1]].push(idx);
+                self.clauses.push(clause);
+            }
+        }
+    }
+
+    /// A value for each variable satisfying every clause, None if there is none
+    pub fn solve(&mut self) -> Option<Vec<bool>> {
+        if self.conflict { return None }
+
+        loop {
+            if !self.propagate() {
+                if !self.backtrack() { return None }
+                continue
+            }
+
+            match self.assigns.iter().position(|val| val.is_none()) {
+                Some(var) => {
+                    self.levels.push(self.trail.len());
+                    self.decided.push((var << 1 | 1, false));
+                    self.assign(var << 1 | 1);
+                },
+                None => return Some(self.assigns.iter().map(|val| val.unwrap()).collect())
+            }
+        }
+    }
+
+    fn value(&self, lit: SatLit) -> Option<bool> {
+        lit_value(self.assigns.as_slice(), lit)
+    }
+
+    fn assign(&mut self, lit: SatLit) {
+        self.assigns[lit >> 1] = Some(lit & 1 == 0);
+        self.trail.push(lit);
+    }
+
+    // Undoes decisions up to the latest one whose other branch is untried and
+    // takes that branch, false when every branch has been tried
+    fn backtrack(&mut self) -> bool {
+        loop {
+            let (lit, flipped) = match self.decided.pop() {
+                Some(decision) => decision,
+                None => return false
+            };
+            let start = self.levels.pop().unwrap();
+            for &undo in self.trail.slice_from(start).iter() {
+                self.assigns[undo >> 1] = None;
+            }
+            self.trail.truncate(start);
+            self.queue = start;
+
+            if !flipped {
+                self.levels.push(start);
+                self.decided.push((lit ^ 1, true));
+                self.assign(lit ^ 1);
+                return true
+            }
+        }
+    }
+
+    // Assigns every literal implied by a unit clause, false on a conflict
+    fn propagate(&mut self) -> bool {
+        while self.queue < self.trail.len() {
+            let falsified = self.trail[self.queue] ^ 1;
+            self.queue += 1;
+
+            let mut watching = mem::replace(&mut self.watches[falsified], Vec::new());
+            let mut idx = 0u;
+            let mut ok = true;
+            while idx < watching.len() {
+                let ci = watching[idx];
+                let (first, moved) = {
+                    let assigns = self.assigns.as_slice();
+                    let clause = &mut self.clauses[ci];
+                    if clause[0] == falsified { clause.swap(0, 1) }
+                    if lit_value(assigns, clause[0]) == Some(true) {
+                        (clause[0], None)
+                    } else {
+                        // Watch another literal which isn't false
+                        let found = range(2, clause.len()).find(|&k| lit_value(assigns, clause[k]) != Some(false));
+                        match found {
+                            Some(k) => {
+                                clause.swap(1, k);
+                                (clause[0], Some(clause[1]))
+                            },
+                            None => (clause[0], None)
+                        }
+                    }
+                };
+
+                match moved {
+                    Some(lit) => {
+                        self.watches[lit].push(ci);
+                        watching.swap_remove(idx);
+                        continue
+                    },
+                    None => {}
+                }
+                match self.value(first) {
+                    Some(true) => {},
+                    Some(false) => { ok = false; break },
+                    None => self.assign(first)
+                }
+                idx += 1;
+            }
+
+            self.watches[falsified] = watching;
+            if !ok { return false }
+        }
+        true
+    }
+}
+
+fn lit_value(assigns: &[Option<bool>], lit: SatLit) -> Option<bool> {
+    assigns[lit >> 1].map(|val| val != (lit & 1 == 1))
+}
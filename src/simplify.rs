@@ -0,0 +1,434 @@
+//! Simplification by the laws of boolean algebra.
+//!
+//! Rewrites are applied one at a time, innermost first, until no law
+//! applies. Each one is recorded as a `Step` so the derivation can be shown.
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use std::fmt;
+
+use expr::{Component, Expr, VarOrExpr};
+use lexer::Type;
+
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+pub enum Law {
+    /// `a & 1 = a`, `a | 0 = a`, `a ^ 0 = a`
+    Identity,
+    /// `a & 0 = 0`, `a | 1 = 1`
+    Domination,
+    /// `a & a = a`, `a | a = a`
+    Idempotence,
+    /// `a & (a | b) = a`, `a | (a & b) = a`
+    Absorption,
+    /// `!(a & b) = !a | !b`, `!(a | b) = !a & !b`
+    DeMorgan,
+    /// `a & !a = 0`, `a | !a = 1`, `!0 = 1`, `!1 = 0`
+    Complement,
+    /// `!!a = a`
+    DoubleNegation,
+    /// The other operators with a constant operand: `a ^ 1 = !a`,
+    /// `a <-> 1 = a`, `a <-> 0 = !a`, `1 -> a = a`, `0 -> a = 1`,
+    /// `a -> 1 = 1`, `a -> 0 = !a`, `1 ? a : b = a`, `0 ? a : b = b`
+    Constant
+}
+
+impl fmt::Show for Law {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Law::Identity => "identity",
+            Law::Domination => "domination",
+            Law::Idempotence => "idempotence",
+            Law::Absorption => "absorption",
+            Law::DeMorgan => "De Morgan",
+            Law::Complement => "complement",
+            Law::DoubleNegation => "double negation",
+            Law::Constant => "constant operand"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A single rewrite and the whole expression after it
+#[deriving(Show)]
+#[deriving(Clone)]
+pub struct Step {
+    pub law:  Law,
+    pub expr: Expr
+}
+
+/// Every rewrite from `expr` to its simplest form
+pub fn simplify_steps(expr: &Expr) -> Vec<Step> {
+    let mut term = Term::from_expr(expr);
+    let mut steps = Vec::new();
+    loop {
+        match term.rewrite() {
+            Some((law, next)) => {
+                steps.push(Step { law: law, expr: next.to_expr() });
+                term = next;
+            },
+            None => return steps
+        }
+    }
+}
+
+pub fn simplify(expr: &Expr) -> Expr {
+    match simplify_steps(expr).pop() {
+        Some(step) => step.expr,
+        None => expr.clone()
+    }
+}
+
+// ANDs and ORs are n-ary so the laws can look at all of their operands
+#[deriving(Clone)]
+#[deriving(PartialEq)]
+enum Term {
+    Var(String),
+    Const(bool),
+    Not(Box<Term>),
+    And(Vec<Term>),
+    Or(Vec<Term>),
+    /// XOR, XNOR, implication and equivalence
+    Binary(Type, Box<Term>, Box<Term>),
+    Ite(Box<Term>, Box<Term>, Box<Term>),
+    /// A call left unexpanded, whose arguments are simplified in place
+    Call(String, Vec<Term>)
+}
+
+impl Term {
+    fn from_expr(expr: &Expr) -> Term {
+        if expr.components.len() == 0 { return Term::Const(false) }
+        let mut term = Term::from_component(&expr.components[0]);
+        for idx in range(1, expr.components.len()) {
+            let rhs = Term::from_component(&expr.components[idx]);
+            term = match expr.ops[idx - 1].token_type {
+                Type::And => and(vec!(term, rhs)),
+                Type::Or => or(vec!(term, rhs)),
+                ref op => Term::Binary(op.clone(), box term, box rhs)
+            };
+        }
+        term
+    }
+
+    fn from_component(component: &Component) -> Term {
+        let term = match component.value {
+            VarOrExpr::Var(ref name) => Term::Var(name.clone()),
+            VarOrExpr::Const(val) => Term::Const(val),
+            VarOrExpr::Expr(ref expr) => Term::from_expr(expr),
+            VarOrExpr::Ite(ref cond, ref then, ref other) => Term::Ite(
+                box Term::from_expr(&**cond), box Term::from_expr(&**then), box Term::from_expr(&**other)
+            ),
+            VarOrExpr::Call(ref name, ref args) => Term::Call(name.clone(), args.iter().map(|arg| Term::from_expr(arg)).collect())
+        };
+        if component.negated { Term::Not(box term) } else { term }
+    }
+
+    fn to_expr(&self) -> Expr {
+        Expr::from_component(self.to_component())
+    }
+
+    fn to_component(&self) -> Component {
+        let value = match *self {
+            Term::Var(ref name) => VarOrExpr::Var(name.clone()),
+            Term::Const(val) => VarOrExpr::Const(val),
+            Term::Not(ref inner) => {
+                let component = inner.to_component();
+                if component.negated {
+                    // Kept nested so a double negation stays visible
+                    VarOrExpr::Expr(Expr::from_component(component))
+                } else {
+                    return Component { value: component.value, negated: true }
+                }
+            },
+            Term::And(ref terms) => return join(terms.as_slice(), Type::And),
+            Term::Or(ref terms) => return join(terms.as_slice(), Type::Or),
+            Term::Binary(ref op, ref a, ref b) => return join(&[(**a).clone(), (**b).clone()], op.clone()),
+            Term::Ite(ref cond, ref then, ref other) => {
+                VarOrExpr::Ite(box cond.to_expr(), box then.to_expr(), box other.to_expr())
+            },
+            Term::Call(ref name, ref args) => VarOrExpr::Call(name.clone(), args.iter().map(|arg| arg.to_expr()).collect())
+        };
+        Component { value: value, negated: match *self { Term::Not(_) => true, _ => false } }
+    }
+
+    // The first rewrite found, trying the operands before the term itself
+    fn rewrite(&self) -> Option<(Law, Term)> {
+        match *self {
+            Term::Var(_) | Term::Const(_) => None,
+            Term::Not(ref inner) => {
+                match inner.rewrite() {
+                    Some((law, inner)) => return Some((law, Term::Not(box inner))),
+                    None => {}
+                }
+                match **inner {
+                    Term::Not(ref term) => Some((Law::DoubleNegation, (**term).clone())),
+                    Term::Const(val) => Some((Law::Complement, Term::Const(!val))),
+                    Term::And(ref terms) => Some((Law::DeMorgan, or(negate_all(terms.as_slice())))),
+                    Term::Or(ref terms) => Some((Law::DeMorgan, and(negate_all(terms.as_slice())))),
+                    _ => None
+                }
+            },
+            Term::And(ref terms) => {
+                match rewrite_operand(terms.as_slice()) {
+                    Some((law, terms)) => Some((law, and(terms))),
+                    None => rewrite_nary(terms.as_slice(), true)
+                }
+            },
+            Term::Or(ref terms) => {
+                match rewrite_operand(terms.as_slice()) {
+                    Some((law, terms)) => Some((law, or(terms))),
+                    None => rewrite_nary(terms.as_slice(), false)
+                }
+            },
+            Term::Binary(ref op, ref a, ref b) => {
+                match rewrite_operand(&[(**a).clone(), (**b).clone()]) {
+                    Some((law, mut terms)) => {
+                        let b = terms.pop().unwrap();
+                        let a = terms.pop().unwrap();
+                        return Some((law, Term::Binary(op.clone(), box a, box b)))
+                    },
+                    None => {}
+                }
+                // XOR with false is the other operand
+                match (op, &**a, &**b) {
+                    (&Type::Xor, &Term::Const(false), other) |
+                    (&Type::Xor, other, &Term::Const(false)) => return Some((Law::Identity, other.clone())),
+                    _ => {}
+                }
+                fold_binary(op, &**a, &**b).map(|term| (Law::Constant, term))
+            },
+            Term::Ite(ref cond, ref then, ref other) => {
+                match rewrite_operand(&[(**cond).clone(), (**then).clone(), (**other).clone()]) {
+                    Some((law, mut terms)) => {
+                        let other = terms.pop().unwrap();
+                        let then = terms.pop().unwrap();
+                        let cond = terms.pop().unwrap();
+                        Some((law, Term::Ite(box cond, box then, box other)))
+                    },
+                    None => match **cond {
+                        Term::Const(val) => Some((Law::Constant, if val { (**then).clone() } else { (**other).clone() })),
+                        _ => None
+                    }
+                }
+            },
+            Term::Call(ref name, ref args) => {
+                rewrite_operand(args.as_slice()).map(|(law, args)| (law, Term::Call(name.clone(), args)))
+            }
+        }
+    }
+}
+
+// Rewrites the first operand which can be, returning all of the operands
+fn rewrite_operand(terms: &[Term]) -> Option<(Law, Vec<Term>)> {
+    for (idx, term) in terms.iter().enumerate() {
+        match term.rewrite() {
+            Some((law, rewritten)) => {
+                let mut terms = terms.to_vec();
+                terms[idx] = rewritten;
+                return Some((law, terms))
+            },
+            None => {}
+        }
+    }
+    None
+}
+
+// The laws for the operands of an AND when `is_and` is set, or of an OR
+fn rewrite_nary(terms: &[Term], is_and: bool) -> Option<(Law, Term)> {
+    let build = |terms: Vec<Term>| if is_and { and(terms) } else { or(terms) };
+
+    // a & 0 = 0, a | 1 = 1
+    if terms.iter().any(|term| *term == Term::Const(!is_and)) {
+        return Some((Law::Domination, Term::Const(!is_and)))
+    }
+    // a & 1 = a, a | 0 = a
+    if terms.iter().any(|term| *term == Term::Const(is_and)) {
+        let rest = terms.iter().filter(|term| **term != Term::Const(is_and)).map(|term| term.clone()).collect();
+        return Some((Law::Identity, build(rest)))
+    }
+    // a & !a = 0, a | !a = 1
+    for term in terms.iter() {
+        match *term {
+            Term::Not(ref inner) if terms.contains(&**inner) => {
+                return Some((Law::Complement, Term::Const(!is_and)))
+            },
+            _ => {}
+        }
+    }
+    // a & a = a
+    for idx in range(1, terms.len()) {
+        if terms.slice_to(idx).contains(&terms[idx]) {
+            let mut rest = terms.to_vec();
+            rest.remove(idx);
+            return Some((Law::Idempotence, build(rest)))
+        }
+    }
+    // a & (a | b) = a, a | (a & b) = a
+    for (idx, term) in terms.iter().enumerate() {
+        let inner = match (is_and, term) {
+            (true, &Term::Or(ref inner)) | (false, &Term::And(ref inner)) => inner,
+            _ => continue
+        };
+        let absorbed = terms.iter().enumerate().any(|(other, term)| other != idx && inner.contains(term));
+        if absorbed {
+            let mut rest = terms.to_vec();
+            rest.remove(idx);
+            return Some((Law::Absorption, build(rest)))
+        }
+    }
+    None
+}
+
+// XOR, XNOR, equivalence and implication with a constant operand, as the
+// other operand, its negation or a constant
+fn fold_binary(op: &Type, a: &Term, b: &Term) -> Option<Term> {
+    let not = |term: &Term| Term::Not(box term.clone());
+    match (op, a, b) {
+        (&Type::Xor, &Term::Const(true), other) | (&Type::Xor, other, &Term::Const(true)) => Some(not(other)),
+        (&Type::Xnor, &Term::Const(val), other) | (&Type::Xnor, other, &Term::Const(val)) |
+        (&Type::Iff, &Term::Const(val), other) | (&Type::Iff, other, &Term::Const(val)) => {
+            Some(if val { other.clone() } else { not(other) })
+        },
+        (&Type::Implies, &Term::Const(true), other) => Some(other.clone()),
+        (&Type::Implies, &Term::Const(false), _) | (&Type::Implies, _, &Term::Const(true)) => Some(Term::Const(true)),
+        (&Type::Implies, other, &Term::Const(false)) => Some(not(other)),
+        _ => None
+    }
+}
+
+fn negate_all(terms: &[Term]) -> Vec<Term> {
+    terms.iter().map(|term| Term::Not(box term.clone())).collect()
+}
+
+// Flattens nested ANDs and unwraps single operands
+fn and(terms: Vec<Term>) -> Term {
+    let mut flat = Vec::new();
+    for term in terms.into_iter() {
+        match term {
+            Term::And(inner) => flat.extend(inner.into_iter()),
+            other => flat.push(other)
+        }
+    }
+    match flat.len() {
+        0 => Term::Const(true),
+        1 => flat.pop().unwrap(),
+        _ => Term::And(flat)
+    }
+}
+
+fn or(terms: Vec<Term>) -> Term {
+    let mut flat = Vec::new();
+    for term in terms.into_iter() {
+        match term {
+            Term::Or(inner) => flat.extend(inner.into_iter()),
+            other => flat.push(other)
+        }
+    }
+    match flat.len() {
+        0 => Term::Const(false),
+        1 => flat.pop().unwrap(),
+        _ => Term::Or(flat)
+    }
+}
+
+fn join(terms: &[Term], op: Type) -> Component {
+    Expr::join(terms.iter().map(|term| term.to_component()).collect(), op).into_component()
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use std::rand::{SeedableRng, StdRng};
+
+    use arbitrary::Generator;
+    use expr::Expr;
+    use super::{simplify, simplify_steps, Law};
+
+    fn results(expr: &Expr, vars: &[String]) -> Vec<bool> {
+        expr.truth_table_ordered(vars.to_vec()).unwrap().iter_rows().map(|row| row.result()).collect()
+    }
+
+    // The laws applied to `expr`, after checking that every step keeps its
+    // truth table
+    fn laws(expr: &Expr) -> Vec<Law> {
+        let vars = expr.get_variables();
+        let expected = results(expr, vars.as_slice());
+        let steps = simplify_steps(expr);
+        for step in steps.iter() {
+            assert!(results(&step.expr, vars.as_slice()) == expected,
+                    "{} took {} to {}, which isn't equivalent", step.law, expr, step.expr);
+        }
+        steps.into_iter().map(|step| step.law).collect()
+    }
+
+    fn check(src: &str, law: Law, simplest: &str) {
+        let expr = Expr::parse(src).unwrap();
+        assert!(laws(&expr).contains(&law), "{} isn't applied to {}", law, src);
+        assert_eq!(simplify(&expr).to_string(), simplest.to_string());
+    }
+
+    #[test]
+    fn identity() {
+        check("a & 1", Law::Identity, "a");
+        check("0 | a", Law::Identity, "a");
+        check("a ^ 0", Law::Identity, "a");
+    }
+
+    #[test]
+    fn domination() {
+        check("a & 0", Law::Domination, "0");
+        check("a | b | 1", Law::Domination, "1");
+    }
+
+    #[test]
+    fn idempotence() {
+        check("a & a", Law::Idempotence, "a");
+        check("a | b | a", Law::Idempotence, "a | b");
+    }
+
+    #[test]
+    fn absorption() {
+        check("a & (a | b)", Law::Absorption, "a");
+        check("a | (a & b)", Law::Absorption, "a");
+    }
+
+    #[test]
+    fn de_morgan() {
+        check("!(a & b)", Law::DeMorgan, "!a | !b");
+        check("!(a | b)", Law::DeMorgan, "!a & !b");
+    }
+
+    #[test]
+    fn complement() {
+        check("a & !a", Law::Complement, "0");
+        check("a | !a", Law::Complement, "1");
+        check("!(0)", Law::Complement, "1");
+    }
+
+    #[test]
+    fn double_negation() {
+        check("!(!a)", Law::DoubleNegation, "a");
+    }
+
+    #[test]
+    fn constant_operand() {
+        check("a ^ 1", Law::Constant, "!a");
+        check("a <-> 1", Law::Constant, "a");
+        check("a !^ 0", Law::Constant, "!a");
+        check("1 -> a", Law::Constant, "a");
+        check("0 -> a", Law::Constant, "1");
+        check("a -> 1", Law::Constant, "1");
+        check("a -> 0", Law::Constant, "!a");
+        check("(1 ? a : b)", Law::Constant, "a");
+        check("(0 ? a : b)", Law::Constant, "b");
+    }
+
+    #[test]
+    fn every_step_is_equivalent() {
+        let mut rng: StdRng = SeedableRng::from_seed([7u, 3, 5].as_slice());
+        let vars = vec!("a".to_string(), "b".to_string(), "c".to_string());
+        let generator = Generator::new(vars, 4);
+        for _ in range(0, 300u) {
+            laws(&generator.generate(&mut rng));
+        }
+    }
+}
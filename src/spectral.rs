@@ -0,0 +1,105 @@
+//! The Walsh spectrum of a truth table and the measures of nonlinearity
+//! built on it, for judging functions such as the component functions of
+//! an S-box, along with avalanche and correlation immunity.
+//!
+//! Entry `a` of the spectrum is `Σ (-1)^(f(x) ^ a·x)` over every row `x`,
+//! where `a·x` is the parity of the bits the two have in common: the number
+//! of rows on which `f` agrees with the linear function `a·x` less the
+//! number on which it differs. Bits are numbered like row indices, so entry
+//! `a` is for the XOR of the variables which are 1 in row `a`.
+
+use std::num::Int;
+
+use expr::{Component, Expr, VarOrExpr};
+use lexer::Type;
+use table::TruthTable;
+
+/// The spectrum of `table`, found with the fast Walsh-Hadamard transform
+pub fn walsh(table: &TruthTable) -> Vec<i64> {
+    let mut spectrum: Vec<i64> = table.iter_rows().map(|row| if row.result() { -1 } else { 1 }).collect();
+    let mut width = 1;
+    while width < spectrum.len() {
+        for start in range(0, spectrum.len()).filter(|&idx| idx & width == 0) {
+            let (low, high) = (spectrum[start], spectrum[start + width]);
+            spectrum[start] = low + high;
+            spectrum[start + width] = low - high;
+        }
+        width <<= 1;
+    }
+    spectrum
+}
+
+/// The number of rows which must change to make the table affine, that is
+/// the distance to the nearest linear function or its complement
+pub fn nonlinearity(table: &TruthTable) -> uint {
+    let spectrum = walsh(table);
+    let peak = spectrum.iter().map(|value| value.abs()).max().unwrap() as uint;
+    (table.len() - peak) / 2
+}
+
+/// An affine function as close to the table as any other, the XOR of some
+/// variables or its complement
+pub fn nearest_affine(table: &TruthTable) -> Expr {
+    let spectrum = walsh(table);
+    // The first of the largest, so ties go to the fewest variables in row order
+    let mut best = 0;
+    for (idx, value) in spectrum.iter().enumerate() {
+        if value.abs() > spectrum[best].abs() { best = idx }
+    }
+
+    // A negative entry means the table agrees more with the complement
+    let complement = spectrum[best] < 0;
+    let vars = table.variables();
+    let literals: Vec<Component> = range(0, vars.len())
+        .filter(|&pos| best >> (vars.len() - 1 - pos) & 1 == 1)
+        .map(|pos| Component { value: VarOrExpr::Var(vars[pos].clone()), negated: false })
+        .collect();
+    if literals.is_empty() {
+        return Expr::from_component(Component { value: VarOrExpr::Const(complement), negated: false })
+    }
+    let mut component = Expr::join(literals, Type::Xor).into_component();
+    component.negated = complement;
+    Expr::from_component(component)
+}
+
+/// Whether the table is bent: its spectrum is flat, every entry being
+/// `±2^(n/2)`, which needs an even number `n` of variables. Bent functions
+/// have the highest nonlinearity there is, `2^(n-1) - 2^(n/2-1)`
+pub fn is_bent(table: &TruthTable) -> bool {
+    let vars = table.variables().len();
+    if vars == 0 || vars % 2 == 1 { return false }
+    let flat = 1i64 << (vars / 2);
+    walsh(table).iter().all(|value| value.abs() == flat)
+}
+
+/// For each variable in column order, the number of rows whose result
+/// changes when just that variable is flipped
+pub fn avalanche(table: &TruthTable) -> Vec<uint> {
+    let vars = table.variables().len();
+    range(0, vars).map(|pos| {
+        let bit = 1 << (vars - 1 - pos);
+        range(0, table.len()).filter(|&idx| table.row(idx).result() != table.row(idx ^ bit).result()).count()
+    }).collect()
+}
+
+/// Whether flipping any one variable changes the result on exactly half
+/// the rows, the strict avalanche criterion
+pub fn satisfies_sac(table: &TruthTable) -> bool {
+    avalanche(table).iter().all(|&changed| 2 * changed == table.len())
+}
+
+/// The largest `m` such that the result is uncorrelated with every XOR of
+/// 1 to `m` variables, so fixing any `m` variables leaves the fraction of
+/// true rows the same. The number of variables for a constant function
+pub fn correlation_immunity(table: &TruthTable) -> uint {
+    let spectrum = walsh(table);
+    let vars = table.variables().len();
+    range(1, vars + 1).find(|&order| {
+        range(0, spectrum.len()).any(|idx| idx.count_ones() == order && spectrum[idx] != 0)
+    }).map_or(vars, |order| order - 1)
+}
+
+/// Whether the table is true on exactly half its rows
+pub fn is_balanced(table: &TruthTable) -> bool {
+    2 * table.minterms().len() == table.len()
+}
@@ -0,0 +1,180 @@
+//! Logic circuit schematics as SVG.
+//!
+//! Inputs are listed down the left edge in the order they appear, one per
+//! occurrence, and each gate is drawn one column right of its deepest
+//! operand, level with the middle of its operands. AND, OR, XOR, XNOR and
+//! NOT use the usual distinctive shapes; implication, equivalence and
+//! if-then-else are labelled boxes.
+
+use expr::{Component, Expr, VarOrExpr};
+use lexer::Type;
+
+const COLUMN: uint = 90;
+const ROW: uint = 40;
+const GATE: uint = 40;
+const MARGIN: uint = 20;
+
+/// The expression as a standalone SVG image
+pub fn expr_to_svg(expr: &Expr) -> String {
+    let mut circuit = Circuit { body: String::new(), rows: 0, cols: 0 };
+    let (col, y) = circuit.expr(expr);
+    // The output wire and label
+    let x = x_of(col) + GATE;
+    circuit.line(x, y, x + 20, y);
+    circuit.text(x + 24, y, "start", "out");
+
+    let width = x_of(circuit.cols) + GATE + 60;
+    let height = circuit.rows * ROW + 2 * MARGIN;
+    format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n\
+             <g fill=\"none\" stroke=\"black\" stroke-width=\"2\" font-family=\"sans-serif\" font-size=\"14\">\n\
+             {body}</g>\n</svg>\n", w = width, h = height, body = circuit.body)
+}
+
+// The left edge of a column, inputs being column 0
+fn x_of(col: uint) -> uint {
+    MARGIN + col * COLUMN
+}
+
+struct Circuit {
+    body: String,
+    rows: uint,
+    cols: uint
+}
+
+impl Circuit {
+    // Draws the expression and returns the column and height of its output
+    fn expr(&mut self, expr: &Expr) -> (uint, uint) {
+        if expr.components.len() == 0 { return self.input("0") }
+        if expr.ops.len() == 0 { return self.component(&expr.components[0]) }
+
+        let op = expr.ops[0].token_type.clone();
+        if expr.ops.iter().all(|tok| tok.token_type == op) {
+            let inputs: Vec<(uint, uint)> = expr.components.iter().map(|component| self.component(component)).collect();
+            return self.gate(&op, inputs.as_slice())
+        }
+
+        // Mixed operators of one precedence nest to the left
+        let mut left = self.component(&expr.components[0]);
+        for idx in range(1, expr.components.len()) {
+            let right = self.component(&expr.components[idx]);
+            left = self.gate(&expr.ops[idx - 1].token_type, &[left, right]);
+        }
+        left
+    }
+
+    fn component(&mut self, component: &Component) -> (uint, uint) {
+        let output = match component.value {
+            VarOrExpr::Var(ref name) => self.input(name.as_slice()),
+            VarOrExpr::Const(val) => self.input(if val { "1" } else { "0" }),
+            VarOrExpr::Expr(ref expr) => self.expr(expr),
+            VarOrExpr::Ite(ref cond, ref then, ref other) => {
+                let inputs = [self.expr(&**cond), self.expr(&**then), self.expr(&**other)];
+                self.boxed("MUX", &inputs)
+            },
+            VarOrExpr::Call(ref name, ref args) => {
+                let inputs: Vec<(uint, uint)> = args.iter().map(|arg| self.expr(arg)).collect();
+                self.boxed(name.as_slice(), inputs.as_slice())
+            }
+        };
+        if component.negated { self.not(output) } else { output }
+    }
+
+    fn input(&mut self, label: &str) -> (uint, uint) {
+        let y = MARGIN + self.rows * ROW + ROW / 2;
+        self.rows += 1;
+        self.text(MARGIN + GATE - 6, y, "end", label);
+        self.line(MARGIN + GATE - 2, y, MARGIN + GATE, y);
+        (0, y)
+    }
+
+    fn gate(&mut self, op: &Type, inputs: &[(uint, uint)]) -> (uint, uint) {
+        let (col, y, h) = self.place(inputs);
+        let x = x_of(col);
+        let (top, bottom) = (y - h / 2, y + h / 2);
+        match *op {
+            Type::And => {
+                self.path(format!("M{} {} H{} A20 {} 0 0 1 {} {} H{} Z", x, top, x + 20, h / 2, x + 20, bottom, x));
+            },
+            Type::Or => {
+                self.path(format!("M{} {} Q{} {} {} {} Q{} {} {} {} Q{} {} {} {} Z",
+                                  x, top, x + 25, top, x + GATE, y, x + 25, bottom, x, bottom, x + 10, y, x, top));
+            },
+            Type::Xor | Type::Xnor => {
+                let end = if *op == Type::Xnor { x + GATE - 8 } else { x + GATE };
+                self.path(format!("M{} {} Q{} {} {} {} Q{} {} {} {} Q{} {} {} {} Z",
+                                  x + 6, top, x + 26, top, end, y, x + 26, bottom, x + 6, bottom, x + 16, y, x + 6, top));
+                self.path(format!("M{} {} Q{} {} {} {}", x, top, x + 10, y, x, bottom));
+                if *op == Type::Xnor { self.bubble(end, y) }
+            },
+            Type::Implies => return self.boxed_at("→", inputs, col, y, h),
+            Type::Iff => return self.boxed_at("↔", inputs, col, y, h),
+            _ => return self.boxed_at("?", inputs, col, y, h)
+        }
+        self.wires(inputs, x, y, h);
+        (col, y)
+    }
+
+    fn not(&mut self, input: (uint, uint)) -> (uint, uint) {
+        let (col, y, _) = self.place(&[input]);
+        let x = x_of(col);
+        self.path(format!("M{} {} L{} {} L{} {} Z", x, y - 12, x + GATE - 8, y, x, y + 12));
+        self.bubble(x + GATE - 8, y);
+        self.wires(&[input], x, y, 24);
+        (col, y)
+    }
+
+    fn boxed(&mut self, label: &str, inputs: &[(uint, uint)]) -> (uint, uint) {
+        let (col, y, h) = self.place(inputs);
+        self.boxed_at(label, inputs, col, y, h)
+    }
+
+    fn boxed_at(&mut self, label: &str, inputs: &[(uint, uint)], col: uint, y: uint, h: uint) -> (uint, uint) {
+        let x = x_of(col);
+        self.body.push_str(format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n", x, y - h / 2, GATE, h).as_slice());
+        self.text(x + GATE / 2, y, "middle", label);
+        self.wires(inputs, x, y, h);
+        (col, y)
+    }
+
+    // The column, middle and height of a gate taking `inputs`
+    fn place(&mut self, inputs: &[(uint, uint)]) -> (uint, uint, uint) {
+        let col = inputs.iter().map(|&(col, _)| col).max().unwrap() + 1;
+        if col > self.cols { self.cols = col }
+        let y = inputs.iter().fold(0, |sum, &(_, y)| sum + y) / inputs.len();
+        let h = if inputs.len() > 2 { inputs.len() * 12 } else { 30 };
+        (col, y, h)
+    }
+
+    // Connects each input to a pin on the left edge of the gate at `x`,
+    // turning down or up in the gap before it, staggered so the wires of
+    // different pins don't overlap
+    fn wires(&mut self, inputs: &[(uint, uint)], x: uint, y: uint, h: uint) {
+        for (pin, &(col, from)) in inputs.iter().enumerate() {
+            let to = y - h / 2 + h * (pin + 1) / (inputs.len() + 1);
+            let start = x_of(col) + GATE;
+            let turn = x - (COLUMN - GATE) + (COLUMN - GATE) * (pin + 1) / (inputs.len() + 1);
+            self.path(format!("M{} {} H{} V{} H{}", start, from, turn, to, x + 4));
+        }
+    }
+
+    fn path(&mut self, d: String) {
+        self.body.push_str(format!("<path d=\"{}\"/>\n", d).as_slice());
+    }
+
+    fn line(&mut self, x1: uint, y1: uint, x2: uint, y2: uint) {
+        self.body.push_str(format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n", x1, y1, x2, y2).as_slice());
+    }
+
+    fn bubble(&mut self, x: uint, y: uint) {
+        self.body.push_str(format!("<circle cx=\"{}\" cy=\"{}\" r=\"4\"/>\n", x + 4, y).as_slice());
+    }
+
+    fn text(&mut self, x: uint, y: uint, anchor: &str, label: &str) {
+        self.body.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"{}\" dominant-baseline=\"middle\" \
+                                    fill=\"black\" stroke=\"none\">{}</text>\n", x, y, anchor, escape(label)).as_slice());
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace("&", "&amp;").replace("<", "&lt;").replace(">", "&gt;")
+}
@@ -0,0 +1,66 @@
+//! Interned variable names.
+//!
+//! Evaluation refers to each variable by a `Symbol`, its index in a
+//! `SymbolTable`, so rows are computed without copying or comparing names.
+//! The names are only looked up again for display.
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use std::collections::BTreeMap;
+
+/// Index of a name in its `SymbolTable`
+pub type Symbol = uint;
+
+#[deriving(Clone)]
+pub struct SymbolTable {
+    names:   Vec<String>,
+    symbols: BTreeMap<String, Symbol>
+}
+
+impl SymbolTable {
+    pub fn new() -> SymbolTable {
+        SymbolTable { names: Vec::new(), symbols: BTreeMap::new() }
+    }
+
+    /// A table giving each of `names` its position as its symbol
+    pub fn from_names(names: &[String]) -> SymbolTable {
+        let mut table = SymbolTable::new();
+        for name in names.iter() {
+            table.intern(name.as_slice());
+        }
+        table
+    }
+
+    /// The symbol for `name`, adding it when it is new
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        match self.symbols.get(name) {
+            Some(&symbol) => return symbol,
+            None => {}
+        }
+        let symbol = self.names.len();
+        self.names.push(name.to_string());
+        self.symbols.insert(name.to_string(), symbol);
+        symbol
+    }
+
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.symbols.get(name).map(|&symbol| symbol)
+    }
+
+    pub fn name(&self, symbol: Symbol) -> &str {
+        self.names[symbol].as_slice()
+    }
+
+    /// Every name, in order of their symbols
+    pub fn names(&self) -> &[String] {
+        self.names.as_slice()
+    }
+
+    pub fn into_names(self) -> Vec<String> {
+        self.names
+    }
+
+    pub fn len(&self) -> uint {
+        self.names.len()
+    }
+}
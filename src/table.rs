@@ -0,0 +1,500 @@
+use std::fmt;
+use std::uint;
+
+use serialize::json;
+
+use error::{Span, TruthError};
+
+/// The most variables `from_csv` and `from_pla` accept, as the file can
+/// claim more rows than there is memory for. Also the default limit on
+/// whole tables printed by `truth`
+pub const MAX_TABLE_VARS: uint = 24;
+
+/// The result of an expression for every assignment of its variables.
+///
+/// Row `i` assigns each variable the matching bit of `i`, the first
+/// variable being the most significant bit. Rows may be marked as don't
+/// cares, which the minimizer is free to make true or false; their result
+/// is false everywhere else.
+///
+/// A table made `lsb_first` over the variables in reverse shows them in
+/// their original order, the first column being the least significant bit.
+#[deriving(Encodable)]
+#[deriving(Decodable)]
+pub struct TruthTable {
+    vars:       Vec<String>,
+    results:    Vec<bool>,
+    // Sorted indices of the don't care rows
+    dont_cares: Vec<uint>,
+    // Whether the columns are shown last variable first, see `lsb_first`
+    reversed:   bool
+}
+
+impl TruthTable {
+    pub fn new(vars: Vec<String>, results: Vec<bool>) -> TruthTable {
+        TruthTable { vars: vars, results: results, dont_cares: Vec::new(), reversed: false }
+    }
+
+    /// The same table with `rows` also don't cares, which are made false.
+    /// Fails when a row is out of range
+    pub fn with_dont_cares(mut self, rows: &[uint]) -> Result<TruthTable, TruthError> {
+        for &row in rows.iter() {
+            if row >= self.len() {
+                return Err(TruthError::Eval(
+                    format!("Row {} out of range for {} variables", row, self.vars.len()), None
+                ))
+            }
+            self.results[row] = false;
+            if !self.dont_cares.contains(&row) { self.dont_cares.push(row) }
+        }
+        self.dont_cares.sort();
+        Ok(self)
+    }
+
+    /// The same table shown with its columns in reverse. Built over the
+    /// variables in reverse, the columns are shown in order with the first
+    /// the least significant bit of the row numbers, as some textbooks
+    /// number rows. Only what is printed changes: `variables`, `Row::value`
+    /// and the row numbers, so the minterms, stay as they were
+    pub fn lsb_first(mut self) -> TruthTable {
+        self.reversed = true;
+        self
+    }
+
+    /// The number of rows of a table over `vars` variables, checked so it
+    /// can't overflow
+    pub fn row_count(vars: uint) -> Result<uint, TruthError> {
+        if vars >= uint::BITS { Err(TruthError::Overflow(vars)) } else { Ok(1 << vars) }
+    }
+
+    /// Reads comma separated rows of 0s and 1s below a header of variable
+    /// names, the last column being the result, which may also be `d` for a
+    /// don't care. Rows may be in any order but each must appear once
+    pub fn from_csv(src: &str) -> Result<TruthTable, TruthError> {
+        let mut lines = src.lines().enumerate().map(|(idx, line)| (idx + 1, line.trim()))
+            .filter(|&(_, line)| !line.is_empty());
+        let header: Vec<String> = match lines.next() {
+            Some((_, line)) => line.split(',').map(|name| name.trim().to_string()).collect(),
+            None => return Err(TruthError::Parse("Missing header".to_string(), None))
+        };
+        if header.len() < 2 {
+            return Err(TruthError::Parse("Expected variables and a result column".to_string(), None))
+        }
+        let vars = header.slice_to(header.len() - 1).to_vec();
+        if vars.len() > MAX_TABLE_VARS { return Err(TruthError::Overflow(vars.len())) }
+        let mut results: Vec<Option<bool>> = Vec::from_elem(try!(TruthTable::row_count(vars.len())), None);
+        let mut dont_cares = Vec::new();
+        for (line_num, line) in lines {
+            let mut index = 0;
+            let mut col = 1;
+            let cells: Vec<&str> = line.split(',').collect();
+            if cells.len() != header.len() {
+                return Err(TruthError::Parse(
+                    format!("Expected {} columns, found {}", header.len(), cells.len()), Some(Span::new(line_num, 1, line.char_len()))
+                ))
+            }
+            let mut values = Vec::new();
+            let mut dont_care = false;
+            for (pos, cell) in cells.iter().enumerate() {
+                let value = match cell.trim() {
+                    "0" => false,
+                    "1" => true,
+                    "d" if pos == vars.len() => {
+                        dont_care = true;
+                        false
+                    },
+                    other => return Err(TruthError::Parse(
+                        format!("Expected 0 or 1, found {}", other), Some(Span::new(line_num, col, col + cell.char_len() - 1))
+                    ))
+                };
+                values.push(value);
+                col += cell.char_len() + 1;
+            }
+            for &value in values.slice_to(vars.len()).iter() {
+                index = index << 1 | value as uint;
+            }
+            if results[index].is_some() {
+                return Err(TruthError::Parse(format!("Repeated row {}", index), Some(Span::new(line_num, 1, line.char_len()))))
+            }
+            results[index] = Some(values[vars.len()]);
+            if dont_care { dont_cares.push(index) }
+        }
+
+        match results.iter().position(|result| result.is_none()) {
+            Some(index) => Err(TruthError::Parse(format!("Missing row {}", index), None)),
+            None => TruthTable::new(vars, results.into_iter().map(|result| result.unwrap()).collect())
+                .with_dont_cares(dont_cares.as_slice())
+        }
+    }
+
+    /// The variables and every row as JSON, in the form
+    /// `{"variables": [...], "rows": [{"inputs": {...}, "output": true}, ...]}`
+    pub fn to_json(&self) -> String {
+        let names: Vec<String> = self.columns().iter().map(|var| json::encode(var)).collect();
+        let mut string = format!("{{\"variables\": [{}], \"rows\": [\n", names.connect(", "));
+        for row in self.iter_rows() {
+            let inputs: Vec<String> = names.iter().zip(self.shown(row.inputs()).iter())
+                .map(|(name, value)| format!("{}: {}", name, value)).collect();
+            let sep = if row.index() + 1 < self.len() { "," } else { "" };
+            string.push_str(format!("  {{\"inputs\": {{{}}}, \"output\": {}}}{}\n",
+                                    inputs.connect(", "), row.result(), sep).as_slice());
+        }
+        string.push_str("]}\n");
+        string
+    }
+
+    /// Rows of 0s and 1s separated by `sep`, such as `,` for CSV or `\t` for
+    /// TSV, below a header of the variable names and `Result` if `header`
+    /// is set. Names containing `sep`, quotes or line breaks are quoted
+    pub fn to_delimited(&self, sep: char, header: bool) -> String {
+        let mut string = String::new();
+        if header {
+            let names: Vec<String> = self.columns().iter().map(|var| quote(var.as_slice(), sep)).collect();
+            string.push_str(names.connect(sep.to_string().as_slice()).as_slice());
+            string.push(sep);
+            string.push_str("Result\n");
+        }
+        for row in self.iter_rows() {
+            for value in self.shown(row.inputs()).iter() {
+                string.push(if *value { '1' } else { '0' });
+                string.push(sep);
+            }
+            string.push(if row.result() { '1' } else { '0' });
+            string.push('\n');
+        }
+        string
+    }
+
+    /// A `tabular` environment with `booktabs` rules, headed by the variables
+    /// in math mode and `result`, which is written as given
+    pub fn to_latex(&self, result: &str) -> String {
+        let mut string = format!("\\begin{{tabular}}{{{}c}}\n\\toprule\n",
+                                 String::from_char(self.vars.len(), 'c'));
+        for var in self.columns().iter() {
+            string.push_str(format!("${}$ & ", var).as_slice());
+        }
+        string.push_str(format!("{} \\\\\n\\midrule\n", result).as_slice());
+        for row in self.iter_rows() {
+            for value in self.shown(row.inputs()).iter() {
+                string.push_str(format!("{} & ", *value as u8).as_slice());
+            }
+            string.push_str(format!("{} \\\\\n", row.result() as u8).as_slice());
+        }
+        string.push_str("\\bottomrule\n\\end{tabular}\n");
+        string
+    }
+
+    /// A standalone HTML page holding the table, with `caption` above it when
+    /// given, such as the expression the table is for
+    pub fn to_html(&self, caption: Option<&str>) -> String {
+        let mut string = String::from_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+            <title>Truth table</title>\n<style>\n\
+            table { border-collapse: collapse; font-family: monospace; }\n\
+            caption { padding: 0.5em; font-weight: bold; }\n\
+            th, td { border: 1px solid #999; padding: 0.25em 0.75em; text-align: center; }\n\
+            th { background: #eee; }\n\
+            td.true { background: #dfd; }\n\
+            td.false { background: #fdd; }\n\
+            </style>\n</head>\n<body>\n<table>\n");
+        match caption {
+            Some(caption) => string.push_str(format!("<caption>{}</caption>\n", escape_html(caption)).as_slice()),
+            None => {}
+        }
+        string.push_str("<tr>");
+        for var in self.columns().iter() {
+            string.push_str(format!("<th>{}</th>", escape_html(var.as_slice())).as_slice());
+        }
+        string.push_str("<th>Result</th></tr>\n");
+        for row in self.iter_rows() {
+            string.push_str("<tr>");
+            for value in self.shown(row.inputs()).iter() {
+                string.push_str(format!("<td>{}</td>", *value as u8).as_slice());
+            }
+            string.push_str(format!("<td class=\"{}\">{}</td></tr>\n", row.result(), row.result() as u8).as_slice());
+        }
+        string.push_str("</table>\n</body>\n</html>\n");
+        string
+    }
+
+    /// The true rows in the `.pla` format of the Espresso minimizer, with a
+    /// single output called `f`, followed by the don't cares with an output
+    /// of `-`
+    pub fn to_pla(&self) -> String {
+        let minterms = self.minterms();
+        let mut pla = format!(".i {}\n.o 1\n.ilb {}\n.ob f\n.p {}\n",
+                              self.vars.len(), self.vars.connect(" "), minterms.len() + self.dont_cares.len());
+        let rows = minterms.iter().map(|&index| (index, '1')).chain(self.dont_cares.iter().map(|&index| (index, '-')));
+        for (index, output) in rows {
+            let row = self.row(index);
+            let inputs: String = row.inputs().iter().map(|&value| if value { '1' } else { '0' }).collect();
+            pla.push_str(format!("{} {}\n", inputs, output).as_slice());
+        }
+        pla.push_str(".e\n");
+        pla
+    }
+
+    /// Reads a single output `.pla` file. Each cube may use `-` for inputs it
+    /// doesn't depend on, and rows no cube with an output of 1 covers are false.
+    /// Rows of cubes with an output of `-` are don't cares
+    pub fn from_pla(src: &str) -> Result<TruthTable, TruthError> {
+        let mut inputs: Option<uint> = None;
+        let mut vars: Option<Vec<String>> = None;
+        let mut results = Vec::new();
+        let mut dont_cares = Vec::new();
+
+        for (idx, line) in src.lines().enumerate() {
+            let line_num = idx + 1;
+            let words: Vec<&str> = line.words().collect();
+            if words.is_empty() || words[0].starts_with("#") { continue }
+            let invalid = |msg: &str| TruthError::Parse(msg.to_string(), Some(Span::new(line_num, 1, line.char_len())));
+
+            match words[0] {
+                ".i" => {
+                    let count = match words.get(1).and_then(|count| from_str::<uint>(*count)) {
+                        Some(count) => count,
+                        None => return Err(invalid("Invalid input count"))
+                    };
+                    if count > MAX_TABLE_VARS { return Err(TruthError::Overflow(count)) }
+                    inputs = Some(count);
+                    results = Vec::from_elem(try!(TruthTable::row_count(count)), false);
+                },
+                ".o" => if words.get(1).map(|count| *count) != Some("1") {
+                    return Err(invalid("Only a single output is supported"))
+                },
+                ".ilb" => vars = Some(words.slice_from(1).iter().map(|name| name.to_string()).collect()),
+                ".e" | ".end" => break,
+                word if word.starts_with(".") => {},
+                _ => {
+                    let count = match inputs {
+                        Some(count) => count,
+                        None => return Err(invalid("Cube before the .i line"))
+                    };
+                    if words.len() != 2 || words[0].char_len() != count {
+                        return Err(invalid("Expected a cube of inputs and an output"))
+                    }
+                    if words[1] != "1" && words[1] != "-" { continue }
+                    // Every row matching the cube, expanding each `-` both ways
+                    let mut rows = vec!(0u);
+                    for c in words[0].chars() {
+                        rows = match c {
+                            '0' => rows.iter().map(|&row| row << 1).collect(),
+                            '1' => rows.iter().map(|&row| row << 1 | 1).collect(),
+                            '-' => rows.iter().flat_map(|&row| vec!(row << 1, row << 1 | 1).into_iter()).collect(),
+                            _ => return Err(invalid("Expected 0, 1 or - in a cube"))
+                        };
+                    }
+                    if words[1] == "-" {
+                        dont_cares.push_all(rows.as_slice());
+                    } else {
+                        for &row in rows.iter() { results[row] = true }
+                    }
+                }
+            }
+        }
+
+        let count = match inputs {
+            Some(count) => count,
+            None => return Err(TruthError::Parse("Missing .i line".to_string(), None))
+        };
+        let vars = match vars {
+            Some(vars) => {
+                if vars.len() != count {
+                    return Err(TruthError::Parse("Expected a label for each input".to_string(), None))
+                }
+                vars
+            },
+            None => range(0, count).map(|pos| format!("x{}", pos + 1)).collect()
+        };
+        // A row given as both true and a don't care is a don't care
+        TruthTable::new(vars, results).with_dont_cares(dont_cares.as_slice())
+    }
+
+    /// Variable names in column order
+    pub fn variables(&self) -> &[String] {
+        self.vars.as_slice()
+    }
+
+    /// Variable names in the order they are printed, which is reversed for
+    /// an `lsb_first` table
+    pub fn columns(&self) -> Vec<String> {
+        self.shown(self.vars.clone())
+    }
+
+    // Values in column order put in the order they are printed
+    fn shown<T>(&self, mut values: Vec<T>) -> Vec<T> {
+        if self.reversed { values.reverse() }
+        values
+    }
+
+    pub fn len(&self) -> uint {
+        self.results.len()
+    }
+
+    pub fn row(&self, index: uint) -> Row {
+        assert!(index < self.len(), "row {} out of range", index);
+        Row { table: self, index: index }
+    }
+
+    pub fn iter_rows(&self) -> Rows {
+        Rows { table: self, index: 0 }
+    }
+
+    /// Indices of the rows where the result is true
+    pub fn minterms(&self) -> Vec<uint> {
+        range(0, self.len()).filter(|&index| self.results[index]).collect()
+    }
+
+    /// Indices of the rows where the result is false, leaving out the don't cares
+    pub fn maxterms(&self) -> Vec<uint> {
+        range(0, self.len()).filter(|&index| !self.results[index] && !self.is_dont_care(index)).collect()
+    }
+
+    /// Indices of the don't care rows, in order
+    pub fn dont_cares(&self) -> &[uint] {
+        self.dont_cares.as_slice()
+    }
+
+    pub fn is_dont_care(&self, index: uint) -> bool {
+        self.dont_cares.contains(&index)
+    }
+
+    /// The sum of minterms, as in `Σm(1,3,5,7)`, followed by any don't
+    /// cares as in `Σm(1,3) + d(5,7)`
+    pub fn format_minterms(&self) -> String {
+        format!("Σm({}){}", join_indices(self.minterms().as_slice()), self.format_dont_cares(" + "))
+    }
+
+    /// The product of maxterms, as in `ΠM(0,2,4,6)`, followed by any don't
+    /// cares as in `ΠM(0,2) · d(5,7)`
+    pub fn format_maxterms(&self) -> String {
+        format!("ΠM({}){}", join_indices(self.maxterms().as_slice()), self.format_dont_cares(" · "))
+    }
+
+    // The don't cares after `sep`, or nothing when there are none
+    fn format_dont_cares(&self, sep: &str) -> String {
+        if self.dont_cares.is_empty() { return String::new() }
+        format!("{}d({})", sep, join_indices(self.dont_cares.as_slice()))
+    }
+
+    /// Column names followed by `Result`, each padded by four spaces
+    pub fn format_header(&self) -> String {
+        TruthTable::format_heading(self.columns().as_slice())
+    }
+
+    /// `format_header` for a table over `vars`, see `format_line`
+    pub fn format_heading(vars: &[String]) -> String {
+        let mut string = String::new();
+        for var in vars.iter() {
+            string.push_str(format!("{}    ", var).as_slice());
+        }
+        string.push_str("Result");
+        string
+    }
+
+    /// A row of 0s and 1s aligned below `format_header`
+    pub fn format_row(&self, index: uint) -> String {
+        TruthTable::format_line(self.columns().as_slice(), index, self.row(index).result(), false, self.reversed)
+    }
+
+    /// `format_row` with ANSI colors for terminals: 1s green and 0s red, and
+    /// the result in bold
+    pub fn format_row_color(&self, index: uint) -> String {
+        TruthTable::format_line(self.columns().as_slice(), index, self.row(index).result(), true, self.reversed)
+    }
+
+    /// The row at `index` of a table over `vars` giving `result`, for rows
+    /// not held in a table such as those of `Expr::stream`. The first of
+    /// `vars` is the least significant bit of `index` if `lsb_first` is set
+    pub fn format_line(vars: &[String], index: uint, result: bool, color: bool, lsb_first: bool) -> String {
+        let cell = |value: bool, is_result: bool| {
+            if !color { return format!("{}", value as u8) }
+            let code = if value { "32" } else { "31" };
+            let weight = if is_result { "1;" } else { "" };
+            format!("\x1b[{}{}m{}\x1b[0m", weight, code, value as u8)
+        };
+        let mut string = String::new();
+        for (pos, var) in vars.iter().enumerate() {
+            let bit = if lsb_first { pos } else { vars.len() - 1 - pos };
+            let value = (index >> bit) & 1 == 1;
+            string.push_str(format!("{}{}    ", cell(value, false),
+                                    String::from_char(var.char_len() - 1, ' ')).as_slice());
+        }
+        string.push_str(cell(result, true).as_slice());
+        string
+    }
+}
+
+impl fmt::Show for TruthTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{}\n\n", self.format_header()));
+        for index in range(0, self.len()) {
+            try!(write!(f, "{}\n", self.format_row(index)));
+        }
+        Ok(())
+    }
+}
+
+// A field quoted like RFC 4180 when it holds a separator, quote or line break
+fn quote(field: &str, sep: char) -> String {
+    if field.chars().any(|c| c == sep || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace("\"", "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace("&", "&amp;").replace("<", "&lt;").replace(">", "&gt;")
+}
+
+fn join_indices(indices: &[uint]) -> String {
+    let strings: Vec<String> = indices.iter().map(|index| index.to_string()).collect();
+    strings.connect(",")
+}
+
+/// A single assignment of the variables and its result
+pub struct Row<'a> {
+    table: &'a TruthTable,
+    index: uint
+}
+
+impl<'a> Row<'a> {
+    pub fn index(&self) -> uint {
+        self.index
+    }
+
+    /// The value of the variable in column `pos`
+    pub fn value(&self, pos: uint) -> bool {
+        let len = self.table.vars.len();
+        (self.index >> (len - 1 - pos)) & 1 == 1
+    }
+
+    /// The value of the variable called `name`, if it is in the table
+    pub fn get(&self, name: &str) -> Option<bool> {
+        self.table.vars.iter().position(|var| var.as_slice() == name).map(|pos| self.value(pos))
+    }
+
+    pub fn inputs(&self) -> Vec<bool> {
+        range(0, self.table.vars.len()).map(|pos| self.value(pos)).collect()
+    }
+
+    pub fn result(&self) -> bool {
+        self.table.results[self.index]
+    }
+}
+
+pub struct Rows<'a> {
+    table: &'a TruthTable,
+    index: uint
+}
+
+impl<'a> Iterator<Row<'a>> for Rows<'a> {
+    fn next(&mut self) -> Option<Row<'a>> {
+        if self.index < self.table.len() {
+            self.index += 1;
+            Some(Row { table: self.table, index: self.index - 1 })
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Traversal of the expression tree.
+//!
+//! Every method of `Visitor` and `Folder` defaults to walking into the
+//! children, so an implementation only overrides the nodes it cares about.
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+
+use expr::{Component, Expr, VarOrExpr};
+use lexer::Token;
+
+/// Read-only traversal, see `walk_expr` for the visiting order
+pub trait Visitor {
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr)
+    }
+
+    fn visit_component(&mut self, component: &Component) {
+        walk_component(self, component)
+    }
+
+    /// Binary operators between the components of an expression
+    fn visit_op(&mut self, _op: &Token) {}
+
+    fn visit_var(&mut self, _name: &str) {}
+
+    fn visit_const(&mut self, _val: bool) {}
+
+    fn visit_ite(&mut self, cond: &Expr, then: &Expr, other: &Expr) {
+        walk_ite(self, cond, then, other)
+    }
+
+    fn visit_call(&mut self, _name: &str, args: &[Expr]) {
+        walk_call(self, args)
+    }
+}
+
+/// Visits the components left to right with the operator joining each
+/// one to the previous in between
+pub fn walk_expr<V: Visitor>(visitor: &mut V, expr: &Expr) {
+    for (idx, component) in expr.components.iter().enumerate() {
+        if idx > 0 {
+            visitor.visit_op(&expr.ops[idx - 1]);
+        }
+        visitor.visit_component(component);
+    }
+}
+
+pub fn walk_component<V: Visitor>(visitor: &mut V, component: &Component) {
+    match component.value {
+        VarOrExpr::Var(ref name) => visitor.visit_var(name.as_slice()),
+        VarOrExpr::Const(val) => visitor.visit_const(val),
+        VarOrExpr::Expr(ref expr) => visitor.visit_expr(expr),
+        VarOrExpr::Ite(ref cond, ref then, ref other) => visitor.visit_ite(&**cond, &**then, &**other),
+        VarOrExpr::Call(ref name, ref args) => visitor.visit_call(name.as_slice(), args.as_slice())
+    }
+}
+
+pub fn walk_ite<V: Visitor>(visitor: &mut V, cond: &Expr, then: &Expr, other: &Expr) {
+    visitor.visit_expr(cond);
+    visitor.visit_expr(then);
+    visitor.visit_expr(other);
+}
+
+/// Visits the arguments of a call in order
+pub fn walk_call<V: Visitor>(visitor: &mut V, args: &[Expr]) {
+    for arg in args.iter() {
+        visitor.visit_expr(arg);
+    }
+}
+
+/// Rebuilding traversal which takes the tree by value
+pub trait Folder {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        noop_fold_expr(self, expr)
+    }
+
+    fn fold_component(&mut self, component: Component) -> Component {
+        noop_fold_component(self, component)
+    }
+
+    fn fold_op(&mut self, op: Token) -> Token {
+        op
+    }
+
+    /// May replace a variable with any value, such as a constant or a sub-expression
+    fn fold_var(&mut self, name: String) -> VarOrExpr {
+        VarOrExpr::Var(name)
+    }
+
+    fn fold_const(&mut self, val: bool) -> VarOrExpr {
+        VarOrExpr::Const(val)
+    }
+
+    fn fold_ite(&mut self, cond: Expr, then: Expr, other: Expr) -> VarOrExpr {
+        let cond = self.fold_expr(cond);
+        let then = self.fold_expr(then);
+        let other = self.fold_expr(other);
+        VarOrExpr::Ite(box cond, box then, box other)
+    }
+
+    fn fold_call(&mut self, name: String, args: Vec<Expr>) -> VarOrExpr {
+        VarOrExpr::Call(name, args.into_iter().map(|arg| self.fold_expr(arg)).collect())
+    }
+}
+
+pub fn noop_fold_expr<F: Folder>(folder: &mut F, expr: Expr) -> Expr {
+    let Expr { components, ops } = expr;
+    Expr {
+        components: components.into_iter().map(|component| folder.fold_component(component)).collect(),
+        ops: ops.into_iter().map(|op| folder.fold_op(op)).collect()
+    }
+}
+
+pub fn noop_fold_component<F: Folder>(folder: &mut F, component: Component) -> Component {
+    let Component { value, negated } = component;
+    let value = match value {
+        VarOrExpr::Var(name) => folder.fold_var(name),
+        VarOrExpr::Const(val) => folder.fold_const(val),
+        VarOrExpr::Expr(expr) => VarOrExpr::Expr(folder.fold_expr(expr)),
+        VarOrExpr::Ite(cond, then, other) => folder.fold_ite(*cond, *then, *other),
+        VarOrExpr::Call(name, args) => folder.fold_call(name, args)
+    };
+    Component { value: value, negated: negated }
+}
@@ -0,0 +1,38 @@
+//! The BDD of each of a set of expressions checked against its truth
+//! table. `cargo test`
+
+extern crate truth;
+
+use truth::Expr;
+
+// Each uses all of `a`, `b` and `c`, so their tables have the same rows
+static EXPRESSIONS: &'static [&'static str] = &[
+    "a & b | c",
+    "a ^ b ^ c",
+    "(a | b) & (!a | c) & (b | !c)",
+    "a & !a & b & c",
+    "a | !a | b & c",
+    "!(a & b) | c",
+    "!a | !b | c",
+    "!(a & b) ^ (b | c)",
+    "a -> (b <-> c)",
+    "(a & b) | (a & c) | (b & c)",
+    "!a & !b & !c",
+    "(a ^ c) & (b -> a)",
+    "a ? b : c",
+    "!(a | b) | !(b | c) ^ a"
+];
+
+#[test]
+fn bdd_agrees_with_the_table() {
+    for src in EXPRESSIONS.iter() {
+        let expr = Expr::parse(*src).unwrap();
+        let table = expr.truth_table().unwrap();
+        let (bdd, f) = expr.to_bdd().unwrap();
+        for row in table.iter_rows() {
+            assert_eq!(bdd.eval(f, row.inputs().as_slice()), row.result());
+        }
+        let count = table.iter_rows().filter(|row| row.result()).count();
+        assert_eq!(bdd.count(f), Some(count as u64));
+    }
+}
@@ -0,0 +1,50 @@
+//! Conversions to CNF checked against the truth tables of a set of
+//! expressions. `cargo test`
+
+extern crate truth;
+
+use truth::{CnfMethod, Expr};
+
+// Each uses all of `a`, `b` and `c`, so their tables have the same rows
+static EXPRESSIONS: &'static [&'static str] = &[
+    "a & b | c",
+    "a ^ b ^ c",
+    "(a | b) & (!a | c) & (b | !c)",
+    "a & !a & b & c",
+    "a | !a | b & c",
+    "!(a & b) | c",
+    "!a | !b | c",
+    "!(a & b) ^ (b | c)",
+    "a -> (b <-> c)",
+    "(a & b) | (a & c) | (b & c)",
+    "!a & !b & !c",
+    "(a ^ c) & (b -> a)",
+    "a ? b : c",
+    "!(a | b) | !(b | c) ^ a"
+];
+
+#[test]
+fn distributed_cnf_is_equivalent() {
+    for src in EXPRESSIONS.iter() {
+        let expr = Expr::parse(*src).unwrap();
+        let cnf = expr.to_cnf().unwrap();
+        assert!(expr.equivalent(&cnf).unwrap().is_none(), "{} has the CNF {}", expr, cnf);
+    }
+}
+
+#[test]
+fn tseitin_is_satisfiable_for_exactly_the_true_rows() {
+    for src in EXPRESSIONS.iter() {
+        let expr = Expr::parse(*src).unwrap();
+        let cnf = expr.cnf(CnfMethod::Tseitin).unwrap().to_expr();
+        let table = expr.truth_table().unwrap();
+        for row in table.iter_rows() {
+            // The clauses with the inputs fixed to those of the row
+            let inputs: Vec<String> = table.variables().iter().zip(row.inputs().iter()).map(|(var, &val)| {
+                if val { var.clone() } else { format!("!{}", var) }
+            }).collect();
+            let fixed = Expr::parse(format!("({}) & {}", cnf, inputs.as_slice().connect(" & ")).as_slice()).unwrap();
+            assert_eq!(fixed.satisfiable().unwrap().is_some(), row.result());
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Minimized forms checked against the truth tables of a set of
+//! expressions. `cargo test`
+
+extern crate truth;
+
+use truth::{Assignment, Expr};
+
+// Each uses all of `a`, `b` and `c`, so their tables have the same rows
+static EXPRESSIONS: &'static [&'static str] = &[
+    "a & b | c",
+    "a ^ b ^ c",
+    "(a | b) & (!a | c) & (b | !c)",
+    "a & !a & b & c",
+    "a | !a | b & c",
+    "!(a & b) | c",
+    "!a | !b | c",
+    "!(a & b) ^ (b | c)",
+    "a -> (b <-> c)",
+    "(a & b) | (a & c) | (b & c)",
+    "!a & !b & !c",
+    "(a ^ c) & (b -> a)",
+    "a ? b : c",
+    "!(a | b) | !(b | c) ^ a"
+];
+
+fn exprs() -> Vec<Expr> {
+    EXPRESSIONS.iter().map(|src| Expr::parse(*src).unwrap()).collect()
+}
+
+// Whether `other` has the value of `expr` in every row of its table,
+// `other` possibly leaving some variables out
+fn agrees(expr: &Expr, other: &Expr) -> bool {
+    let table = expr.truth_table().unwrap();
+    table.iter_rows().all(|row| {
+        let assignment = Assignment::new(table.variables().to_vec(), row.inputs());
+        other.eval(&assignment).unwrap() == row.result()
+    })
+}
+
+#[test]
+fn minimize_is_equivalent() {
+    for expr in exprs().iter() {
+        let minimal = expr.minimize().unwrap();
+        assert!(agrees(expr, &minimal), "{} minimized to {}", expr, minimal);
+    }
+}
+
+#[test]
+fn minimal_forms_are_equivalent() {
+    for expr in exprs().iter() {
+        for form in expr.minimal_forms().unwrap().iter() {
+            assert!(agrees(expr, form), "{} has the minimal form {}", expr, form);
+        }
+    }
+}
+
+#[test]
+fn heuristic_is_equivalent() {
+    for expr in exprs().iter() {
+        let cover = expr.minimize_heuristic().unwrap();
+        assert!(agrees(expr, &cover), "{} minimized to {}", expr, cover);
+    }
+}
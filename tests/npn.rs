@@ -0,0 +1,36 @@
+//! NPN classes of random expressions and their negated and permuted
+//! forms. `cargo test`
+
+extern crate truth;
+
+use std::rand::{SeedableRng, StdRng};
+
+use truth::{npn, Component, Expr, TruthTable, VarOrExpr};
+use truth::arbitrary::Generator;
+
+const EXPRESSIONS: uint = 200;
+
+fn vars() -> Vec<String> {
+    vec!("a".to_string(), "b".to_string(), "c".to_string())
+}
+
+fn table(expr: &Expr) -> TruthTable {
+    expr.truth_table_ordered(vars()).unwrap()
+}
+
+#[test]
+fn npn_transforms_share_a_class() {
+    // A fixed seed, so a failure is found again on the next run
+    let mut rng: StdRng = SeedableRng::from_seed([5u, 8, 13].as_slice());
+    let generator = Generator::new(vars(), 3);
+    let swapped = [Expr::parse("!b").unwrap(), Expr::parse("a").unwrap()];
+    for _ in range(0, EXPRESSIONS) {
+        let expr = generator.generate(&mut rng);
+        // !f(!b, a, c)
+        let inner = expr.substitute_all(&["a".to_string(), "b".to_string()], &swapped);
+        let other = Expr::from_component(Component { value: VarOrExpr::Expr(inner), negated: true });
+        let (f, g) = (table(&expr), table(&other));
+        assert!(npn::equivalent(&f, &g).unwrap(), "{} and {} are in different classes", expr, other);
+        assert_eq!(npn::canonical(&f).unwrap().key(), npn::canonical(&g).unwrap().key());
+    }
+}
@@ -0,0 +1,32 @@
+//! Post's classes of functions whose classes are known. `cargo test`
+
+extern crate truth;
+
+use truth::{post, Expr};
+use truth::post::PostClasses;
+
+fn classify(src: &str) -> PostClasses {
+    post::classify(&Expr::parse(src).unwrap().truth_table().unwrap())
+}
+
+#[test]
+fn post_classes_of_known_functions() {
+    let and = classify("a & b");
+    assert!(and.monotone && !and.linear && !and.self_dual && and.preserves_false && and.preserves_true);
+
+    let xor = classify("a ^ b");
+    assert!(!xor.monotone && xor.linear && !xor.self_dual && xor.preserves_false && !xor.preserves_true);
+
+    let majority = classify("a & b | a & c | b & c");
+    assert!(majority.monotone && !majority.linear && majority.self_dual);
+
+    let not = classify("!a");
+    assert!(not.linear && not.self_dual && !not.preserves_false && !not.preserves_true);
+}
+
+#[test]
+fn functional_completeness() {
+    assert!(post::is_complete(&[classify("!(a & b)")]));
+    assert!(post::is_complete(&[classify("!a"), classify("a & b")]));
+    assert!(!post::is_complete(&[classify("a & b"), classify("a ^ b")]));
+}
@@ -0,0 +1,47 @@
+//! Error recovery in the parser, each problem reported once. `cargo test`
+
+extern crate truth;
+
+use std::io::MemReader;
+
+use truth::{Expr, Lexer, Parser, TruthError};
+
+fn errors(src: &str) -> uint {
+    match Expr::parse_all(src) {
+        Ok(_) => 0,
+        Err(errs) => errs.len()
+    }
+}
+
+#[test]
+fn a_stray_token_is_reported_once() {
+    assert_eq!(errors("a & )"), 1);
+    assert_eq!(errors("(a |) & b"), 1);
+}
+
+#[test]
+fn a_missing_colon_is_reported_once() {
+    assert_eq!(errors("a ? b"), 1);
+}
+
+#[test]
+fn every_problem_is_reported() {
+    assert_eq!(errors("(a & ) | (b | )"), 2);
+    assert_eq!(errors("(a & ) | b $"), 2);
+    assert_eq!(errors("a & b"), 0);
+}
+
+#[test]
+fn bad_bytes_in_a_stream_are_reported() {
+    let input = MemReader::new(b"a & b \xff| c".to_vec());
+    match Parser::parse_recovering(&mut Lexer::from_buffer(input)) {
+        Err(errs) => {
+            assert_eq!(errs.len(), 1);
+            match errs[0] {
+                TruthError::Lex(_, ref span) => assert_eq!(span.byte_range, Some((6, 7))),
+                ref err => panic!("not a lex error: {}", err)
+            }
+        },
+        Ok(expr) => panic!("parsed past bad bytes as {}", expr)
+    }
+}
@@ -0,0 +1,44 @@
+//! Random expressions from `truth::arbitrary` printed in the native style
+//! and parsed back. `cargo test`
+
+extern crate truth;
+
+use std::rand::{Rng, SeedableRng, StdRng};
+
+use truth::Expr;
+use truth::arbitrary::Generator;
+
+const EXPRESSIONS: uint = 500;
+
+// A fixed seed, so a failure is found again on the next run
+fn rng() -> StdRng {
+    SeedableRng::from_seed([1u, 2, 3, 4].as_slice())
+}
+
+fn results(expr: &Expr) -> Vec<bool> {
+    expr.truth_table().unwrap().iter_rows().map(|row| row.result()).collect()
+}
+
+#[test]
+fn reparses_to_the_same_form() {
+    let mut rng = rng();
+    for _ in range(0, EXPRESSIONS) {
+        let expr: Expr = rng.gen();
+        let printed = expr.to_string();
+        let reparsed = Expr::parse(printed.as_slice()).unwrap();
+        assert_eq!(reparsed.to_string(), printed);
+    }
+}
+
+#[test]
+fn reparses_to_the_same_table() {
+    let mut rng = rng();
+    let vars: Vec<String> = range(0, 6u).map(|idx| format!("v{}", idx)).collect();
+    let generator = Generator::new(vars, 5).width(4);
+    for _ in range(0, EXPRESSIONS) {
+        let expr = generator.generate(&mut rng);
+        let reparsed = Expr::parse(expr.to_string().as_slice()).unwrap();
+        assert_eq!(reparsed.get_variables(), expr.get_variables());
+        assert_eq!(results(&reparsed), results(&expr));
+    }
+}
@@ -0,0 +1,49 @@
+//! The SAT solver checked against the truth tables of a set of
+//! expressions. `cargo test`
+
+extern crate truth;
+
+use truth::Expr;
+
+// Each uses all of `a`, `b` and `c`, so their tables have the same rows
+static EXPRESSIONS: &'static [&'static str] = &[
+    "a & b | c",
+    "a ^ b ^ c",
+    "(a | b) & (!a | c) & (b | !c)",
+    "a & !a & b & c",
+    "a | !a | b & c",
+    "!(a & b) | c",
+    "!a | !b | c",
+    "!(a & b) ^ (b | c)",
+    "a -> (b <-> c)",
+    "(a & b) | (a & c) | (b & c)",
+    "!a & !b & !c",
+    "(a ^ c) & (b -> a)",
+    "a ? b : c",
+    "!(a | b) | !(b | c) ^ a"
+];
+
+fn results(expr: &Expr) -> Vec<bool> {
+    expr.truth_table().unwrap().iter_rows().map(|row| row.result()).collect()
+}
+
+#[test]
+fn sat_finds_a_model_exactly_when_there_is_one() {
+    for src in EXPRESSIONS.iter() {
+        let expr = Expr::parse(*src).unwrap();
+        match expr.satisfiable().unwrap() {
+            Some(model) => assert!(expr.eval(&model).unwrap(), "{} is false for its model", expr),
+            None => assert!(!results(&expr).iter().any(|&result| result), "no model found for {}", expr)
+        }
+    }
+}
+
+#[test]
+fn sat_equivalence_agrees_with_tables() {
+    let exprs: Vec<Expr> = EXPRESSIONS.iter().map(|src| Expr::parse(*src).unwrap()).collect();
+    for left in exprs.iter() {
+        for right in exprs.iter() {
+            assert_eq!(left.equivalent(right).unwrap().is_none(), results(left) == results(right));
+        }
+    }
+}